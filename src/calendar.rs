@@ -0,0 +1,344 @@
+//! Per-day habit states and the `habit calendar` heatmap.
+//!
+//! `day_mark` is the single place that decides how a day looks; `habit
+//! report` lays the same states out as weekly tables.
+
+use crate::completion::{CompletionIndex, DayOutcome};
+use crate::date::{add_days, date_range_inclusive, day_of_month, iso_week_start};
+use crate::error::CliError;
+use crate::habits::is_scheduled_on;
+use crate::model::{Habit, HabitKind};
+use crate::output::Styler;
+use std::collections::BTreeMap;
+
+const WEEKDAY_LABELS: [&str; 7] = ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"];
+const MONTH_LABELS: [&str; 12] = [
+    "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+];
+
+/// How one calendar day looks for a habit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DayMark {
+    /// Target met (goal reached, limit respected, or a counted check-in
+    /// toward a week/month target).
+    Met,
+    /// Some check-ins, target not reached.
+    Partial,
+    /// Scheduled and not met (or a limit exceeded).
+    Missed,
+    /// Not met, covered by an allowed excuse.
+    Excused,
+    /// Check-ins that don't count because the day has no declaration.
+    Undeclared,
+    /// Not scheduled (week/month habits: no check-in that day).
+    Unscheduled,
+    /// Outside the range or the habit's lifetime, or in the future.
+    Outside,
+}
+
+impl DayMark {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            DayMark::Met => "met",
+            DayMark::Partial => "partial",
+            DayMark::Missed => "missed",
+            DayMark::Excused => "excused",
+            DayMark::Undeclared => "undeclared",
+            DayMark::Unscheduled => "unscheduled",
+            DayMark::Outside => "outside",
+        }
+    }
+
+    pub fn glyph(self) -> &'static str {
+        match self {
+            DayMark::Met => "█",
+            DayMark::Partial => "▒",
+            DayMark::Missed => "░",
+            DayMark::Excused => "E",
+            DayMark::Undeclared => "?",
+            DayMark::Unscheduled => "·",
+            DayMark::Outside => " ",
+        }
+    }
+
+    fn styled(self, styler: &Styler) -> String {
+        match self {
+            DayMark::Met | DayMark::Partial => styler.green(self.glyph()),
+            DayMark::Excused => styler.yellow(self.glyph()),
+            DayMark::Undeclared => styler.red(self.glyph()),
+            DayMark::Missed | DayMark::Unscheduled => styler.gray(self.glyph()),
+            DayMark::Outside => self.glyph().to_string(),
+        }
+    }
+}
+
+pub const LEGEND: [(DayMark, &str); 6] = [
+    (DayMark::Met, "met"),
+    (DayMark::Partial, "partial"),
+    (DayMark::Missed, "missed"),
+    (DayMark::Excused, "excused"),
+    (DayMark::Undeclared, "undeclared"),
+    (DayMark::Unscheduled, "not scheduled"),
+];
+
+/// Marks one day of `habit`'s calendar.
+pub fn day_mark(idx: &CompletionIndex, habit: &Habit, date: &str, today: &str) -> DayMark {
+    let alive = date >= habit.created_date.as_str()
+        && habit.archived_date.as_deref().is_none_or(|a| date <= a);
+    if !alive || date > today {
+        return DayMark::Outside;
+    }
+
+    let goal = habit.kind == HabitKind::Goal;
+    let raw = idx.raw_quantity(&habit.id, date);
+    let undeclared = goal && raw > 0 && !idx.is_declared(habit, date);
+
+    if habit.target.period != "day" {
+        return if goal && idx.counted_quantity(habit, date) > 0 {
+            DayMark::Met
+        } else if undeclared {
+            DayMark::Undeclared
+        } else {
+            DayMark::Unscheduled
+        };
+    }
+    if !is_scheduled_on(habit, date).unwrap_or(false) {
+        return DayMark::Unscheduled;
+    }
+    match idx.day_outcome(habit, date) {
+        DayOutcome::Met => DayMark::Met,
+        DayOutcome::Excused => DayMark::Excused,
+        DayOutcome::Missed if undeclared => DayMark::Undeclared,
+        DayOutcome::Missed if goal && raw > 0 => DayMark::Partial,
+        DayOutcome::Missed => DayMark::Missed,
+    }
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct CalendarDay {
+    pub date: String,
+    pub state: DayMark,
+    /// Raw per-day total (declared or not).
+    pub quantity: u32,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct Calendar {
+    pub habit_id: String,
+    pub name: String,
+    pub from: String,
+    pub to: String,
+    /// Days per state, `outside` excluded.
+    pub counts: BTreeMap<String, u32>,
+    pub days: Vec<CalendarDay>,
+}
+
+pub fn build_calendar(
+    idx: &CompletionIndex,
+    habit: &Habit,
+    from: &str,
+    to: &str,
+    today: &str,
+) -> Result<Calendar, CliError> {
+    let mut counts: BTreeMap<String, u32> = LEGEND
+        .iter()
+        .map(|(m, _)| (m.as_str().to_string(), 0))
+        .collect();
+
+    let mut days: Vec<CalendarDay> = Vec::new();
+    for date in date_range_inclusive(from, to)? {
+        let state = day_mark(idx, habit, &date, today);
+        if state != DayMark::Outside {
+            *counts.entry(state.as_str().to_string()).or_insert(0) += 1;
+        }
+        days.push(CalendarDay {
+            quantity: idx.raw_quantity(&habit.id, &date),
+            date,
+            state,
+        });
+    }
+
+    Ok(Calendar {
+        habit_id: habit.id.clone(),
+        name: habit.name.clone(),
+        from: from.to_string(),
+        to: to.to_string(),
+        counts,
+        days,
+    })
+}
+
+/// GitHub-style heatmap: one row per weekday, one column per ISO week, with
+/// month names above the week in which each month starts.
+pub fn render_heatmap(cal: &Calendar, styler: &Styler) -> Result<String, CliError> {
+    let first_week = iso_week_start(&cal.from)?;
+    let lead = crate::date::days_between(&first_week, &cal.from)? as usize;
+    let weeks = (lead + cal.days.len()).div_ceil(7);
+
+    let mut cells: Vec<Vec<DayMark>> = vec![vec![DayMark::Outside; weeks]; 7];
+    for (i, day) in cal.days.iter().enumerate() {
+        let slot = lead + i;
+        cells[slot % 7][slot / 7] = day.state;
+    }
+
+    // month labels, skipped where they would overlap the previous one
+    let mut header: Vec<char> = vec![' '; weeks + 3];
+    let mut free_from = 0;
+    for w in 0..weeks {
+        for d in 0..7 {
+            let Ok(date) = add_days(&first_week, (w * 7 + d) as i32) else {
+                continue;
+            };
+            let in_range = date.as_str() >= cal.from.as_str() && date.as_str() <= cal.to.as_str();
+            let starts_month = day_of_month(&date).map(|(n, _)| n == 1).unwrap_or(false);
+            if in_range && (starts_month || date == cal.from) && w >= free_from {
+                let month: usize = date[5..7].parse().unwrap_or(1);
+                for (k, ch) in MONTH_LABELS[month - 1].chars().enumerate() {
+                    header[w + k] = ch;
+                }
+                free_from = w + 4;
+                break;
+            }
+        }
+    }
+
+    let mut lines: Vec<String> = Vec::new();
+    lines.push(format!(
+        "{} ({}) {} to {}",
+        cal.name, cal.habit_id, cal.from, cal.to
+    ));
+    lines.push(format!(
+        "    {}",
+        header.iter().collect::<String>().trim_end()
+    ));
+    for (d, row) in cells.iter().enumerate() {
+        let body: String = row.iter().map(|m| m.styled(styler)).collect();
+        lines.push(
+            format!("{} {}", WEEKDAY_LABELS[d], body)
+                .trim_end()
+                .to_string(),
+        );
+    }
+    lines.push(String::new());
+
+    let counts: Vec<String> = LEGEND
+        .iter()
+        .map(|(m, label)| {
+            let n = cal.counts.get(m.as_str()).copied().unwrap_or(0);
+            format!("{} {} {}", m.styled(styler), label, n)
+        })
+        .collect();
+    lines.push(counts.join("  "));
+    Ok(lines.join("\n"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::checkins::{add_quantity, EventInfo};
+    use crate::declarations::declare;
+    use crate::habits::make_habit;
+    use crate::model::{default_db, ExcuseKind, ExcusePolicy};
+
+    fn info() -> EventInfo {
+        EventInfo {
+            ts: "2026-01-05T08:00:00Z".to_string(),
+            note: None,
+            source: "cli".to_string(),
+        }
+    }
+
+    #[test]
+    fn day_marks_follow_completion_excuses_and_declarations() {
+        let mut db = default_db();
+        let mut habit = make_habit(
+            "h0001".to_string(),
+            "Water",
+            "weekdays",
+            "day",
+            2,
+            None,
+            "2026-01-05",
+            true,
+            2,
+            HabitKind::Goal,
+        )
+        .unwrap();
+        db.habits.push(habit.clone());
+        for (date, qty) in [("2026-01-05", 2), ("2026-01-06", 1), ("2026-01-09", 2)] {
+            add_quantity(&mut db, "h0001", date, qty, &info()).unwrap();
+        }
+        for date in ["2026-01-05", "2026-01-06"] {
+            declare(&mut db, "h0001", date, "2026-01-05T07:00:00Z", "yes").unwrap();
+        }
+        crate::excuses::excuse(
+            &mut db,
+            "h0001",
+            "2026-01-08",
+            "2026-01-08T20:00:00Z",
+            ExcuseKind::Allowed,
+            "sick",
+            2,
+        )
+        .unwrap();
+
+        let idx = CompletionIndex::new(&db);
+        let marks: Vec<DayMark> = date_range_inclusive("2026-01-04", "2026-01-11")
+            .unwrap()
+            .iter()
+            .map(|d| day_mark(&idx, &habit, d, "2026-01-10"))
+            .collect();
+        assert_eq!(
+            marks,
+            vec![
+                DayMark::Outside,     // before created_date
+                DayMark::Met,         // declared, 2/2
+                DayMark::Partial,     // declared, 1/2
+                DayMark::Missed,      // nothing
+                DayMark::Excused,     // allowed excuse
+                DayMark::Undeclared,  // 2/2 without a declaration
+                DayMark::Unscheduled, // Saturday
+                DayMark::Outside,     // after today
+            ]
+        );
+
+        // habits that ignore excuses show the day as missed
+        habit.excuse_policy = ExcusePolicy::Ignore;
+        assert_eq!(
+            day_mark(&idx, &habit, "2026-01-08", "2026-01-10"),
+            DayMark::Missed
+        );
+    }
+
+    #[test]
+    fn heatmap_places_days_by_weekday() {
+        let mut db = default_db();
+        let habit = make_habit(
+            "h0001".to_string(),
+            "Read",
+            "everyday",
+            "day",
+            1,
+            None,
+            "2026-01-01",
+            false,
+            2,
+            HabitKind::Goal,
+        )
+        .unwrap();
+        add_quantity(&mut db, "h0001", "2026-01-01", 1, &info()).unwrap();
+        let idx = CompletionIndex::new(&db);
+
+        let cal = build_calendar(&idx, &habit, "2026-01-01", "2026-01-07", "2026-01-07").unwrap();
+        assert_eq!(cal.counts["met"], 1);
+        assert_eq!(cal.counts["missed"], 6);
+
+        let text = render_heatmap(&cal, &Styler::new(false)).unwrap();
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines[1], "    Jan");
+        assert_eq!(lines[2], "Mon  ░");
+        assert_eq!(lines[5], "Thu █");
+        assert_eq!(lines[8], "Sun ░");
+    }
+}
@@ -1,79 +1,174 @@
 use crate::date::parse_date_string;
 use crate::error::CliError;
-use crate::model::{Checkin, Db};
-use std::collections::BTreeSet;
+use crate::model::{Checkin, CheckinEvent, CheckinEventKind, Db};
+use crate::ts::validate_rfc3339;
+use std::collections::{BTreeMap, BTreeSet};
 
-fn find_checkin_index(db: &Db, habit_id: &str, date: &str) -> Option<usize> {
-    db.checkins
-        .iter()
-        .position(|c| c.habit_id == habit_id && c.date == date)
+/// Where/when a check-in event was recorded.
+#[derive(Debug, Clone)]
+pub struct EventInfo {
+    pub ts: String,
+    pub note: Option<String>,
+    pub source: String,
 }
 
-pub fn get_quantity(db: &Db, habit_id: &str, date: &str) -> u32 {
-    match find_checkin_index(db, habit_id, date) {
-        Some(i) => db.checkins[i].quantity,
-        None => 0,
+impl EventInfo {
+    fn validate(&self) -> Result<(), CliError> {
+        validate_rfc3339(&self.ts, "ts")?;
+        if self.source.trim().is_empty() {
+            return Err(CliError::usage("Invalid source: (empty)"));
+        }
+        Ok(())
     }
 }
 
+pub fn next_checkin_event_id(db: &mut Db) -> String {
+    let n = db.meta.next_checkin_number;
+    let id = format!("c{:06}", n);
+    db.meta.next_checkin_number = n + 1;
+    id
+}
+
+fn clamp_total(sum: i64) -> u32 {
+    u32::try_from(sum.max(0)).unwrap_or(u32::MAX)
+}
+
+/// Per-day total derived from the event log.
+pub fn get_quantity(db: &Db, habit_id: &str, date: &str) -> u32 {
+    let sum: i64 = db
+        .checkin_events
+        .iter()
+        .filter(|e| e.habit_id == habit_id && e.date == date)
+        .map(|e| e.delta)
+        .sum();
+    clamp_total(sum)
+}
+
+fn append_event(
+    db: &mut Db,
+    habit_id: &str,
+    date: &str,
+    kind: CheckinEventKind,
+    delta: i64,
+    info: &EventInfo,
+) -> CheckinEvent {
+    let id = next_checkin_event_id(db);
+    let ev = CheckinEvent {
+        id,
+        habit_id: habit_id.to_string(),
+        date: date.to_string(),
+        ts: info.ts.trim().to_string(),
+        kind,
+        delta,
+        note: info
+            .note
+            .as_deref()
+            .map(|n| n.trim())
+            .filter(|n| !n.is_empty())
+            .map(|n| n.to_string()),
+        source: info.source.trim().to_string(),
+    };
+    db.checkin_events.push(ev.clone());
+    ev
+}
+
+/// Records a compensating `set` (or `delete` when `quantity == 0`) event that
+/// brings the day's total to `quantity`; `None` when it already is.
 pub fn set_quantity(
     db: &mut Db,
     habit_id: &str,
     date: &str,
     quantity: u32,
-) -> Result<(), CliError> {
+    info: &EventInfo,
+) -> Result<Option<CheckinEvent>, CliError> {
     parse_date_string(date, "date")?;
+    info.validate()?;
 
-    let idx = find_checkin_index(db, habit_id, date);
-
-    if quantity == 0 {
-        if let Some(i) = idx {
-            db.checkins.remove(i);
-        }
-        return Ok(());
-    }
-
-    match idx {
-        None => db.checkins.push(Checkin {
-            habit_id: habit_id.to_string(),
-            date: date.to_string(),
-            quantity,
-        }),
-        Some(i) => db.checkins[i].quantity = quantity,
+    let cur = get_quantity(db, habit_id, date);
+    if cur == quantity {
+        return Ok(None);
     }
-
-    Ok(())
+    let kind = if quantity == 0 {
+        CheckinEventKind::Delete
+    } else {
+        CheckinEventKind::Set
+    };
+    let delta = i64::from(quantity) - i64::from(cur);
+    Ok(Some(append_event(db, habit_id, date, kind, delta, info)))
 }
 
-pub fn add_quantity(db: &mut Db, habit_id: &str, date: &str, delta: u32) -> Result<u32, CliError> {
+pub fn add_quantity(
+    db: &mut Db,
+    habit_id: &str,
+    date: &str,
+    delta: u32,
+    info: &EventInfo,
+) -> Result<(u32, CheckinEvent), CliError> {
     parse_date_string(date, "date")?;
     if delta < 1 {
         return Err(CliError::usage("Invalid quantity"));
     }
+    info.validate()?;
 
     let cur = get_quantity(db, habit_id, date);
     let total = cur.saturating_add(delta);
-    set_quantity(db, habit_id, date, total)?;
-    Ok(total)
+    let ev = append_event(
+        db,
+        habit_id,
+        date,
+        CheckinEventKind::Add,
+        i64::from(total - cur),
+        info,
+    );
+    Ok((total, ev))
 }
 
-pub fn list_checkins_for_habit(db: &Db, habit_id: &str) -> Vec<Checkin> {
-    let mut out: Vec<Checkin> = db
-        .checkins
-        .iter()
-        .filter(|c| c.habit_id == habit_id)
-        .cloned()
-        .collect();
+/// Folds matching events into per-day totals (days netting to 0 are dropped),
+/// sorted by date then habit id.
+fn totals<'a>(events: impl Iterator<Item = &'a CheckinEvent>) -> Vec<Checkin> {
+    let mut sums: BTreeMap<(String, String), i64> = BTreeMap::new();
+    for e in events {
+        *sums
+            .entry((e.date.clone(), e.habit_id.clone()))
+            .or_insert(0) += e.delta;
+    }
 
-    out.sort_by(|a, b| {
-        if a.date != b.date {
-            a.date.cmp(&b.date)
-        } else {
-            a.habit_id.cmp(&b.habit_id)
+    sums.into_iter()
+        .map(|((date, habit_id), sum)| Checkin {
+            habit_id,
+            date,
+            quantity: clamp_total(sum),
+        })
+        .filter(|c| c.quantity > 0)
+        .collect()
+}
+
+fn event_in_range(
+    e: &CheckinEvent,
+    from: Option<&str>,
+    to: Option<&str>,
+    habit_ids: Option<&BTreeSet<String>>,
+) -> bool {
+    if let Some(ids) = habit_ids {
+        if !ids.contains(&e.habit_id) {
+            return false;
+        }
+    }
+    if let Some(f) = from {
+        if e.date.as_str() < f {
+            return false;
         }
-    });
+    }
+    if let Some(t) = to {
+        if e.date.as_str() > t {
+            return false;
+        }
+    }
+    true
+}
 
-    out
+pub fn list_checkins_for_habit(db: &Db, habit_id: &str) -> Vec<Checkin> {
+    totals(db.checkin_events.iter().filter(|e| e.habit_id == habit_id))
 }
 
 pub fn list_checkins_in_range(
@@ -82,37 +177,81 @@ pub fn list_checkins_in_range(
     to: Option<&str>,
     habit_ids: Option<&BTreeSet<String>>,
 ) -> Vec<Checkin> {
-    let mut out: Vec<Checkin> = db
-        .checkins
+    totals(
+        db.checkin_events
+            .iter()
+            .filter(|e| event_in_range(e, from, to, habit_ids)),
+    )
+}
+
+/// Raw events (in recording order) for the given filters.
+pub fn list_events_in_range(
+    db: &Db,
+    from: Option<&str>,
+    to: Option<&str>,
+    habit_ids: Option<&BTreeSet<String>>,
+) -> Vec<CheckinEvent> {
+    db.checkin_events
         .iter()
-        .filter(|c| {
-            if let Some(ids) = habit_ids {
-                if !ids.contains(&c.habit_id) {
-                    return false;
-                }
-            }
-            if let Some(f) = from {
-                if c.date.as_str() < f {
-                    return false;
-                }
-            }
-            if let Some(t) = to {
-                if c.date.as_str() > t {
-                    return false;
-                }
-            }
-            true
-        })
+        .filter(|e| event_in_range(e, from, to, habit_ids))
         .cloned()
-        .collect();
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::default_db;
 
-    out.sort_by(|a, b| {
-        if a.date != b.date {
-            a.date.cmp(&b.date)
-        } else {
-            a.habit_id.cmp(&b.habit_id)
+    fn info(ts: &str) -> EventInfo {
+        EventInfo {
+            ts: ts.to_string(),
+            note: None,
+            source: "cli".to_string(),
         }
-    });
+    }
+
+    #[test]
+    fn totals_are_derived_from_events() {
+        let mut db = default_db();
+        let i = info("2026-01-01T08:00:00Z");
+        assert_eq!(
+            add_quantity(&mut db, "h0001", "2026-01-01", 2, &i)
+                .unwrap()
+                .0,
+            2
+        );
+        assert_eq!(
+            add_quantity(&mut db, "h0001", "2026-01-01", 3, &i)
+                .unwrap()
+                .0,
+            5
+        );
+        set_quantity(&mut db, "h0001", "2026-01-01", 1, &i).unwrap();
+        assert_eq!(get_quantity(&db, "h0001", "2026-01-01"), 1);
+
+        // compensating events keep the full history
+        let deltas: Vec<i64> = db.checkin_events.iter().map(|e| e.delta).collect();
+        assert_eq!(deltas, vec![2, 3, -4]);
+        assert_eq!(db.checkin_events[2].kind, CheckinEventKind::Set);
+
+        set_quantity(&mut db, "h0001", "2026-01-01", 0, &i).unwrap();
+        assert_eq!(get_quantity(&db, "h0001", "2026-01-01"), 0);
+        assert_eq!(db.checkin_events[3].kind, CheckinEventKind::Delete);
+        assert!(list_checkins_for_habit(&db, "h0001").is_empty());
+        assert_eq!(db.meta.next_checkin_number, 5);
 
-    out
+        // setting the current value again appends nothing
+        assert!(set_quantity(&mut db, "h0001", "2026-01-01", 0, &i)
+            .unwrap()
+            .is_none());
+        assert_eq!(db.checkin_events.len(), 4);
+    }
+
+    #[test]
+    fn rejects_bad_ts() {
+        let mut db = default_db();
+        assert!(add_quantity(&mut db, "h0001", "2026-01-01", 1, &info("nope")).is_err());
+        assert!(db.checkin_events.is_empty());
+    }
 }
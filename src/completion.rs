@@ -1,20 +1,346 @@
-use crate::checkins::get_quantity;
-use crate::declarations::has_declaration;
-use crate::model::{Db, Habit};
+//! Completion semantics shared by every report (status, due, stats, recap,
+//! penalty tick).
+//!
+//! Reports build one `CompletionIndex` per command; lookups are then keyed by
+//! habit and date instead of rescanning the check-in event log and the
+//! declarations for every day they ask about.
 
-pub fn is_declared(db: &Db, habit: &Habit, date: &str) -> bool {
-    if !habit.needs_declaration {
-        return true;
+use crate::model::{Db, ExcuseKind, ExcusePolicy, Habit, HabitKind};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::ops::Bound;
+
+/// Per-habit day totals and declaration dates.
+#[derive(Debug, Default)]
+pub struct CompletionIndex {
+    /// habit_id -> date -> derived total (days with a zero total are absent).
+    quantities: HashMap<String, BTreeMap<String, u32>>,
+    /// habit_id -> dates with at least one declaration.
+    declared: HashMap<String, BTreeSet<String>>,
+    /// habit_id -> dates with an allowed excuse.
+    excused: HashMap<String, BTreeSet<String>>,
+}
+
+/// A scheduled day of a day-period habit, after the habit's excuse policy.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DayOutcome {
+    Met,
+    Missed,
+    /// Unmet, with an allowed excuse the policy honors.
+    Excused,
+}
+
+/// How a scheduled day of a day-period habit counts in streaks, success
+/// rates and strength, once the excuse policy is applied.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DayCredit {
+    /// Met, or excused under `count`.
+    Credited {
+        excused: bool,
+    },
+    /// Excused under `freeze`: neither counts for nor against the habit.
+    Skipped,
+    Missed,
+}
+
+impl DayCredit {
+    /// `Some(met)` for days that count, `None` for skipped ones.
+    pub fn counted(self) -> Option<bool> {
+        match self {
+            DayCredit::Credited { .. } => Some(true),
+            DayCredit::Missed => Some(false),
+            DayCredit::Skipped => None,
+        }
+    }
+
+    /// Whether an allowed excuse covered the day (whatever the policy made of it).
+    pub fn excused(self) -> bool {
+        matches!(
+            self,
+            DayCredit::Credited { excused: true } | DayCredit::Skipped
+        )
+    }
+}
+
+impl CompletionIndex {
+    pub fn new(db: &Db) -> Self {
+        // Group by habit first; the log is append-only, so each habit's
+        // events are nearly date-ordered already and sort cheaply.
+        let mut by_habit: HashMap<&str, Vec<(&str, i64)>> = HashMap::new();
+        for e in db.checkin_events.iter() {
+            by_habit
+                .entry(e.habit_id.as_str())
+                .or_default()
+                .push((e.date.as_str(), e.delta));
+        }
+
+        let mut quantities: HashMap<String, BTreeMap<String, u32>> = HashMap::new();
+        for (habit_id, mut events) in by_habit {
+            events.sort_by(|a, b| a.0.cmp(b.0));
+            let mut totals: Vec<(String, u32)> = Vec::new();
+            for group in events.chunk_by(|a, b| a.0 == b.0) {
+                let sum: i64 = group.iter().map(|(_, delta)| delta).sum();
+                if sum > 0 {
+                    totals.push((
+                        group[0].0.to_string(),
+                        u32::try_from(sum).unwrap_or(u32::MAX),
+                    ));
+                }
+            }
+            if !totals.is_empty() {
+                quantities.insert(habit_id.to_string(), totals.into_iter().collect());
+            }
+        }
+
+        let mut declared: HashMap<String, BTreeSet<String>> = HashMap::new();
+        for d in db.declarations.iter() {
+            declared
+                .entry(d.habit_id.clone())
+                .or_default()
+                .insert(d.date.clone());
+        }
+
+        let mut excused: HashMap<String, BTreeSet<String>> = HashMap::new();
+        for e in db.excuses.iter().filter(|e| e.kind == ExcuseKind::Allowed) {
+            excused
+                .entry(e.habit_id.clone())
+                .or_default()
+                .insert(e.date.clone());
+        }
+
+        CompletionIndex {
+            quantities,
+            declared,
+            excused,
+        }
+    }
+
+    /// Raw per-day total, regardless of declarations.
+    pub fn raw_quantity(&self, habit_id: &str, date: &str) -> u32 {
+        self.quantities
+            .get(habit_id)
+            .and_then(|days| days.get(date))
+            .copied()
+            .unwrap_or(0)
+    }
+
+    fn has_declaration(&self, habit_id: &str, date: &str) -> bool {
+        self.declared
+            .get(habit_id)
+            .is_some_and(|dates| dates.contains(date))
+    }
+
+    pub fn is_declared(&self, habit: &Habit, date: &str) -> bool {
+        if !habit.needs_declaration {
+            return true;
+        }
+        self.has_declaration(&habit.id, date)
+    }
+
+    /// Whether `counted_quantity` drops undeclared days for this habit.
+    fn counts_declared_only(habit: &Habit) -> bool {
+        habit.kind == HabitKind::Goal && habit.needs_declaration
+    }
+
+    /// Quantity that counts toward completion semantics.
+    ///
+    /// Limit habits always count the raw quantity: a missing declaration must not
+    /// hide consumption from the ceiling.
+    pub fn counted_quantity(&self, habit: &Habit, date: &str) -> u32 {
+        if Self::counts_declared_only(habit) && !self.has_declaration(&habit.id, date) {
+            0
+        } else {
+            self.raw_quantity(&habit.id, date)
+        }
+    }
+
+    /// Whether a habit met its target on a single day (day-period semantics).
+    pub fn day_met(&self, habit: &Habit, date: &str) -> bool {
+        let counted = self.counted_quantity(habit, date);
+        match habit.kind {
+            HabitKind::Goal => self.is_declared(habit, date) && meets_target(habit, counted),
+            HabitKind::Limit => meets_target(habit, counted),
+        }
+    }
+
+    pub fn has_allowed_excuse(&self, habit_id: &str, date: &str) -> bool {
+        self.excused
+            .get(habit_id)
+            .is_some_and(|dates| dates.contains(date))
+    }
+
+    /// `day_met` with allowed excuses applied per the habit's `excuse_policy`.
+    ///
+    /// `Excused` is returned for both `freeze` and `count`; callers decide
+    /// whether the day is skipped or credited.
+    pub fn day_outcome(&self, habit: &Habit, date: &str) -> DayOutcome {
+        if self.day_met(habit, date) {
+            DayOutcome::Met
+        } else if habit.excuse_policy != ExcusePolicy::Ignore
+            && self.has_allowed_excuse(&habit.id, date)
+        {
+            DayOutcome::Excused
+        } else {
+            DayOutcome::Missed
+        }
+    }
+
+    /// `day_outcome`, with excused days credited (`count`) or skipped (`freeze`).
+    pub fn day_credit(&self, habit: &Habit, date: &str) -> DayCredit {
+        match self.day_outcome(habit, date) {
+            DayOutcome::Met => DayCredit::Credited { excused: false },
+            DayOutcome::Missed => DayCredit::Missed,
+            DayOutcome::Excused if habit.excuse_policy == ExcusePolicy::Count => {
+                DayCredit::Credited { excused: true }
+            }
+            DayOutcome::Excused => DayCredit::Skipped,
+        }
+    }
+
+    /// Sum of (raw, counted) quantities over `from..=to`.
+    ///
+    /// Only days with check-ins are visited, so the cost does not grow with
+    /// the length of the range.
+    pub fn range_sums(&self, habit: &Habit, from: &str, to: &str) -> (u32, u32) {
+        let days = match self.quantities.get(&habit.id) {
+            Some(days) if from <= to => days,
+            _ => return (0, 0),
+        };
+        let declared_only = Self::counts_declared_only(habit);
+
+        let mut raw_sum = 0u32;
+        let mut counted_sum = 0u32;
+        for (date, qty) in days.range::<str, _>((Bound::Included(from), Bound::Included(to))) {
+            raw_sum = raw_sum.saturating_add(*qty);
+            if !declared_only || self.has_declaration(&habit.id, date) {
+                counted_sum = counted_sum.saturating_add(*qty);
+            }
+        }
+        (raw_sum, counted_sum)
     }
-    has_declaration(db, &habit.id, date)
 }
 
-/// Quantity that counts toward completion semantics.
-pub fn counted_quantity(db: &Db, habit: &Habit, date: &str) -> u32 {
-    let raw = get_quantity(db, &habit.id, date);
-    if habit.needs_declaration && !has_declaration(db, &habit.id, date) {
-        0
-    } else {
-        raw
+/// Whether a (counted) quantity satisfies the habit's target: at least the
+/// target for goals, at most the target for limits.
+pub fn meets_target(habit: &Habit, quantity: u32) -> bool {
+    match habit.kind {
+        HabitKind::Goal => quantity >= habit.target.quantity,
+        HabitKind::Limit => quantity <= habit.target.quantity,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::checkins::{add_quantity, get_quantity, set_quantity, EventInfo};
+    use crate::declarations::declare;
+    use crate::habits::make_habit;
+    use crate::model::default_db;
+
+    #[test]
+    fn index_matches_event_log_and_declarations() {
+        let mut db = default_db();
+        let habit = make_habit(
+            "h0001".to_string(),
+            "Water",
+            "everyday",
+            "day",
+            3,
+            None,
+            "2026-01-01",
+            true,
+            0,
+            HabitKind::Goal,
+        )
+        .unwrap();
+        let info = EventInfo {
+            ts: "2026-01-01T08:00:00Z".to_string(),
+            note: None,
+            source: "cli".to_string(),
+        };
+        add_quantity(&mut db, "h0001", "2026-01-01", 3, &info).unwrap();
+        add_quantity(&mut db, "h0001", "2026-01-02", 5, &info).unwrap();
+        set_quantity(&mut db, "h0001", "2026-01-02", 0, &info).unwrap();
+        add_quantity(&mut db, "h0001", "2026-01-03", 4, &info).unwrap();
+        declare(
+            &mut db,
+            "h0001",
+            "2026-01-03",
+            "2026-01-03T07:00:00Z",
+            "yes",
+        )
+        .unwrap();
+
+        let idx = CompletionIndex::new(&db);
+        for d in ["2026-01-01", "2026-01-02", "2026-01-03", "2026-01-04"] {
+            assert_eq!(idx.raw_quantity("h0001", d), get_quantity(&db, "h0001", d));
+        }
+
+        // undeclared days don't count for goals that need a declaration
+        assert_eq!(idx.counted_quantity(&habit, "2026-01-01"), 0);
+        assert!(!idx.day_met(&habit, "2026-01-01"));
+        assert_eq!(idx.counted_quantity(&habit, "2026-01-03"), 4);
+        assert!(idx.day_met(&habit, "2026-01-03"));
+
+        assert_eq!(idx.range_sums(&habit, "2026-01-01", "2026-01-07"), (7, 4));
+        assert_eq!(idx.range_sums(&habit, "2026-01-02", "2026-01-02"), (0, 0));
+        assert_eq!(idx.range_sums(&habit, "2026-01-07", "2026-01-01"), (0, 0));
+    }
+
+    #[test]
+    fn day_outcome_honors_the_excuse_policy() {
+        let mut db = default_db();
+        let mut habit = make_habit(
+            "h0001".to_string(),
+            "Run",
+            "everyday",
+            "day",
+            1,
+            None,
+            "2026-01-01",
+            false,
+            2,
+            HabitKind::Goal,
+        )
+        .unwrap();
+        let info = EventInfo {
+            ts: "2026-01-01T08:00:00Z".to_string(),
+            note: None,
+            source: "cli".to_string(),
+        };
+        add_quantity(&mut db, "h0001", "2026-01-01", 1, &info).unwrap();
+        for date in ["2026-01-01", "2026-01-02"] {
+            crate::excuses::excuse(
+                &mut db,
+                "h0001",
+                date,
+                "2026-01-02T20:00:00Z",
+                ExcuseKind::Allowed,
+                "sick",
+                2,
+            )
+            .unwrap();
+        }
+
+        let idx = CompletionIndex::new(&db);
+        assert!(idx.has_allowed_excuse("h0001", "2026-01-02"));
+        assert_eq!(idx.day_outcome(&habit, "2026-01-01"), DayOutcome::Met);
+        assert_eq!(idx.day_outcome(&habit, "2026-01-02"), DayOutcome::Excused);
+        assert_eq!(idx.day_outcome(&habit, "2026-01-03"), DayOutcome::Missed);
+
+        assert_eq!(idx.day_credit(&habit, "2026-01-02"), DayCredit::Skipped);
+
+        habit.excuse_policy = ExcusePolicy::Count;
+        assert_eq!(
+            idx.day_credit(&habit, "2026-01-02"),
+            DayCredit::Credited { excused: true }
+        );
+        assert_eq!(
+            idx.day_credit(&habit, "2026-01-01"),
+            DayCredit::Credited { excused: false }
+        );
+
+        habit.excuse_policy = ExcusePolicy::Ignore;
+        assert_eq!(idx.day_outcome(&habit, "2026-01-02"), DayOutcome::Missed);
+        assert_eq!(idx.day_credit(&habit, "2026-01-02"), DayCredit::Missed);
     }
 }
@@ -0,0 +1,35 @@
+use crate::date::{parse_day_start, parse_timezone};
+use crate::error::CliError;
+use crate::model::Db;
+
+/// How "today" is derived from the system clock.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct DayConfig {
+    pub timezone: Option<String>,
+    pub day_start: String,
+}
+
+pub fn day_config(db: &Db) -> DayConfig {
+    DayConfig {
+        timezone: db.meta.timezone.clone(),
+        day_start: db.meta.day_start.clone(),
+    }
+}
+
+pub fn set_day_config(
+    db: &mut Db,
+    timezone: Option<&str>,
+    day_start: Option<&str>,
+) -> Result<DayConfig, CliError> {
+    if let Some(tz) = timezone {
+        parse_timezone(tz)?;
+        db.meta.timezone = Some(tz.trim().to_string());
+    }
+
+    if let Some(ds) = day_start {
+        let t = parse_day_start(ds)?;
+        db.meta.day_start = t.format("%H:%M").to_string();
+    }
+
+    Ok(day_config(db))
+}
@@ -1,4 +1,5 @@
 use crate::error::CliError;
+use chrono::{DateTime, Duration, FixedOffset, NaiveDateTime, NaiveTime, Utc};
 use std::time::{SystemTime, UNIX_EPOCH};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
@@ -8,15 +9,8 @@ struct Date {
     d: u32,
 }
 
-fn is_valid_date(y: i32, m: u32, d: u32) -> bool {
-    if !(1..=12).contains(&m) {
-        return false;
-    }
-    if d < 1 {
-        return false;
-    }
-
-    let dim = match m {
+fn days_in_month(y: i32, m: u32) -> u32 {
+    match m {
         1 | 3 | 5 | 7 | 8 | 10 | 12 => 31,
         4 | 6 | 9 | 11 => 30,
         2 => {
@@ -27,10 +21,19 @@ fn is_valid_date(y: i32, m: u32, d: u32) -> bool {
                 28
             }
         }
-        _ => return false,
-    };
+        _ => 0,
+    }
+}
+
+fn is_valid_date(y: i32, m: u32, d: u32) -> bool {
+    if !(1..=12).contains(&m) {
+        return false;
+    }
+    if d < 1 {
+        return false;
+    }
 
-    d <= dim
+    d <= days_in_month(y, m)
 }
 
 // Howard Hinnant's algorithm: days since 1970-01-01 (Unix epoch)
@@ -107,6 +110,13 @@ pub fn add_days(date: &str, delta_days: i32) -> Result<String, CliError> {
     Ok(fmt_date(civil_from_days(days + delta_days)))
 }
 
+/// Signed number of days from `from` to `to`.
+pub fn days_between(from: &str, to: &str) -> Result<i32, CliError> {
+    let a = parse_date(from, "date")?;
+    let b = parse_date(to, "date")?;
+    Ok(days_from_civil(b.y, b.m, b.d) - days_from_civil(a.y, a.m, a.d))
+}
+
 /// ISO weekday number: Mon=1..Sun=7
 pub fn iso_weekday(date: &str) -> Result<u8, CliError> {
     let dt = parse_date(date, "date")?;
@@ -145,6 +155,31 @@ pub fn iso_week_end(date: &str) -> Result<String, CliError> {
     add_days(&start, 6)
 }
 
+/// Day of month (1..=31) and whether it is the last day of its month.
+pub fn day_of_month(date: &str) -> Result<(u32, bool), CliError> {
+    let dt = parse_date(date, "date")?;
+    Ok((dt.d, dt.d == days_in_month(dt.y, dt.m)))
+}
+
+pub fn month_start(date: &str) -> Result<String, CliError> {
+    let dt = parse_date(date, "date")?;
+    Ok(fmt_date(Date { d: 1, ..dt }))
+}
+
+pub fn month_end(date: &str) -> Result<String, CliError> {
+    let dt = parse_date(date, "date")?;
+    Ok(fmt_date(Date {
+        d: days_in_month(dt.y, dt.m),
+        ..dt
+    }))
+}
+
+/// Calendar month id, e.g. `2026-01`.
+pub fn month_id(date: &str) -> Result<String, CliError> {
+    let dt = parse_date(date, "date")?;
+    Ok(format!("{:04}-{:02}", dt.y, dt.m))
+}
+
 pub fn iso_week_id(week_start_date: &str) -> Result<String, CliError> {
     // week_year is the year of Thursday in that ISO week.
     let wd = iso_weekday(week_start_date)? as i32;
@@ -166,13 +201,89 @@ pub fn iso_week_id(week_start_date: &str) -> Result<String, CliError> {
     Ok(format!("{:04}-W{:02}", week_year, week as i32))
 }
 
-pub fn system_today_utc() -> String {
+/// A stored timezone: IANA name (e.g. `Asia/Seoul`) or fixed UTC offset (e.g. `+09:00`).
+#[derive(Debug, Clone, Copy)]
+pub enum Timezone {
+    Fixed(FixedOffset),
+    Iana(chrono_tz::Tz),
+}
+
+fn parse_fixed_offset(s: &str) -> Option<FixedOffset> {
+    let bytes = s.as_bytes();
+    if bytes.len() != 6 || bytes[3] != b':' {
+        return None;
+    }
+    let sign = match bytes[0] {
+        b'+' => 1,
+        b'-' => -1,
+        _ => return None,
+    };
+    let h: i32 = s[1..3].parse().ok()?;
+    let m: i32 = s[4..6].parse().ok()?;
+    if h > 23 || m > 59 {
+        return None;
+    }
+    FixedOffset::east_opt(sign * (h * 3600 + m * 60))
+}
+
+pub fn parse_timezone(s: &str) -> Result<Timezone, CliError> {
+    let t = s.trim();
+    if t.is_empty() {
+        return Err(CliError::usage("Invalid timezone: (empty)"));
+    }
+    if let Some(off) = parse_fixed_offset(t) {
+        return Ok(Timezone::Fixed(off));
+    }
+    t.parse::<chrono_tz::Tz>()
+        .map(Timezone::Iana)
+        .map_err(|_| CliError::usage(format!("Invalid timezone: {}", s)))
+}
+
+/// Parses a day boundary (HH:MM local time at which a new logical day starts).
+pub fn parse_day_start(s: &str) -> Result<NaiveTime, CliError> {
+    let v = s.trim();
+    if v.is_empty() {
+        return Err(CliError::usage("Invalid day_start: (empty)"));
+    }
+    NaiveTime::parse_from_str(v, "%H:%M")
+        .map_err(|_| CliError::usage(format!("Invalid day_start: {}", s)))
+}
+
+fn local_naive(instant: DateTime<Utc>, tz: Timezone) -> NaiveDateTime {
+    match tz {
+        Timezone::Fixed(off) => instant.with_timezone(&off).naive_local(),
+        Timezone::Iana(z) => instant.with_timezone(&z).naive_local(),
+    }
+}
+
+/// Logical date for a Unix timestamp, given a timezone (None = UTC) and day boundary.
+///
+/// Local times before `day_start` belong to the previous logical day, so a
+/// 01:30 check-in with `day_start = 04:00` counts toward yesterday.
+pub fn logical_date_at(
+    unix_secs: i64,
+    timezone: Option<&str>,
+    day_start: &str,
+) -> Result<String, CliError> {
+    let instant = DateTime::<Utc>::from_timestamp(unix_secs, 0)
+        .ok_or_else(|| CliError::usage("Invalid timestamp"))?;
+    let local = match timezone {
+        Some(tz) => local_naive(instant, parse_timezone(tz)?),
+        None => instant.naive_utc(),
+    };
+    let start = parse_day_start(day_start)?;
+    let shifted =
+        local - Duration::seconds(start.signed_duration_since(NaiveTime::MIN).num_seconds());
+    Ok(shifted.date().format("%Y-%m-%d").to_string())
+}
+
+/// Logical "today" from the system clock.
+pub fn system_today(timezone: Option<&str>, day_start: &str) -> Result<String, CliError> {
     let secs = SystemTime::now()
         .duration_since(UNIX_EPOCH)
         .unwrap_or_default()
         .as_secs();
-    let days = (secs / 86_400) as i32;
-    fmt_date(civil_from_days(days))
+    logical_date_at(secs as i64, timezone, day_start)
 }
 
 #[cfg(test)]
@@ -194,4 +305,40 @@ mod tests {
         assert_eq!(iso_week_end("2026-01-31").unwrap(), "2026-02-01");
         assert_eq!(iso_week_id("2026-01-26").unwrap(), "2026-W05");
     }
+
+    #[test]
+    fn month_math_matches_expectations() {
+        assert_eq!(month_start("2026-02-17").unwrap(), "2026-02-01");
+        assert_eq!(month_end("2026-02-17").unwrap(), "2026-02-28");
+        assert_eq!(month_end("2024-02-01").unwrap(), "2024-02-29");
+        assert_eq!(month_id("2026-12-31").unwrap(), "2026-12");
+        assert_eq!(day_of_month("2026-04-30").unwrap(), (30, true));
+        assert_eq!(day_of_month("2026-05-30").unwrap(), (30, false));
+    }
+
+    #[test]
+    fn logical_date_respects_timezone_and_day_start() {
+        // 2026-01-31T16:30:00Z
+        let t = 1_769_877_000;
+        assert_eq!(logical_date_at(t, None, "00:00").unwrap(), "2026-01-31");
+        assert_eq!(
+            logical_date_at(t, Some("+09:00"), "00:00").unwrap(),
+            "2026-02-01"
+        );
+        assert_eq!(
+            logical_date_at(t, Some("Asia/Seoul"), "00:00").unwrap(),
+            "2026-02-01"
+        );
+        // 01:30 in Seoul with a 04:00 boundary is still the previous logical day.
+        assert_eq!(
+            logical_date_at(t, Some("Asia/Seoul"), "04:00").unwrap(),
+            "2026-01-31"
+        );
+        assert_eq!(
+            logical_date_at(t, Some("-05:00"), "00:00").unwrap(),
+            "2026-01-31"
+        );
+        assert!(parse_timezone("Mars/Olympus").is_err());
+        assert!(parse_day_start("25:00").is_err());
+    }
 }
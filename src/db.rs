@@ -1,6 +1,9 @@
 use crate::error::CliError;
 use crate::model::{default_db, Db};
 use crate::stable_json::stable_to_string_pretty;
+use crate::storage::{self, Storage, StorageKind};
+use serde_json::Value;
+use std::collections::BTreeSet;
 use std::fs;
 use std::fs::OpenOptions;
 use std::io::Write;
@@ -47,8 +50,186 @@ pub fn resolve_db_path(cli_db_path: Option<&str>) -> Result<String, CliError> {
         .to_string())
 }
 
+/// Schema version written by this build. Older files are upgraded on read.
+pub const CURRENT_DB_VERSION: u32 = 2;
+
+/// One schema upgrade step: `from` -> `from + 1`.
+///
+/// Migrations operate on the raw JSON value because an older file may not
+/// deserialize into the current `Db` shape. `apply` returns a human-readable
+/// list of changes (used by `habit db migrate --dry-run`).
+pub struct Migration {
+    pub from: u32,
+    pub description: &'static str,
+    pub apply: fn(&mut Value) -> Result<Vec<String>, CliError>,
+}
+
+/// Registered migrations, ordered by `from`.
+const MIGRATIONS: &[Migration] = &[Migration {
+    from: 1,
+    description: "Convert per-day check-in rows into check-in events",
+    apply: migrate_v1_checkin_events,
+}];
+
+/// One v1 check-in row: `(habit_id, date, quantity)`.
+pub type V1Row = (String, String, u64);
+
+/// v1 check-in rows split into the row v1 read
+/// for each (habit, date) -- the first in file order -- and the repeats it
+/// never looked at.
+pub fn v1_first_rows(rows: &[Value]) -> Result<(Vec<V1Row>, Vec<V1Row>), CliError> {
+    let mut seen: BTreeSet<(String, String)> = BTreeSet::new();
+    let mut first: Vec<V1Row> = Vec::new();
+    let mut repeats: Vec<V1Row> = Vec::new();
+    for row in rows.iter() {
+        let habit_id = row.get("habit_id").and_then(|x| x.as_str());
+        let date = row.get("date").and_then(|x| x.as_str());
+        let quantity = row.get("quantity").and_then(|x| x.as_u64());
+        let (h, d, q) = match (habit_id, date, quantity) {
+            (Some(h), Some(d), Some(q)) => (h.to_string(), d.to_string(), q),
+            _ => return Err(CliError::io("DB corrupted")),
+        };
+        if seen.insert((h.clone(), d.clone())) {
+            first.push((h, d, q));
+        } else {
+            repeats.push((h, d, q));
+        }
+    }
+    Ok((first, repeats))
+}
+
+/// v1 stored one `{habit_id, date, quantity}` row per day. The row v1 read
+/// for each (habit, date) becomes a single `add` event timestamped at the
+/// start of its date (UTC); repeated rows are dropped and listed.
+fn migrate_v1_checkin_events(v: &mut Value) -> Result<Vec<String>, CliError> {
+    let obj = v
+        .as_object_mut()
+        .ok_or_else(|| CliError::io("DB corrupted"))?;
+
+    let rows = match obj.remove("checkins") {
+        Some(Value::Array(rows)) => rows,
+        None | Some(Value::Null) => Vec::new(),
+        Some(_) => return Err(CliError::io("DB corrupted")),
+    };
+
+    let (first, repeats) = v1_first_rows(&rows)?;
+    let mut kept: Vec<(String, String, u64)> = first
+        .into_iter()
+        .map(|(h, d, q)| (d, h, q))
+        .filter(|p| p.2 > 0)
+        .collect();
+    kept.sort();
+
+    let mut events: Vec<Value> = Vec::new();
+    for (date, habit_id, quantity) in kept {
+        events.push(serde_json::json!({
+            "id": format!("c{:06}", events.len() + 1),
+            "habit_id": habit_id,
+            "date": date,
+            "ts": format!("{}T00:00:00Z", date),
+            "kind": "add",
+            "delta": quantity,
+            "note": null,
+            "source": "migration",
+        }));
+    }
+
+    let n = events.len();
+    obj.insert("checkin_events".to_string(), Value::Array(events));
+    if let Some(meta) = obj.get_mut("meta").and_then(|m| m.as_object_mut()) {
+        meta.insert("next_checkin_number".to_string(), Value::from(n + 1));
+    }
+
+    let mut changes = vec![format!(
+        "checkins: {} per-day rows -> {} add events",
+        rows.len(),
+        n
+    )];
+    for (habit_id, date, quantity) in repeats {
+        changes.push(format!(
+            "checkins: dropped repeated row for {} on {} (quantity {}; v1 only read the first)",
+            habit_id, date, quantity
+        ));
+    }
+    Ok(changes)
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct MigrationStep {
+    pub from_version: u32,
+    pub to_version: u32,
+    pub description: String,
+    pub changes: Vec<String>,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct MigrationReport {
+    pub from_version: u32,
+    pub to_version: u32,
+    pub steps: Vec<MigrationStep>,
+}
+
+impl MigrationReport {
+    pub fn is_noop(&self) -> bool {
+        self.steps.is_empty()
+    }
+}
+
+fn raw_version(v: &Value) -> Result<u32, CliError> {
+    v.get("version")
+        .and_then(|n| n.as_u64())
+        .and_then(|n| u32::try_from(n).ok())
+        .filter(|n| *n >= 1)
+        .ok_or_else(|| CliError::io("DB corrupted"))
+}
+
+fn migrate_value_with(
+    v: &mut Value,
+    migrations: &[Migration],
+    target: u32,
+) -> Result<MigrationReport, CliError> {
+    let from_version = raw_version(v)?;
+    if from_version > target {
+        return Err(CliError::io(format!(
+            "DB version {} is newer than supported version {}",
+            from_version, target
+        )));
+    }
+
+    let mut steps: Vec<MigrationStep> = Vec::new();
+    let mut cur = from_version;
+    while cur < target {
+        let m = migrations
+            .iter()
+            .find(|m| m.from == cur)
+            .ok_or_else(|| CliError::io(format!("No migration from DB version {}", cur)))?;
+        let changes = (m.apply)(v)?;
+        cur += 1;
+        if let Some(obj) = v.as_object_mut() {
+            obj.insert("version".to_string(), Value::from(cur));
+        }
+        steps.push(MigrationStep {
+            from_version: m.from,
+            to_version: cur,
+            description: m.description.to_string(),
+            changes,
+        });
+    }
+
+    Ok(MigrationReport {
+        from_version,
+        to_version: target,
+        steps,
+    })
+}
+
+/// Upgrade a raw DB value to `CURRENT_DB_VERSION` in place.
+pub fn migrate_value(v: &mut Value) -> Result<MigrationReport, CliError> {
+    migrate_value_with(v, MIGRATIONS, CURRENT_DB_VERSION)
+}
+
 fn validate_db_shape(db: &Db) -> Result<(), CliError> {
-    if db.version != 1 {
+    if db.version != CURRENT_DB_VERSION {
         return Err(CliError::io("DB corrupted"));
     }
     if db.meta.next_habit_number < 1
@@ -56,30 +237,94 @@ fn validate_db_shape(db: &Db) -> Result<(), CliError> {
         || db.meta.next_excuse_number < 1
         || db.meta.next_penalty_rule_number < 1
         || db.meta.next_routine_number < 1
+        || db.meta.next_checkin_number < 1
     {
         return Err(CliError::io("DB corrupted"));
     }
     Ok(())
 }
 
-pub fn read_db(db_path: &str) -> Result<Db, CliError> {
-    match fs::read_to_string(db_path) {
-        Ok(txt) => {
-            let db: Db = serde_json::from_str(&txt).map_err(|_| CliError::io("DB corrupted"))?;
-            validate_db_shape(&db)?;
-            Ok(db)
-        }
-        Err(e) => {
-            if e.kind() == std::io::ErrorKind::NotFound {
-                Ok(default_db())
-            } else {
-                Err(CliError::io("DB IO error"))
-            }
+struct LoadedDb {
+    db: Db,
+    report: MigrationReport,
+    /// What the backend returned, before migrations (None if nothing was stored).
+    original: Option<Value>,
+}
+
+fn db_from_value(mut v: Value) -> Result<(Db, MigrationReport), CliError> {
+    let report = migrate_value(&mut v)?;
+    let db: Db = serde_json::from_value(v).map_err(|_| CliError::io("DB corrupted"))?;
+    validate_db_shape(&db)?;
+    Ok((db, report))
+}
+
+fn load_db(storage: &dyn Storage) -> Result<LoadedDb, CliError> {
+    match storage.load()? {
+        Some(v) => {
+            let (db, report) = db_from_value(v.clone())?;
+            Ok(LoadedDb {
+                db,
+                report,
+                original: Some(v),
+            })
         }
+        None => Ok(LoadedDb {
+            db: default_db(),
+            report: MigrationReport {
+                from_version: CURRENT_DB_VERSION,
+                to_version: CURRENT_DB_VERSION,
+                steps: Vec::new(),
+            },
+            original: None,
+        }),
     }
 }
 
-fn ensure_parent_dir(db_path: &str) -> Result<(), CliError> {
+/// Reads the DB, upgrading older schema versions in memory.
+///
+/// The upgraded DB is only persisted (with a backup) by the next write.
+pub fn read_db(db_path: &str) -> Result<Db, CliError> {
+    Ok(load_db(storage::open(db_path)?.as_ref())?.db)
+}
+
+fn db_to_value(db: &Db) -> Result<Value, CliError> {
+    validate_db_shape(db)?;
+    serde_json::to_value(db).map_err(|_| CliError::io("DB IO error"))
+}
+
+/// Backups are always JSON, whatever the storage backend.
+fn backup_text(v: &Value) -> Result<String, CliError> {
+    Ok(stable_to_string_pretty(v).map_err(|_| CliError::io("DB IO error"))? + "\n")
+}
+
+pub fn backup_path_for(db_path: &str, version: u32) -> String {
+    format!("{}.v{}.bak", db_path, version)
+}
+
+fn write_backup(
+    db_path: &str,
+    report: &MigrationReport,
+    raw: Option<&str>,
+) -> Result<Option<String>, CliError> {
+    let raw = match raw {
+        Some(raw) if !report.is_noop() => raw,
+        _ => return Ok(None),
+    };
+
+    let path = backup_path_for(db_path, report.from_version);
+    fs::File::create(&path)
+        .and_then(|mut f| f.write_all(raw.as_bytes()).and_then(|_| f.sync_all()))
+        .map_err(|_| CliError::io("DB IO error"))?;
+
+    #[cfg(unix)]
+    {
+        let _ = fs::set_permissions(&path, fs::Permissions::from_mode(0o600));
+    }
+
+    Ok(Some(path))
+}
+
+pub fn ensure_parent_dir(db_path: &str) -> Result<(), CliError> {
     let dir = Path::new(db_path)
         .parent()
         .ok_or_else(|| CliError::io("DB IO error"))?;
@@ -93,98 +338,457 @@ fn ensure_parent_dir(db_path: &str) -> Result<(), CliError> {
     Ok(())
 }
 
-struct WriteLock {
-    path: PathBuf,
-}
-
-impl Drop for WriteLock {
-    fn drop(&mut self) {
-        let _ = fs::remove_file(&self.path);
-    }
-}
-
 fn with_write_lock<R>(
     db_path: &str,
     f: impl FnOnce() -> Result<R, CliError>,
 ) -> Result<R, CliError> {
     let lock_path = PathBuf::from(format!("{}.lock", db_path));
+    let _guard = crate::lock::acquire(&lock_path)?;
+    f()
+}
 
-    match OpenOptions::new()
-        .write(true)
-        .create_new(true)
-        .open(&lock_path)
-    {
-        Ok(mut file) => {
-            #[cfg(unix)]
-            {
-                let _ = file.set_permissions(fs::Permissions::from_mode(0o600));
-            }
-            let _ = file.write_all(b"");
-            let _guard = WriteLock { path: lock_path };
-            f()
-        }
-        Err(e) => {
-            if e.kind() == std::io::ErrorKind::AlreadyExists {
-                Err(CliError::io("DB is locked"))
-            } else {
-                Err(CliError::io("DB IO error"))
-            }
+/// Rolling backups kept in `<db>.backups/` when `HABITCLI_BACKUPS` is unset.
+pub const DEFAULT_BACKUP_COUNT: usize = 10;
+
+fn backup_count() -> usize {
+    std::env::var("HABITCLI_BACKUPS")
+        .ok()
+        .and_then(|s| s.trim().parse::<usize>().ok())
+        .unwrap_or(DEFAULT_BACKUP_COUNT)
+}
+
+pub fn backups_dir_for(db_path: &str) -> PathBuf {
+    PathBuf::from(format!("{}.backups", db_path))
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct BackupInfo {
+    /// File name (`YYYYMMDDTHHMMSS.mmmZ.json`, UTC time of the backup).
+    pub name: String,
+    pub path: String,
+    pub bytes: u64,
+}
+
+/// Backups in `<db>.backups/`, newest first.
+pub fn list_backups(db_path: &str) -> Result<Vec<BackupInfo>, CliError> {
+    let dir = backups_dir_for(db_path);
+    let entries = match fs::read_dir(&dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(_) => return Err(CliError::io("DB IO error")),
+    };
+
+    let mut out: Vec<BackupInfo> = Vec::new();
+    for entry in entries.flatten() {
+        let name = entry.file_name().to_string_lossy().to_string();
+        if !name.ends_with(".json") || name.starts_with('.') {
+            continue;
         }
+        let bytes = entry.metadata().map(|m| m.len()).unwrap_or(0);
+        out.push(BackupInfo {
+            path: entry.path().to_string_lossy().to_string(),
+            name,
+            bytes,
+        });
     }
+    out.sort_by(|a, b| backup_order(&b.name).cmp(&backup_order(&a.name)));
+    Ok(out)
 }
 
-fn write_db_inner(db_path: &str, db: &Db) -> Result<(), CliError> {
-    validate_db_shape(db)?;
-    ensure_parent_dir(db_path)?;
+/// `(timestamp, seq)` of a backup name; same-millisecond backups are
+/// `<stamp>-<seq>.json`, written after the plain `<stamp>.json`.
+fn backup_order(name: &str) -> (&str, u32) {
+    let stem = name.strip_suffix(".json").unwrap_or(name);
+    match stem.rsplit_once('-') {
+        Some((stamp, seq)) => match seq.parse::<u32>() {
+            Ok(seq) => (stamp, seq),
+            Err(_) => (stem, 0),
+        },
+        None => (stem, 0),
+    }
+}
 
-    let dir = Path::new(db_path)
-        .parent()
-        .ok_or_else(|| CliError::io("DB IO error"))?;
+fn backup_stamp() -> String {
+    let millis = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_millis() as i64)
+        .unwrap_or(0);
+    chrono::DateTime::from_timestamp_millis(millis)
+        .unwrap_or_default()
+        .format("%Y%m%dT%H%M%S%.3fZ")
+        .to_string()
+}
 
-    let tmp_path = dir.join(format!(".db.json.tmp.{}", std::process::id()));
-    let data = stable_to_string_pretty(db).map_err(|_| CliError::io("DB IO error"))? + "\n";
+/// Copies the current file contents into `<db>.backups/` and prunes the
+/// oldest copies beyond `HABITCLI_BACKUPS` (0 disables backups).
+fn write_rolling_backup(db_path: &str, raw: &[u8]) -> Result<Option<String>, CliError> {
+    let keep = backup_count();
+    if keep == 0 {
+        return Ok(None);
+    }
 
+    let dir = backups_dir_for(db_path);
+    fs::create_dir_all(&dir).map_err(|_| CliError::io("DB IO error"))?;
+    #[cfg(unix)]
     {
-        let mut f = OpenOptions::new()
-            .write(true)
-            .create(true)
-            .truncate(true)
-            .open(&tmp_path)
-            .map_err(|_| CliError::io("DB IO error"))?;
-
-        #[cfg(unix)]
-        {
-            let _ = f.set_permissions(fs::Permissions::from_mode(0o600));
-        }
-
-        f.write_all(data.as_bytes())
-            .map_err(|_| CliError::io("DB IO error"))?;
-        let _ = f.flush();
+        let _ = fs::set_permissions(&dir, fs::Permissions::from_mode(0o700));
     }
 
-    fs::rename(&tmp_path, db_path).map_err(|_| {
-        let _ = fs::remove_file(&tmp_path);
-        CliError::io("DB IO error")
-    })?;
+    let stamp = backup_stamp();
+
+    // Same-millisecond writes get a numeric suffix so nothing is overwritten.
+    let mut n = 0;
+    let (path, mut f) = loop {
+        let name = if n == 0 {
+            format!("{}.json", stamp)
+        } else {
+            format!("{}-{:03}.json", stamp, n)
+        };
+        let path = dir.join(name);
+        match OpenOptions::new().write(true).create_new(true).open(&path) {
+            Ok(f) => break (path, f),
+            Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists && n < 1000 => n += 1,
+            Err(_) => return Err(CliError::io("DB IO error")),
+        }
+    };
 
     #[cfg(unix)]
     {
-        let _ = fs::set_permissions(db_path, fs::Permissions::from_mode(0o600));
+        let _ = f.set_permissions(fs::Permissions::from_mode(0o600));
     }
+    f.write_all(raw)
+        .and_then(|_| f.sync_all())
+        .map_err(|_| CliError::io("DB IO error"))?;
+    drop(f);
+    // the new copy must be durable before older ones are pruned
+    storage::sync_dir(&dir)?;
 
-    Ok(())
+    for old in list_backups(db_path)?.into_iter().skip(keep) {
+        let _ = fs::remove_file(&old.path);
+    }
+
+    Ok(Some(path.to_string_lossy().to_string()))
 }
 
 pub fn update_db<R>(
     db_path: &str,
     mutator: impl FnOnce(&mut Db) -> Result<R, CliError>,
 ) -> Result<R, CliError> {
+    let storage = storage::open(db_path)?;
     ensure_parent_dir(db_path)?;
     with_write_lock(db_path, || {
-        let mut db = read_db(db_path)?;
+        let LoadedDb {
+            mut db,
+            report,
+            original,
+        } = load_db(storage.as_ref())?;
+        // The stored value already is the pre-command DB unless a migration
+        // changed its shape; only then does it need serializing again.
+        let migrated_before = match original.as_ref() {
+            Some(_) if report.is_noop() => None,
+            _ => Some(db_to_value(&db)?),
+        };
         let out = mutator(&mut db)?;
-        validate_db_shape(&db)?;
-        write_db_inner(db_path, &db)?;
+
+        let mut value = db_to_value(&db)?;
+        let before = migrated_before.as_ref().or(original.as_ref());
+        if let Some(before) = before {
+            let last = db.journal.last().map(|e| e.id.clone());
+            crate::journal::record(&mut db, before, &value)?;
+            if db.journal.last().map(|e| &e.id) != last.as_ref() {
+                value["journal"] =
+                    serde_json::to_value(&db.journal).map_err(|_| CliError::io("DB IO error"))?;
+            }
+        }
+
+        if report.is_noop() && original.as_ref() == Some(&value) {
+            return Ok(out);
+        }
+        if let Some(orig) = original.as_ref() {
+            let text = backup_text(orig)?;
+            write_backup(db_path, &report, Some(&text))?;
+            write_rolling_backup(db_path, text.as_bytes())?;
+        }
+        storage.save(&value, original.as_ref())?;
         Ok(out)
     })
 }
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct MigrateOutcome {
+    pub dry_run: bool,
+    pub report: MigrationReport,
+    /// Path of the pre-migration backup (only when a migration was written).
+    pub backup: Option<String>,
+}
+
+/// Upgrades the DB file to `CURRENT_DB_VERSION`.
+///
+/// With `dry_run`, reports the pending steps without touching the file.
+pub fn migrate_db(db_path: &str, dry_run: bool) -> Result<MigrateOutcome, CliError> {
+    let storage = storage::open(db_path)?;
+    if dry_run {
+        let loaded = load_db(storage.as_ref())?;
+        return Ok(MigrateOutcome {
+            dry_run,
+            report: loaded.report,
+            backup: None,
+        });
+    }
+
+    ensure_parent_dir(db_path)?;
+    with_write_lock(db_path, || {
+        let loaded = load_db(storage.as_ref())?;
+        if loaded.report.is_noop() {
+            return Ok(MigrateOutcome {
+                dry_run,
+                report: loaded.report,
+                backup: None,
+            });
+        }
+        let text = loaded.original.as_ref().map(backup_text).transpose()?;
+        let backup = write_backup(db_path, &loaded.report, text.as_deref())?;
+        storage.save(&db_to_value(&loaded.db)?, loaded.original.as_ref())?;
+        Ok(MigrateOutcome {
+            dry_run,
+            report: loaded.report,
+            backup,
+        })
+    })
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct RestoreOutcome {
+    pub restored_from: String,
+    /// Rolling backup of the DB as it was right before the restore (for an
+    /// unreadable SQLite file, where it was moved aside).
+    pub backup: Option<String>,
+}
+
+fn parse_db_text(txt: &str) -> Result<Db, CliError> {
+    let v: Value = serde_json::from_str(txt).map_err(|_| CliError::io("DB corrupted"))?;
+    Ok(db_from_value(v)?.0)
+}
+
+/// Replaces the DB with a rolling backup (newest when `selector` is None).
+///
+/// `selector` is a backup file name from `list_backups` or a path. The
+/// current DB is itself backed up first, so a restore can be reverted.
+pub fn restore_db(db_path: &str, selector: Option<&str>) -> Result<RestoreOutcome, CliError> {
+    let backups = list_backups(db_path)?;
+    let source = match selector.map(|s| s.trim()).filter(|s| !s.is_empty()) {
+        None => backups
+            .first()
+            .map(|b| b.path.clone())
+            .ok_or_else(|| CliError::not_found("No backups found"))?,
+        Some(sel) => match backups.iter().find(|b| b.name == sel) {
+            Some(b) => b.path.clone(),
+            None if Path::new(sel).is_file() => sel.to_string(),
+            None => return Err(CliError::not_found(format!("Backup not found: {}", sel))),
+        },
+    };
+
+    let txt = fs::read_to_string(&source).map_err(|_| CliError::io("DB IO error"))?;
+    let restored = db_to_value(&parse_db_text(&txt)?)?;
+
+    let storage = storage::open(db_path)?;
+    ensure_parent_dir(db_path)?;
+    with_write_lock(db_path, || {
+        // The current DB is set aside without parsing it: a truncated or
+        // unreadable file is exactly what a restore is for.
+        let raw = match fs::read(db_path) {
+            Ok(raw) => Some(raw),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
+            Err(_) => return Err(CliError::io("DB IO error")),
+        };
+        let backup = match (raw, storage.kind()) {
+            (None, _) => None,
+            (Some(raw), StorageKind::Json) => write_rolling_backup(db_path, &raw)?,
+            // Rolling backups are JSON; an SQLite file is exported if it still
+            // reads, and moved aside as-is otherwise.
+            (Some(_), StorageKind::Sqlite) => match storage.load() {
+                Ok(Some(v)) => write_rolling_backup(db_path, backup_text(&v)?.as_bytes())?,
+                Ok(None) => None,
+                Err(_) => {
+                    let aside = format!("{}.unreadable-{}", db_path, backup_stamp());
+                    fs::rename(db_path, &aside).map_err(|_| CliError::io("DB IO error"))?;
+                    Some(aside)
+                }
+            },
+        };
+        storage.save(&restored, None)?;
+        Ok(RestoreOutcome {
+            restored_from: source.clone(),
+            backup,
+        })
+    })
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct ConvertOutcome {
+    pub from: String,
+    pub from_storage: StorageKind,
+    pub to: String,
+    pub to_storage: StorageKind,
+    pub version: u32,
+}
+
+/// Copies the DB at `db_path` into a new `to_path` (possibly another backend).
+///
+/// The source is upgraded to `CURRENT_DB_VERSION` in the copy only; the copy
+/// is read back and compared, so a lossy conversion is an error.
+pub fn convert_db(
+    db_path: &str,
+    to_path: &str,
+    to_kind: Option<StorageKind>,
+) -> Result<ConvertOutcome, CliError> {
+    let source = storage::open(db_path)?;
+    let to_kind = to_kind.unwrap_or_else(|| storage::kind_for_path(to_path));
+    let dest = storage::open_kind(to_path, to_kind)?;
+
+    if Path::new(to_path).exists() {
+        return Err(CliError::usage(format!("Destination exists: {}", to_path)));
+    }
+
+    let loaded = load_db(source.as_ref())?;
+    let value = db_to_value(&loaded.db)?;
+
+    ensure_parent_dir(to_path)?;
+    with_write_lock(to_path, || {
+        dest.save(&value, None)?;
+        if dest.load()?.as_ref() != Some(&value) {
+            let _ = fs::remove_file(to_path);
+            return Err(CliError::io("DB conversion mismatch"));
+        }
+        Ok(ConvertOutcome {
+            from: db_path.to_string(),
+            from_storage: source.kind(),
+            to: to_path.to_string(),
+            to_storage: dest.kind(),
+            version: loaded.db.version,
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn add_field(v: &mut Value) -> Result<Vec<String>, CliError> {
+        v.as_object_mut()
+            .unwrap()
+            .insert("added".to_string(), Value::Bool(true));
+        Ok(vec!["added field".to_string()])
+    }
+
+    const TEST_MIGRATIONS: &[Migration] = &[
+        Migration {
+            from: 1,
+            description: "v1 -> v2",
+            apply: add_field,
+        },
+        Migration {
+            from: 2,
+            description: "v2 -> v3",
+            apply: |_| Ok(Vec::new()),
+        },
+    ];
+
+    #[test]
+    fn migrations_chain_in_order() {
+        let mut v = serde_json::json!({ "version": 1 });
+        let report = migrate_value_with(&mut v, TEST_MIGRATIONS, 3).unwrap();
+        assert_eq!(report.from_version, 1);
+        assert_eq!(report.to_version, 3);
+        assert_eq!(report.steps.len(), 2);
+        assert_eq!(report.steps[0].changes, vec!["added field".to_string()]);
+        assert_eq!(v["version"], 3);
+        assert_eq!(v["added"], true);
+    }
+
+    #[test]
+    fn current_version_is_noop() {
+        let mut v = serde_json::json!({ "version": 3 });
+        let report = migrate_value_with(&mut v, TEST_MIGRATIONS, 3).unwrap();
+        assert!(report.is_noop());
+    }
+
+    #[test]
+    fn v1_checkins_become_add_events() {
+        let mut v = serde_json::json!({
+            "version": 1,
+            "meta": { "next_habit_number": 2 },
+            "habits": [],
+            "checkins": [
+                { "habit_id": "h0001", "date": "2026-01-02", "quantity": 3 },
+                { "habit_id": "h0001", "date": "2026-01-01", "quantity": 1 },
+            ],
+        });
+        let report = migrate_value(&mut v).unwrap();
+        assert_eq!(report.steps.len(), 1);
+        assert!(v.get("checkins").is_none());
+
+        let db: Db = serde_json::from_value(v).unwrap();
+        assert_eq!(db.version, CURRENT_DB_VERSION);
+        assert_eq!(db.checkin_events.len(), 2);
+        assert_eq!(db.checkin_events[0].id, "c000001");
+        assert_eq!(db.checkin_events[0].date, "2026-01-01");
+        assert_eq!(db.checkin_events[1].delta, 3);
+        assert_eq!(db.meta.next_checkin_number, 3);
+        assert_eq!(crate::checkins::get_quantity(&db, "h0001", "2026-01-02"), 3);
+    }
+
+    #[test]
+    fn v1_repeated_rows_keep_the_first_in_file_order() {
+        let mut v = serde_json::json!({
+            "version": 1,
+            "meta": { "next_habit_number": 2 },
+            "habits": [],
+            "checkins": [
+                { "habit_id": "h0001", "date": "2026-01-01", "quantity": 5 },
+                { "habit_id": "h0001", "date": "2026-01-01", "quantity": 2 },
+            ],
+        });
+        let report = migrate_value(&mut v).unwrap();
+        assert_eq!(report.steps[0].changes.len(), 2);
+        assert!(report.steps[0].changes[1].contains("quantity 2"));
+
+        let db: Db = serde_json::from_value(v).unwrap();
+        assert_eq!(db.checkin_events.len(), 1);
+        assert_eq!(crate::checkins::get_quantity(&db, "h0001", "2026-01-01"), 5);
+    }
+
+    #[test]
+    fn newer_or_missing_version_is_rejected() {
+        let mut newer = serde_json::json!({ "version": 4 });
+        assert!(migrate_value_with(&mut newer, TEST_MIGRATIONS, 3).is_err());
+
+        let mut missing = serde_json::json!({});
+        assert!(migrate_value_with(&mut missing, TEST_MIGRATIONS, 3).is_err());
+
+        let mut gap = serde_json::json!({ "version": 1 });
+        assert!(migrate_value_with(&mut gap, &TEST_MIGRATIONS[1..], 3).is_err());
+    }
+
+    #[test]
+    fn same_millisecond_backups_sort_by_sequence() {
+        let mut names = vec![
+            "20260105T101010.123Z-002.json",
+            "20260105T101010.123Z.json",
+            "20260105T101010.123Z-010.json",
+            "20260105T101010.124Z.json",
+            "20260105T101010.123Z-001.json",
+        ];
+        names.sort_by(|a, b| backup_order(b).cmp(&backup_order(a)));
+        assert_eq!(
+            names,
+            vec![
+                "20260105T101010.124Z.json",
+                "20260105T101010.123Z-010.json",
+                "20260105T101010.123Z-002.json",
+                "20260105T101010.123Z-001.json",
+                "20260105T101010.123Z.json",
+            ]
+        );
+    }
+}
@@ -36,9 +36,3 @@ pub fn declare(
     db.declarations.push(decl.clone());
     Ok(decl)
 }
-
-pub fn has_declaration(db: &Db, habit_id: &str, date: &str) -> bool {
-    db.declarations
-        .iter()
-        .any(|d| d.habit_id == habit_id && d.date == date)
-}
@@ -1,8 +1,8 @@
-use crate::completion::{counted_quantity, is_declared};
-use crate::date::{iso_week_end, iso_week_start, date_range_inclusive};
+use crate::completion::CompletionIndex;
 use crate::error::CliError;
 use crate::habits::{is_scheduled_on, stable_habit_sort};
-use crate::model::{Db, Habit};
+use crate::model::{Db, Habit, HabitKind};
+use crate::period::{window_start, window_sum};
 
 #[derive(Debug, Clone, serde::Serialize)]
 pub struct DueOutput {
@@ -28,30 +28,11 @@ pub struct DueCounts {
     pub due: u32,
 }
 
-fn week_sum_for_habit(
-    db: &Db,
-    habit: &Habit,
-    week_start_date: &str,
-) -> Result<u32, CliError> {
-    let end = iso_week_end(week_start_date)?;
-    let days = date_range_inclusive(week_start_date, &end)?;
-    let mut counted_sum = 0u32;
-    for d in days {
-        if d < habit.created_date {
-            continue;
-        }
-        counted_sum = counted_sum.saturating_add(counted_quantity(db, habit, &d));
-    }
-    Ok(counted_sum)
-}
-
 pub fn build_due(
     db: &Db,
     date: &str,
     include_archived: bool,
 ) -> Result<DueOutput, CliError> {
-    let week_start = iso_week_start(date)?;
-
     // Collect and sort habits
     let mut habits: Vec<Habit> = db
         .habits
@@ -61,6 +42,7 @@ pub fn build_due(
         .collect();
     habits.sort_by(stable_habit_sort);
 
+    let idx = CompletionIndex::new(db);
     let mut due_rows: Vec<DueHabitRow> = Vec::new();
 
     for h in habits.iter() {
@@ -69,10 +51,14 @@ pub fn build_due(
             continue;
         }
 
+        // Limit habits never need action: staying under the ceiling is the goal.
+        if h.kind == HabitKind::Limit {
+            continue;
+        }
+
         if h.target.period == "day" {
-            let counted = counted_quantity(db, h, date);
-            let declared = is_declared(db, h, date);
-            let done = declared && counted >= h.target.quantity;
+            let counted = idx.counted_quantity(h, date);
+            let done = idx.day_met(h, date);
 
             // Only include if not done
             if !done {
@@ -89,8 +75,10 @@ pub fn build_due(
                 });
             }
         } else {
-            // Weekly habit
-            let counted_sum = week_sum_for_habit(db, h, &week_start)?;
+            // Weekly or monthly habit
+            let period = h.target.period.as_str();
+            let start = window_start(period, date)?;
+            let (_, counted_sum) = window_sum(&idx, h, period, &start)?;
             let done = counted_sum >= h.target.quantity;
 
             // Only include if not done
@@ -99,7 +87,7 @@ pub fn build_due(
                 due_rows.push(DueHabitRow {
                     id: h.id.clone(),
                     name: h.name.clone(),
-                    period: "week".to_string(),
+                    period: period.to_string(),
                     target: h.target.quantity,
                     quantity: counted_sum,
                     remaining,
@@ -74,9 +74,3 @@ pub fn excuse(
 
     Ok((ex, used2, remaining2))
 }
-
-pub fn has_allowed_excuse(db: &Db, habit_id: &str, date: &str) -> bool {
-    db.excuses
-        .iter()
-        .any(|e| e.habit_id == habit_id && e.date == date && e.kind == ExcuseKind::Allowed)
-}
@@ -1,6 +1,12 @@
+use crate::checkins::{list_checkins_in_range, list_events_in_range};
 use crate::error::CliError;
-use crate::model::{Checkin, Habit};
+use crate::habits::list_habits;
+use crate::model::{
+    Checkin, CheckinEvent, Db, Declaration, Excuse, Habit, Meta, Nag, PenaltyAction, PenaltyDebt,
+    PenaltyRule, Routine, RoutineAction, RoutineSession, RoutineStep,
+};
 use crate::schedule::schedule_to_string;
+use std::collections::BTreeSet;
 use std::fs;
 use std::io::Write;
 use std::path::Path;
@@ -8,6 +14,121 @@ use std::path::Path;
 #[cfg(unix)]
 use std::os::unix::fs::PermissionsExt;
 
+/// Version of the `habit export --format json` payload.
+///
+/// 1: habits + check-ins only. 2: every collection, plus `meta` and `nag`.
+pub const EXPORT_VERSION: u32 = 2;
+
+/// `habit export --format json` output, and `habit import` input.
+///
+/// Everything but the journal: undo history only makes sense in the DB that
+/// recorded it.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct ExportPayload {
+    pub version: u32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub meta: Option<Meta>,
+    pub habits: Vec<Habit>,
+    /// Per-day totals derived from `checkin_events`; informational only.
+    #[serde(default)]
+    pub checkins: Vec<Checkin>,
+    #[serde(default)]
+    pub checkin_events: Vec<CheckinEvent>,
+    #[serde(default)]
+    pub declarations: Vec<Declaration>,
+    #[serde(default)]
+    pub excuses: Vec<Excuse>,
+    #[serde(default)]
+    pub penalty_rules: Vec<PenaltyRule>,
+    #[serde(default)]
+    pub penalty_debts: Vec<PenaltyDebt>,
+    #[serde(default)]
+    pub penalty_actions: Vec<PenaltyAction>,
+    #[serde(default)]
+    pub routines: Vec<Routine>,
+    #[serde(default)]
+    pub routine_sessions: Vec<RoutineSession>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub nag: Option<Nag>,
+}
+
+fn in_range(date: &str, from: Option<&str>, to: Option<&str>) -> bool {
+    from.is_none_or(|f| date >= f) && to.is_none_or(|t| date <= t)
+}
+
+/// Builds the JSON export.
+///
+/// `from`/`to` filter every dated record (check-ins, declarations, excuses,
+/// debts by trigger date, routine sessions). Records that belong to an
+/// excluded habit, routine or debt are excluded with it.
+pub fn build_export(
+    db: &Db,
+    from: Option<&str>,
+    to: Option<&str>,
+    include_archived: bool,
+) -> ExportPayload {
+    let habits = list_habits(db, include_archived);
+    let habit_ids: BTreeSet<String> = habits.iter().map(|h| h.id.clone()).collect();
+    let has_habit = |id: &String| habit_ids.contains(id);
+
+    let penalty_debts: Vec<PenaltyDebt> = db
+        .penalty_debts
+        .iter()
+        .filter(|d| has_habit(&d.habit_id) && in_range(&d.trigger_date, from, to))
+        .cloned()
+        .collect();
+    let debt_ids: BTreeSet<&str> = penalty_debts.iter().map(|d| d.id.as_str()).collect();
+
+    let routines: Vec<Routine> = db
+        .routines
+        .iter()
+        .filter(|r| include_archived || !r.archived)
+        .cloned()
+        .collect();
+    let routine_ids: BTreeSet<&str> = routines.iter().map(|r| r.id.as_str()).collect();
+
+    ExportPayload {
+        version: EXPORT_VERSION,
+        meta: Some(db.meta.clone()),
+        checkins: list_checkins_in_range(db, from, to, Some(&habit_ids)),
+        checkin_events: list_events_in_range(db, from, to, Some(&habit_ids)),
+        declarations: db
+            .declarations
+            .iter()
+            .filter(|d| has_habit(&d.habit_id) && in_range(&d.date, from, to))
+            .cloned()
+            .collect(),
+        excuses: db
+            .excuses
+            .iter()
+            .filter(|e| has_habit(&e.habit_id) && in_range(&e.date, from, to))
+            .cloned()
+            .collect(),
+        penalty_rules: db
+            .penalty_rules
+            .iter()
+            .filter(|r| has_habit(&r.habit_id))
+            .cloned()
+            .collect(),
+        penalty_actions: db
+            .penalty_actions
+            .iter()
+            .filter(|a| debt_ids.contains(a.debt_id.as_str()))
+            .cloned()
+            .collect(),
+        routine_sessions: db
+            .routine_sessions
+            .iter()
+            .filter(|s| routine_ids.contains(s.routine_id.as_str()) && in_range(&s.date, from, to))
+            .cloned()
+            .collect(),
+        penalty_debts,
+        routines,
+        habits,
+        nag: Some(db.nag.clone()),
+    }
+}
+
 fn csv_escape(value: &str) -> String {
     if value.contains(['\n', '\r', '"', ',']) {
         format!("\"{}\"", value.replace('"', "\"\""))
@@ -24,11 +145,54 @@ fn to_csv_line(values: &[String]) -> String {
         .join(",")
 }
 
-pub fn export_csv_to_dir(
-    out_dir: &str,
-    habits: &[Habit],
-    checkins: &[Checkin],
+fn bool_str(b: bool) -> String {
+    if b {
+        "true".to_string()
+    } else {
+        "false".to_string()
+    }
+}
+
+fn opt_str(v: &Option<String>) -> String {
+    v.clone().unwrap_or_default()
+}
+
+/// snake_case name of a serde enum variant (`allowed`, `resolve`, ...).
+fn enum_str<T: serde::Serialize>(v: &T) -> String {
+    serde_json::to_value(v)
+        .ok()
+        .and_then(|v| v.as_str().map(|s| s.to_string()))
+        .unwrap_or_default()
+}
+
+fn write_csv(
+    out_path: &Path,
+    file_name: &str,
+    header: &[&str],
+    rows: Vec<Vec<String>>,
 ) -> Result<(), CliError> {
+    let header: Vec<String> = header.iter().map(|s| s.to_string()).collect();
+    let mut lines: Vec<String> = Vec::with_capacity(rows.len() + 1);
+    lines.push(to_csv_line(&header));
+    for r in rows.iter() {
+        lines.push(to_csv_line(r));
+    }
+
+    let mut f =
+        fs::File::create(out_path.join(file_name)).map_err(|_| CliError::io("DB IO error"))?;
+    #[cfg(unix)]
+    {
+        let _ = f.set_permissions(fs::Permissions::from_mode(0o600));
+    }
+    f.write_all(lines.join("\n").as_bytes())
+        .map_err(|_| CliError::io("DB IO error"))?;
+    let _ = f.write_all(b"\n");
+    Ok(())
+}
+
+/// Writes one CSV file per collection of `payload` (see `build_export` for
+/// filtering). Rows are sorted so repeated exports diff cleanly.
+pub fn export_csv_to_dir(out_dir: &str, payload: &ExportPayload) -> Result<(), CliError> {
     let out_path = Path::new(out_dir);
     fs::create_dir_all(out_path).map_err(|_| CliError::io("DB IO error"))?;
 
@@ -37,80 +201,322 @@ pub fn export_csv_to_dir(
         let _ = fs::set_permissions(out_path, fs::Permissions::from_mode(0o700));
     }
 
-    let habits_header: Vec<String> = vec![
-        "id",
-        "name",
-        "schedule",
-        "period",
-        "target",
-        "notes",
-        "archived",
-        "created_date",
-        "archived_date",
-    ]
-    .into_iter()
-    .map(|s| s.to_string())
-    .collect();
-
-    let mut habit_lines: Vec<String> = Vec::new();
-    habit_lines.push(to_csv_line(&habits_header));
-    for h in habits.iter() {
-        habit_lines.push(to_csv_line(&[
-            h.id.clone(),
-            h.name.clone(),
-            schedule_to_string(&h.schedule),
-            h.target.period.clone(),
-            h.target.quantity.to_string(),
-            h.notes.clone().unwrap_or_default(),
-            if h.archived {
-                "true".to_string()
-            } else {
-                "false".to_string()
-            },
-            h.created_date.clone(),
-            h.archived_date.clone().unwrap_or_default(),
-        ]));
-    }
+    // habits: already in `stable_habit_sort` order
+    write_csv(
+        out_path,
+        "habits.csv",
+        &[
+            "id",
+            "name",
+            "schedule",
+            "period",
+            "target",
+            "notes",
+            "archived",
+            "created_date",
+            "archived_date",
+            "needs_declaration",
+            "excuse_quota_per_week",
+            "kind",
+            "excuse_policy",
+        ],
+        payload
+            .habits
+            .iter()
+            .map(|h| {
+                vec![
+                    h.id.clone(),
+                    h.name.clone(),
+                    schedule_to_string(&h.schedule),
+                    h.target.period.clone(),
+                    h.target.quantity.to_string(),
+                    opt_str(&h.notes),
+                    bool_str(h.archived),
+                    h.created_date.clone(),
+                    opt_str(&h.archived_date),
+                    bool_str(h.needs_declaration),
+                    h.excuse_quota_per_week.to_string(),
+                    enum_str(&h.kind),
+                    enum_str(&h.excuse_policy),
+                ]
+            })
+            .collect(),
+    )?;
 
-    let checkins_header: Vec<String> = vec!["habit_id", "date", "quantity"]
-        .into_iter()
-        .map(|s| s.to_string())
-        .collect();
+    // checkins: derived totals, by (date, habit_id)
+    write_csv(
+        out_path,
+        "checkins.csv",
+        &["habit_id", "date", "quantity"],
+        payload
+            .checkins
+            .iter()
+            .map(|c| vec![c.habit_id.clone(), c.date.clone(), c.quantity.to_string()])
+            .collect(),
+    )?;
 
-    let mut checkin_lines: Vec<String> = Vec::new();
-    checkin_lines.push(to_csv_line(&checkins_header));
-    for c in checkins.iter() {
-        checkin_lines.push(to_csv_line(&[
-            c.habit_id.clone(),
-            c.date.clone(),
-            c.quantity.to_string(),
-        ]));
-    }
+    // checkin events: recording (id) order
+    let mut events: Vec<&CheckinEvent> = payload.checkin_events.iter().collect();
+    events.sort_by(|a, b| a.id.cmp(&b.id));
+    write_csv(
+        out_path,
+        "checkin_events.csv",
+        &[
+            "id", "habit_id", "date", "ts", "kind", "delta", "note", "source",
+        ],
+        events
+            .iter()
+            .map(|e| {
+                vec![
+                    e.id.clone(),
+                    e.habit_id.clone(),
+                    e.date.clone(),
+                    e.ts.clone(),
+                    enum_str(&e.kind),
+                    e.delta.to_string(),
+                    opt_str(&e.note),
+                    e.source.clone(),
+                ]
+            })
+            .collect(),
+    )?;
 
-    let habits_csv = out_path.join("habits.csv");
-    let checkins_csv = out_path.join("checkins.csv");
+    let mut declarations: Vec<&Declaration> = payload.declarations.iter().collect();
+    declarations.sort_by(|a, b| (&a.date, &a.habit_id, &a.id).cmp(&(&b.date, &b.habit_id, &b.id)));
+    write_csv(
+        out_path,
+        "declarations.csv",
+        &["id", "habit_id", "date", "ts", "text"],
+        declarations
+            .iter()
+            .map(|d| {
+                vec![
+                    d.id.clone(),
+                    d.habit_id.clone(),
+                    d.date.clone(),
+                    d.ts.clone(),
+                    d.text.clone(),
+                ]
+            })
+            .collect(),
+    )?;
 
-    {
-        let mut f = fs::File::create(&habits_csv).map_err(|_| CliError::io("DB IO error"))?;
-        #[cfg(unix)]
-        {
-            let _ = f.set_permissions(fs::Permissions::from_mode(0o600));
-        }
-        f.write_all(habit_lines.join("\n").as_bytes())
-            .map_err(|_| CliError::io("DB IO error"))?;
-        let _ = f.write_all(b"\n");
-    }
+    let mut excuses: Vec<&Excuse> = payload.excuses.iter().collect();
+    excuses.sort_by(|a, b| (&a.date, &a.habit_id, &a.id).cmp(&(&b.date, &b.habit_id, &b.id)));
+    write_csv(
+        out_path,
+        "excuses.csv",
+        &["id", "habit_id", "date", "ts", "kind", "reason"],
+        excuses
+            .iter()
+            .map(|e| {
+                vec![
+                    e.id.clone(),
+                    e.habit_id.clone(),
+                    e.date.clone(),
+                    e.ts.clone(),
+                    enum_str(&e.kind),
+                    e.reason.clone(),
+                ]
+            })
+            .collect(),
+    )?;
 
-    {
-        let mut f = fs::File::create(&checkins_csv).map_err(|_| CliError::io("DB IO error"))?;
-        #[cfg(unix)]
-        {
-            let _ = f.set_permissions(fs::Permissions::from_mode(0o600));
+    let mut rules: Vec<&PenaltyRule> = payload.penalty_rules.iter().collect();
+    rules.sort_by(|a, b| a.id.cmp(&b.id));
+    write_csv(
+        out_path,
+        "penalty_rules.csv",
+        &[
+            "id",
+            "habit_id",
+            "multiplier",
+            "cap",
+            "deadline_days",
+            "armed_date",
+            "armed_ts",
+            "disarmed_date",
+            "pauses",
+        ],
+        rules
+            .iter()
+            .map(|r| {
+                vec![
+                    r.id.clone(),
+                    r.habit_id.clone(),
+                    r.multiplier.to_string(),
+                    r.cap.to_string(),
+                    r.deadline_days.to_string(),
+                    r.armed_date.clone(),
+                    r.armed_ts.clone(),
+                    r.disarmed_date.clone().unwrap_or_default(),
+                    r.pauses
+                        .iter()
+                        .map(|p| format!("{}..{}", p.from, p.to))
+                        .collect::<Vec<_>>()
+                        .join(";"),
+                ]
+            })
+            .collect(),
+    )?;
+
+    let mut debts: Vec<&PenaltyDebt> = payload.penalty_debts.iter().collect();
+    debts.sort_by(|a, b| (&a.trigger_date, &a.id).cmp(&(&b.trigger_date, &b.id)));
+    write_csv(
+        out_path,
+        "penalty_debts.csv",
+        &[
+            "id",
+            "habit_id",
+            "trigger_date",
+            "due_date",
+            "deadline_date",
+            "quantity",
+            "rule_id",
+            "created_date",
+            "created_ts",
+        ],
+        debts
+            .iter()
+            .map(|d| {
+                vec![
+                    d.id.clone(),
+                    d.habit_id.clone(),
+                    d.trigger_date.clone(),
+                    d.due_date.clone(),
+                    d.deadline().to_string(),
+                    d.quantity.to_string(),
+                    d.rule_id.clone(),
+                    d.created_date.clone(),
+                    d.created_ts.clone(),
+                ]
+            })
+            .collect(),
+    )?;
+
+    let mut actions: Vec<&PenaltyAction> = payload.penalty_actions.iter().collect();
+    actions.sort_by(|a, b| (&a.date, &a.id).cmp(&(&b.date, &b.id)));
+    write_csv(
+        out_path,
+        "penalty_actions.csv",
+        &["id", "debt_id", "kind", "date", "ts", "reason", "quantity"],
+        actions
+            .iter()
+            .map(|a| {
+                vec![
+                    a.id.clone(),
+                    a.debt_id.clone(),
+                    enum_str(&a.kind),
+                    a.date.clone(),
+                    a.ts.clone(),
+                    a.reason.clone(),
+                    a.quantity.map(|q| q.to_string()).unwrap_or_default(),
+                ]
+            })
+            .collect(),
+    )?;
+
+    let mut routines: Vec<&Routine> = payload.routines.iter().collect();
+    routines.sort_by(|a, b| a.id.cmp(&b.id));
+    write_csv(
+        out_path,
+        "routines.csv",
+        &[
+            "id",
+            "name",
+            "at",
+            "archived",
+            "created_date",
+            "archived_date",
+        ],
+        routines
+            .iter()
+            .map(|r| {
+                vec![
+                    r.id.clone(),
+                    r.name.clone(),
+                    opt_str(&r.at),
+                    bool_str(r.archived),
+                    r.created_date.clone(),
+                    opt_str(&r.archived_date),
+                ]
+            })
+            .collect(),
+    )?;
+
+    let mut steps: Vec<(&str, &RoutineStep)> = routines
+        .iter()
+        .flat_map(|r| r.steps.iter().map(move |s| (r.id.as_str(), s)))
+        .collect();
+    steps.sort_by(|a, b| (a.0, a.1.index).cmp(&(b.0, b.1.index)));
+    write_csv(
+        out_path,
+        "routine_steps.csv",
+        &["routine_id", "index", "name", "minutes", "quote"],
+        steps
+            .iter()
+            .map(|(routine_id, s)| {
+                vec![
+                    routine_id.to_string(),
+                    s.index.to_string(),
+                    s.name.clone(),
+                    s.minutes.to_string(),
+                    opt_str(&s.quote),
+                ]
+            })
+            .collect(),
+    )?;
+
+    let mut sessions: Vec<&RoutineSession> = payload.routine_sessions.iter().collect();
+    sessions.sort_by(|a, b| (&a.date, &a.started_ts, &a.id).cmp(&(&b.date, &b.started_ts, &b.id)));
+    write_csv(
+        out_path,
+        "routine_sessions.csv",
+        &[
+            "id",
+            "routine_id",
+            "routine_name",
+            "date",
+            "started_ts",
+            "state",
+        ],
+        sessions
+            .iter()
+            .map(|s| {
+                vec![
+                    s.id.clone(),
+                    s.routine_id.clone(),
+                    s.routine_name.clone(),
+                    s.date.clone(),
+                    s.started_ts.clone(),
+                    enum_str(&s.state),
+                ]
+            })
+            .collect(),
+    )?;
+
+    // session actions: per session (in session order), in the order they happened
+    let mut session_actions: Vec<Vec<String>> = Vec::new();
+    for s in sessions.iter() {
+        let mut acts: Vec<&RoutineAction> = s.actions.iter().collect();
+        acts.sort_by(|a, b| (&a.ts, &a.id).cmp(&(&b.ts, &b.id)));
+        for a in acts {
+            session_actions.push(vec![
+                s.id.clone(),
+                a.id.clone(),
+                enum_str(&a.kind),
+                a.ts.clone(),
+                a.step_index.map(|i| i.to_string()).unwrap_or_default(),
+                opt_str(&a.reason),
+            ]);
         }
-        f.write_all(checkin_lines.join("\n").as_bytes())
-            .map_err(|_| CliError::io("DB IO error"))?;
-        let _ = f.write_all(b"\n");
     }
+    write_csv(
+        out_path,
+        "routine_session_actions.csv",
+        &["session_id", "id", "kind", "ts", "step_index", "reason"],
+        session_actions,
+    )?;
 
     Ok(())
 }
@@ -1,7 +1,6 @@
-use crate::date::iso_weekday;
 use crate::error::CliError;
-use crate::model::{Db, Habit, Target};
-use crate::schedule::{parse_schedule_pattern, validate_schedule, Schedule};
+use crate::model::{Db, ExcusePolicy, Habit, HabitKind, Target};
+use crate::schedule::{parse_schedule_pattern, schedule_matches, validate_schedule, Schedule};
 
 fn normalize_name(name: &str) -> String {
     name.trim().to_string()
@@ -118,12 +117,13 @@ pub fn make_habit(
     today: &str,
     needs_declaration: bool,
     excuse_quota_per_week: u32,
+    kind: HabitKind,
 ) -> Result<Habit, CliError> {
     let habit_name = validate_habit_name(name)?;
-    let schedule: Schedule = parse_schedule_pattern(schedule_pattern)?;
+    let schedule: Schedule = parse_schedule_pattern(schedule_pattern)?.with_default_anchor(today);
     validate_schedule(&schedule)?;
 
-    if period != "day" && period != "week" {
+    if period != "day" && period != "week" && period != "month" {
         return Err(CliError::usage(format!("Invalid period: {}", period)));
     }
 
@@ -147,13 +147,26 @@ pub fn make_habit(
         archived_date: None,
         needs_declaration,
         excuse_quota_per_week,
+        kind,
+        excuse_policy: ExcusePolicy::default(),
     })
 }
 
+/// Target label for tables, e.g. `3/week` or `<=2/day` for limit habits.
+pub fn target_label(habit: &Habit) -> String {
+    let prefix = match habit.kind {
+        HabitKind::Goal => "",
+        HabitKind::Limit => "<=",
+    };
+    format!(
+        "{}{}/{}",
+        prefix, habit.target.quantity, habit.target.period
+    )
+}
+
 pub fn is_scheduled_on(habit: &Habit, date: &str) -> Result<bool, CliError> {
     if date < habit.created_date.as_str() {
         return Ok(false);
     }
-    let wd = iso_weekday(date)?;
-    Ok(habit.schedule.days.contains(&wd))
+    schedule_matches(&habit.schedule, date)
 }
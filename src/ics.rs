@@ -0,0 +1,384 @@
+//! `habit export --format ics`: habits and routines as an iCalendar feed.
+//!
+//! Every habit and routine is one recurring VEVENT; every day with check-ins
+//! and every routine session is a VTODO linked to it (`RELATED-TO`), marked
+//! `COMPLETED` when the day met its target or the session finished.
+//!
+//! The output only depends on the exported data: UIDs are derived from ids,
+//! `DTSTAMP` from the record's own dates, and components are written in id
+//! and date order, so exporting twice yields the same bytes.
+
+use crate::completion::CompletionIndex;
+use crate::date::{add_days, days_between};
+use crate::export::ExportPayload;
+use crate::habits::target_label;
+use crate::model::{default_db, Habit, Routine, RoutineSession, RoutineSessionState};
+use crate::schedule::schedule_matches;
+use chrono::{DateTime, NaiveTime, Utc};
+use std::collections::BTreeMap;
+
+const UID_DOMAIN: &str = "habit-cli";
+
+/// Longest gap between two occurrences of a weekday or day-of-month schedule
+/// (`monthly:31`); `every_n_days` is computed directly.
+const FIRST_OCCURRENCE_SEARCH_DAYS: i32 = 62;
+
+const WEEKDAYS: [&str; 7] = ["MO", "TU", "WE", "TH", "FR", "SA", "SU"];
+
+fn uid(key: &str) -> String {
+    format!("{}@{}", key.replace(':', "-"), UID_DOMAIN)
+}
+
+fn ics_date(date: &str) -> String {
+    date.replace('-', "")
+}
+
+/// RFC 3339 timestamp as a UTC `DATE-TIME` (`20260104T080000Z`).
+fn ics_utc(ts: &str) -> Option<String> {
+    DateTime::parse_from_rfc3339(ts)
+        .ok()
+        .map(|t| t.with_timezone(&Utc).format("%Y%m%dT%H%M%SZ").to_string())
+}
+
+fn midnight_utc(date: &str) -> String {
+    format!("{}T000000Z", ics_date(date))
+}
+
+/// TEXT value escaping (RFC 5545 3.3.11).
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace("\r\n", "\\n")
+        .replace('\n', "\\n")
+}
+
+/// Appends one content line, folded at 75 octets without splitting a
+/// UTF-8 sequence.
+fn push_line(out: &mut String, line: &str) {
+    let mut width = 0;
+    for c in line.chars() {
+        if width + c.len_utf8() > 75 {
+            out.push_str("\r\n ");
+            width = 1;
+        }
+        out.push(c);
+        width += c.len_utf8();
+    }
+    out.push_str("\r\n");
+}
+
+/// RRULE for a habit schedule.
+fn rrule(habit: &Habit) -> String {
+    let s = &habit.schedule;
+    let mut rule = match s.kind.as_str() {
+        "every_n_days" => format!("FREQ=DAILY;INTERVAL={}", s.every.unwrap_or(1)),
+        "days_of_month" => {
+            let mut days: Vec<String> = s.days.iter().map(|d| d.to_string()).collect();
+            if s.last_day {
+                days.push("-1".to_string());
+            }
+            format!("FREQ=MONTHLY;BYMONTHDAY={}", days.join(","))
+        }
+        _ if s.days.len() == 7 => "FREQ=DAILY".to_string(),
+        _ => {
+            let days: Vec<&str> = s
+                .days
+                .iter()
+                .filter_map(|d| WEEKDAYS.get(usize::from(*d).wrapping_sub(1)).copied())
+                .collect();
+            format!("FREQ=WEEKLY;BYDAY={}", days.join(","))
+        }
+    };
+    if let Some(until) = habit.archived_date.as_deref() {
+        rule.push_str(&format!(";UNTIL={}", ics_date(until)));
+    }
+    rule
+}
+
+/// First scheduled date on or after the habit's start; DTSTART has to be an
+/// occurrence of the RRULE.
+fn first_occurrence(habit: &Habit) -> String {
+    let s = &habit.schedule;
+    let created = habit.created_date.as_str();
+    if let ("every_n_days", Some(anchor)) = (s.kind.as_str(), s.anchor.as_deref()) {
+        // anchor + ceil((created - anchor) / n) * n
+        let n = s.every.unwrap_or(1).max(1) as i32;
+        return match days_between(anchor, created) {
+            Ok(gap) if gap > 0 => add_days(anchor, (gap + n - 1) / n * n),
+            _ => Ok(anchor.to_string()),
+        }
+        .unwrap_or_else(|_| created.to_string());
+    }
+    (0..=FIRST_OCCURRENCE_SEARCH_DAYS)
+        .filter_map(|i| add_days(created, i).ok())
+        .find(|d| schedule_matches(s, d).unwrap_or(false))
+        .unwrap_or_else(|| created.to_string())
+}
+
+fn push_habit(out: &mut String, habit: &Habit) {
+    push_line(out, "BEGIN:VEVENT");
+    push_line(out, &format!("UID:{}", uid(&habit.id)));
+    push_line(
+        out,
+        &format!("DTSTAMP:{}", midnight_utc(&habit.created_date)),
+    );
+    push_line(out, &format!("SUMMARY:{}", escape(&habit.name)));
+    let mut description = format!("Target: {}", target_label(habit));
+    if let Some(notes) = habit.notes.as_deref() {
+        description.push_str(&format!("\n{}", notes));
+    }
+    push_line(out, &format!("DESCRIPTION:{}", escape(&description)));
+    push_line(
+        out,
+        &format!("DTSTART;VALUE=DATE:{}", ics_date(&first_occurrence(habit))),
+    );
+    push_line(out, &format!("RRULE:{}", rrule(habit)));
+    push_line(out, "TRANSP:TRANSPARENT");
+    push_line(out, "END:VEVENT");
+}
+
+fn push_routine(out: &mut String, routine: &Routine) {
+    let minutes: u32 = routine.steps.iter().map(|s| s.minutes).sum();
+    let at = routine
+        .at
+        .as_deref()
+        .and_then(|a| NaiveTime::parse_from_str(a.trim(), "%H:%M").ok());
+
+    push_line(out, "BEGIN:VEVENT");
+    push_line(out, &format!("UID:{}", uid(&routine.id)));
+    push_line(
+        out,
+        &format!("DTSTAMP:{}", midnight_utc(&routine.created_date)),
+    );
+    push_line(out, &format!("SUMMARY:{}", escape(&routine.name)));
+    let steps: Vec<String> = routine
+        .steps
+        .iter()
+        .map(|s| format!("{}. {} ({} min)", s.index, s.name, s.minutes))
+        .collect();
+    if !steps.is_empty() {
+        push_line(out, &format!("DESCRIPTION:{}", escape(&steps.join("\n"))));
+    }
+    match at {
+        // floating local time: the calendar app shows it at that wall-clock time
+        Some(t) => {
+            push_line(
+                out,
+                &format!(
+                    "DTSTART:{}T{}",
+                    ics_date(&routine.created_date),
+                    t.format("%H%M%S")
+                ),
+            );
+            push_line(out, &format!("DURATION:PT{}M", minutes));
+        }
+        None => push_line(
+            out,
+            &format!("DTSTART;VALUE=DATE:{}", ics_date(&routine.created_date)),
+        ),
+    }
+    let mut rule = "FREQ=DAILY".to_string();
+    if let Some(until) = routine.archived_date.as_deref() {
+        // UNTIL has to match DTSTART's value type
+        let suffix = if at.is_some() { "T235959" } else { "" };
+        rule.push_str(&format!(";UNTIL={}{}", ics_date(until), suffix));
+    }
+    push_line(out, &format!("RRULE:{}", rule));
+    push_line(out, "END:VEVENT");
+}
+
+fn push_session(out: &mut String, session: &RoutineSession) {
+    let done = session.state == RoutineSessionState::Done;
+    let finished = session
+        .steps
+        .iter()
+        .filter_map(|s| s.action_ts.as_deref().and_then(ics_utc))
+        .max()
+        .or_else(|| ics_utc(&session.started_ts))
+        .unwrap_or_else(|| midnight_utc(&session.date));
+
+    push_line(out, "BEGIN:VTODO");
+    push_line(out, &format!("UID:{}", uid(&session.id)));
+    push_line(out, &format!("DTSTAMP:{}", finished));
+    push_line(out, &format!("SUMMARY:{}", escape(&session.routine_name)));
+    push_line(
+        out,
+        &format!("DTSTART;VALUE=DATE:{}", ics_date(&session.date)),
+    );
+    push_line(out, &format!("RELATED-TO:{}", uid(&session.routine_id)));
+    if done {
+        push_line(out, "STATUS:COMPLETED");
+        push_line(out, &format!("COMPLETED:{}", finished));
+    } else {
+        push_line(out, "STATUS:IN-PROCESS");
+    }
+    push_line(out, "END:VTODO");
+}
+
+/// The export as an iCalendar document (CRLF line endings).
+pub fn build_ics(payload: &ExportPayload) -> String {
+    let mut db = default_db();
+    db.checkin_events = payload.checkin_events.clone();
+    db.declarations = payload.declarations.clone();
+    let idx = CompletionIndex::new(&db);
+
+    // habit_id -> date -> latest check-in (UTC)
+    let mut last_ts: BTreeMap<(&str, &str), String> = BTreeMap::new();
+    for e in payload.checkin_events.iter() {
+        if let Some(ts) = ics_utc(&e.ts) {
+            let slot = last_ts
+                .entry((e.habit_id.as_str(), e.date.as_str()))
+                .or_default();
+            if ts > *slot {
+                *slot = ts;
+            }
+        }
+    }
+
+    let mut out = String::new();
+    push_line(&mut out, "BEGIN:VCALENDAR");
+    push_line(&mut out, "VERSION:2.0");
+    push_line(&mut out, "PRODID:-//habit-cli//habit export//EN");
+    push_line(&mut out, "CALSCALE:GREGORIAN");
+    push_line(&mut out, "X-WR-CALNAME:Habits");
+
+    let mut habits: Vec<&Habit> = payload.habits.iter().collect();
+    habits.sort_by(|a, b| a.id.cmp(&b.id));
+    for habit in habits.iter() {
+        push_habit(&mut out, habit);
+    }
+
+    let mut days: Vec<(&str, &str, u32)> = payload
+        .checkins
+        .iter()
+        .filter(|c| c.quantity > 0)
+        .map(|c| (c.habit_id.as_str(), c.date.as_str(), c.quantity))
+        .collect();
+    days.sort();
+    for (habit_id, date, quantity) in days {
+        let Some(habit) = habits.iter().find(|h| h.id == habit_id) else {
+            continue;
+        };
+        // week/month targets are met across days, so every check-in day counts
+        let completed = habit.target.period != "day" || idx.day_met(habit, date);
+        let stamp = last_ts
+            .get(&(habit_id, date))
+            .cloned()
+            .unwrap_or_else(|| midnight_utc(date));
+
+        push_line(&mut out, "BEGIN:VTODO");
+        push_line(
+            &mut out,
+            &format!("UID:{}", uid(&format!("{}-{}", habit_id, ics_date(date)))),
+        );
+        push_line(&mut out, &format!("DTSTAMP:{}", stamp));
+        push_line(&mut out, &format!("SUMMARY:{}", escape(&habit.name)));
+        push_line(
+            &mut out,
+            &format!(
+                "DESCRIPTION:{}",
+                escape(&format!("{} of {}", quantity, target_label(habit)))
+            ),
+        );
+        push_line(&mut out, &format!("DTSTART;VALUE=DATE:{}", ics_date(date)));
+        push_line(&mut out, &format!("RELATED-TO:{}", uid(habit_id)));
+        if completed {
+            push_line(&mut out, "STATUS:COMPLETED");
+            push_line(&mut out, &format!("COMPLETED:{}", stamp));
+        } else {
+            push_line(&mut out, "STATUS:IN-PROCESS");
+        }
+        push_line(&mut out, "END:VTODO");
+    }
+
+    let mut routines: Vec<&Routine> = payload.routines.iter().collect();
+    routines.sort_by(|a, b| a.id.cmp(&b.id));
+    for routine in routines {
+        push_routine(&mut out, routine);
+    }
+    let mut sessions: Vec<&RoutineSession> = payload.routine_sessions.iter().collect();
+    sessions.sort_by(|a, b| (&a.date, &a.id).cmp(&(&b.date, &b.id)));
+    for session in sessions {
+        push_session(&mut out, session);
+    }
+
+    push_line(&mut out, "END:VCALENDAR");
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::habits::make_habit;
+    use crate::model::HabitKind;
+
+    fn habit(id: &str, schedule: &str, period: &str, target: u32) -> Habit {
+        make_habit(
+            id.to_string(),
+            "Water",
+            schedule,
+            period,
+            target,
+            None,
+            "2026-01-01",
+            false,
+            0,
+            HabitKind::Goal,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn schedules_map_to_rrules_starting_on_an_occurrence() {
+        // 2026-01-01 is a Thursday
+        let cases = [
+            ("everyday", "FREQ=DAILY", "2026-01-01"),
+            ("mon,wed", "FREQ=WEEKLY;BYDAY=MO,WE", "2026-01-05"),
+            (
+                "monthly:15,last",
+                "FREQ=MONTHLY;BYMONTHDAY=15,-1",
+                "2026-01-15",
+            ),
+            ("every:3d@2026-01-10", "FREQ=DAILY;INTERVAL=3", "2026-01-10"),
+        ];
+        for (pattern, rule, start) in cases {
+            let h = habit("h0001", pattern, "day", 1);
+            assert_eq!(rrule(&h), rule, "{}", pattern);
+            assert_eq!(first_occurrence(&h), start, "{}", pattern);
+        }
+
+        let mut archived = habit("h0001", "weekdays", "day", 1);
+        archived.archived_date = Some("2026-02-01".to_string());
+        assert_eq!(
+            rrule(&archived),
+            "FREQ=WEEKLY;BYDAY=MO,TU,WE,TH,FR;UNTIL=20260201"
+        );
+    }
+
+    #[test]
+    fn long_intervals_start_on_the_next_occurrence_after_creation() {
+        let mut h = habit("h0001", "every:90d@2026-01-01", "day", 1);
+        h.created_date = "2026-01-05".to_string();
+        let mut out = String::new();
+        push_habit(&mut out, &h);
+        assert!(out.contains("DTSTART;VALUE=DATE:20260401\r\n"), "{}", out);
+
+        h.schedule.every = Some(400);
+        assert_eq!(first_occurrence(&h), "2027-02-05");
+        h.created_date = "2026-01-01".to_string();
+        assert_eq!(first_occurrence(&h), "2026-01-01");
+    }
+
+    #[test]
+    fn lines_are_escaped_and_folded() {
+        assert_eq!(escape("a,b;c\\d\ne"), "a\\,b\\;c\\\\d\\ne");
+
+        let mut out = String::new();
+        push_line(&mut out, &format!("SUMMARY:{}", "é".repeat(60)));
+        let lines: Vec<&str> = out.trim_end_matches("\r\n").split("\r\n").collect();
+        assert_eq!(lines.len(), 2);
+        assert!(lines.iter().all(|l| l.len() <= 75));
+        assert!(lines[1].starts_with(' '));
+    }
+}
@@ -0,0 +1,599 @@
+//! `habit import`: loads a `habit export --format json` payload.
+//!
+//! Into an empty DB the payload is restored as-is (ids kept). Into a DB that
+//! already has data it is merged: habits and routines are matched by name
+//! (case-insensitive), everything else gets fresh ids from the target's
+//! counters, in payload order, so the same payload merged into the same DB
+//! always produces the same ids. Records the target already has (same
+//! content, ignoring ids) are skipped, so re-importing a file is a no-op.
+
+use crate::checkins::next_checkin_event_id;
+use crate::declarations::next_declaration_id;
+use crate::error::CliError;
+use crate::excuses::next_excuse_id;
+use crate::export::{ExportPayload, EXPORT_VERSION};
+use crate::habits::next_habit_id;
+use crate::model::{default_db, CheckinEvent, CheckinEventKind, Db, PenaltyRule};
+use crate::penalty::{debt_id_for, next_penalty_rule_id};
+use crate::routines::{next_routine_id, session_number_for};
+use std::collections::{BTreeMap, BTreeSet};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ImportMode {
+    Restore,
+    Merge,
+}
+
+/// What kind of file was read.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ImportSource {
+    /// `habit export --format json`
+    Json,
+    /// Loop Habit Tracker CSV export
+    Loop,
+    /// Any CSV with one row per check-in, mapped by column
+    Csv,
+}
+
+/// Where an imported habit or routine ended up.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct IdMapping {
+    pub from: String,
+    pub to: String,
+    pub name: String,
+    /// True when an existing record with the same name was reused.
+    pub matched: bool,
+}
+
+#[derive(Debug, Clone, Copy, Default, serde::Serialize)]
+pub struct CollectionCounts {
+    pub added: u32,
+    /// Already present in the target (or, for penalty rules, the habit already has one).
+    pub skipped: u32,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct ImportReport {
+    pub source: ImportSource,
+    pub mode: ImportMode,
+    /// Payload version that was read (JSON exports only).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub version: Option<u32>,
+    pub collections: BTreeMap<String, CollectionCounts>,
+    pub habits: Vec<IdMapping>,
+    pub routines: Vec<IdMapping>,
+    /// Source columns that were not carried over (CSV imports only).
+    pub unmapped: Vec<String>,
+    pub notes: Vec<String>,
+}
+
+/// Parses an export file, upgrading older payload versions.
+pub fn parse_payload(text: &str) -> Result<ExportPayload, CliError> {
+    let mut payload: ExportPayload = serde_json::from_str(text)
+        .map_err(|e| CliError::usage(format!("Invalid import file: {}", e)))?;
+
+    if payload.version < 1 || payload.version > EXPORT_VERSION {
+        return Err(CliError::usage(format!(
+            "Unsupported export version: {}",
+            payload.version
+        )));
+    }
+
+    // v1 files written before the event log only carry per-day totals.
+    if payload.checkin_events.is_empty() && !payload.checkins.is_empty() {
+        payload.checkin_events = payload
+            .checkins
+            .iter()
+            .filter(|c| c.quantity > 0)
+            .enumerate()
+            .map(|(i, c)| CheckinEvent {
+                id: format!("c{:06}", i + 1),
+                habit_id: c.habit_id.clone(),
+                date: c.date.clone(),
+                ts: format!("{}T00:00:00Z", c.date),
+                kind: CheckinEventKind::Add,
+                delta: i64::from(c.quantity),
+                note: None,
+                source: "import".to_string(),
+            })
+            .collect();
+    }
+
+    Ok(payload)
+}
+
+fn payload_db(payload: &ExportPayload, base: &Db) -> Db {
+    let mut db = default_db();
+    db.meta = payload.meta.clone().unwrap_or_else(|| base.meta.clone());
+    db.habits = payload.habits.clone();
+    db.checkin_events = payload.checkin_events.clone();
+    db.declarations = payload.declarations.clone();
+    db.excuses = payload.excuses.clone();
+    db.penalty_rules = payload.penalty_rules.clone();
+    db.penalty_debts = payload.penalty_debts.clone();
+    db.penalty_actions = payload.penalty_actions.clone();
+    db.routines = payload.routines.clone();
+    db.routine_sessions = payload.routine_sessions.clone();
+    db.nag = payload.nag.clone().unwrap_or_else(|| base.nag.clone());
+    db
+}
+
+/// Rejects payloads with dangling references or repeated ids.
+fn validate(payload: &Db) -> Result<(), CliError> {
+    let problem = crate::integrity::check(payload)
+        .into_iter()
+        .find(|i| i.kind != "counter_behind");
+    match problem {
+        Some(i) => Err(CliError::usage(format!(
+            "Invalid import file: {} {}: {}",
+            i.collection, i.id, i.detail
+        ))),
+        None => Ok(()),
+    }
+}
+
+fn is_empty(db: &Db) -> bool {
+    db.habits.is_empty()
+        && db.checkin_events.is_empty()
+        && db.declarations.is_empty()
+        && db.excuses.is_empty()
+        && db.penalty_rules.is_empty()
+        && db.penalty_debts.is_empty()
+        && db.penalty_actions.is_empty()
+        && db.routines.is_empty()
+        && db.routine_sessions.is_empty()
+}
+
+const COLLECTIONS: [&str; 9] = [
+    "habits",
+    "checkin_events",
+    "declarations",
+    "excuses",
+    "penalty_rules",
+    "penalty_debts",
+    "penalty_actions",
+    "routines",
+    "routine_sessions",
+];
+
+pub(crate) fn count(report: &mut ImportReport, collection: &str, added: bool) {
+    let c = report
+        .collections
+        .entry(collection.to_string())
+        .or_default();
+    if added {
+        c.added += 1;
+    } else {
+        c.skipped += 1;
+    }
+}
+
+/// Imports `payload` into `db` and reports what was (or, on a scratch copy
+/// for `--dry-run`, would be) added.
+pub fn import(db: &mut Db, payload: &ExportPayload) -> Result<ImportReport, CliError> {
+    let incoming = payload_db(payload, db);
+    validate(&incoming)?;
+
+    let mut report = ImportReport {
+        source: ImportSource::Json,
+        mode: if is_empty(db) {
+            ImportMode::Restore
+        } else {
+            ImportMode::Merge
+        },
+        version: Some(payload.version),
+        collections: COLLECTIONS
+            .iter()
+            .map(|c| (c.to_string(), CollectionCounts::default()))
+            .collect(),
+        habits: Vec::new(),
+        routines: Vec::new(),
+        unmapped: Vec::new(),
+        notes: Vec::new(),
+    };
+
+    match report.mode {
+        ImportMode::Restore => restore(db, incoming, &mut report),
+        ImportMode::Merge => merge(db, incoming, &mut report),
+    }
+    Ok(report)
+}
+
+fn restore(db: &mut Db, incoming: Db, report: &mut ImportReport) {
+    let sizes = [
+        incoming.habits.len(),
+        incoming.checkin_events.len(),
+        incoming.declarations.len(),
+        incoming.excuses.len(),
+        incoming.penalty_rules.len(),
+        incoming.penalty_debts.len(),
+        incoming.penalty_actions.len(),
+        incoming.routines.len(),
+        incoming.routine_sessions.len(),
+    ];
+    for (collection, n) in COLLECTIONS.iter().zip(sizes) {
+        report.collections.insert(
+            collection.to_string(),
+            CollectionCounts {
+                added: n as u32,
+                skipped: 0,
+            },
+        );
+    }
+    report.habits = incoming
+        .habits
+        .iter()
+        .map(|h| IdMapping {
+            from: h.id.clone(),
+            to: h.id.clone(),
+            name: h.name.clone(),
+            matched: false,
+        })
+        .collect();
+    report.routines = incoming
+        .routines
+        .iter()
+        .map(|r| IdMapping {
+            from: r.id.clone(),
+            to: r.id.clone(),
+            name: r.name.clone(),
+            matched: false,
+        })
+        .collect();
+
+    let journal = std::mem::take(&mut db.journal);
+    *db = incoming;
+    db.journal = journal;
+    crate::integrity::advance_counters(db);
+}
+
+/// Lowest-id existing record whose name matches case-insensitively.
+pub(crate) fn match_by_name<'a>(
+    rows: impl Iterator<Item = (&'a str, &'a str)>,
+    name: &str,
+) -> Option<String> {
+    let wanted = name.to_lowercase();
+    rows.filter(|(_, n)| n.to_lowercase() == wanted)
+        .map(|(id, _)| id)
+        .min()
+        .map(|id| id.to_string())
+}
+
+fn merge(db: &mut Db, incoming: Db, report: &mut ImportReport) {
+    // habits
+    let mut habit_map: BTreeMap<String, String> = BTreeMap::new();
+    for h in incoming.habits.iter() {
+        let existing = match_by_name(
+            db.habits.iter().map(|x| (x.id.as_str(), x.name.as_str())),
+            &h.name,
+        );
+        let matched = existing.is_some();
+        let to = match existing {
+            Some(id) => id,
+            None => {
+                let mut habit = h.clone();
+                habit.id = next_habit_id(db);
+                let id = habit.id.clone();
+                db.habits.push(habit);
+                id
+            }
+        };
+        count(report, "habits", !matched);
+        habit_map.insert(h.id.clone(), to.clone());
+        report.habits.push(IdMapping {
+            from: h.id.clone(),
+            to,
+            name: h.name.clone(),
+            matched,
+        });
+    }
+    let habit = |id: &str| habit_map.get(id).cloned().unwrap_or_default();
+
+    // check-in events (same habit/date/ts/kind/delta/note/source = same event)
+    type EventKey = (String, String, String, String, i64, Option<String>, String);
+    let event_key = |e: &CheckinEvent| -> EventKey {
+        (
+            e.habit_id.clone(),
+            e.date.clone(),
+            e.ts.clone(),
+            format!("{:?}", e.kind),
+            e.delta,
+            e.note.clone(),
+            e.source.clone(),
+        )
+    };
+    let mut seen: BTreeSet<EventKey> = db.checkin_events.iter().map(event_key).collect();
+    for e in incoming.checkin_events.iter() {
+        let mut ev = e.clone();
+        ev.habit_id = habit(&e.habit_id);
+        let added = seen.insert(event_key(&ev));
+        if added {
+            ev.id = next_checkin_event_id(db);
+            db.checkin_events.push(ev);
+        }
+        count(report, "checkin_events", added);
+    }
+
+    // declarations
+    let mut seen: BTreeSet<(String, String, String, String)> = db
+        .declarations
+        .iter()
+        .map(|d| {
+            (
+                d.habit_id.clone(),
+                d.date.clone(),
+                d.ts.clone(),
+                d.text.clone(),
+            )
+        })
+        .collect();
+    for d in incoming.declarations.iter() {
+        let mut decl = d.clone();
+        decl.habit_id = habit(&d.habit_id);
+        let added = seen.insert((
+            decl.habit_id.clone(),
+            decl.date.clone(),
+            decl.ts.clone(),
+            decl.text.clone(),
+        ));
+        if added {
+            decl.id = next_declaration_id(db);
+            db.declarations.push(decl);
+        }
+        count(report, "declarations", added);
+    }
+
+    // excuses
+    let mut seen: BTreeSet<(String, String, String, String, String)> = db
+        .excuses
+        .iter()
+        .map(|e| {
+            (
+                e.habit_id.clone(),
+                e.date.clone(),
+                e.ts.clone(),
+                format!("{:?}", e.kind),
+                e.reason.clone(),
+            )
+        })
+        .collect();
+    for e in incoming.excuses.iter() {
+        let mut ex = e.clone();
+        ex.habit_id = habit(&e.habit_id);
+        let added = seen.insert((
+            ex.habit_id.clone(),
+            ex.date.clone(),
+            ex.ts.clone(),
+            format!("{:?}", ex.kind),
+            ex.reason.clone(),
+        ));
+        if added {
+            ex.id = next_excuse_id(db);
+            db.excuses.push(ex);
+        }
+        count(report, "excuses", added);
+    }
+
+    // penalty rules: a habit's whole version history comes in only if the
+    // habit has no rule yet; otherwise the existing history wins
+    let mut rule_map: BTreeMap<String, String> = BTreeMap::new();
+    let mut incoming_rules: Vec<&PenaltyRule> = incoming.penalty_rules.iter().collect();
+    incoming_rules.sort_by(|a, b| a.id.cmp(&b.id));
+    let armed_before: BTreeSet<String> = db
+        .penalty_rules
+        .iter()
+        .map(|r| r.habit_id.clone())
+        .collect();
+    for r in incoming_rules {
+        let habit_id = habit(&r.habit_id);
+        let existing = if armed_before.contains(&habit_id) {
+            db.penalty_rules
+                .iter()
+                .filter(|x| x.habit_id == habit_id)
+                .map(|x| x.id.clone())
+                .max()
+        } else {
+            None
+        };
+        let added = existing.is_none();
+        let to = match existing {
+            Some(id) => {
+                report.notes.push(format!(
+                    "penalty rule {} skipped: habit {} already has rule {}",
+                    r.id, habit_id, id
+                ));
+                id
+            }
+            None => {
+                let mut rule = r.clone();
+                rule.id = next_penalty_rule_id(db);
+                rule.habit_id = habit_id;
+                let id = rule.id.clone();
+                db.penalty_rules.push(rule);
+                id
+            }
+        };
+        rule_map.insert(r.id.clone(), to);
+        count(report, "penalty_rules", added);
+    }
+
+    // penalty debts and actions (ids derive from habit/date and debt/kind)
+    let mut debt_map: BTreeMap<String, String> = BTreeMap::new();
+    for d in incoming.penalty_debts.iter() {
+        let mut debt = d.clone();
+        debt.habit_id = habit(&d.habit_id);
+        // keep the id's tail (day, ISO week, `_x` escalation); swap the habit
+        debt.id = match d.id.strip_prefix(&format!("pd_{}_", d.habit_id)) {
+            Some(tail) => format!("pd_{}_{}", debt.habit_id, tail),
+            None => debt_id_for(&debt.habit_id, &debt.trigger_date),
+        };
+        debt.rule_id = rule_map.get(&d.rule_id).cloned().unwrap_or_default();
+        debt_map.insert(d.id.clone(), debt.id.clone());
+        let added = !db.penalty_debts.iter().any(|x| x.id == debt.id);
+        if added {
+            db.penalty_debts.push(debt);
+        }
+        count(report, "penalty_debts", added);
+    }
+    for a in incoming.penalty_actions.iter() {
+        let mut action = a.clone();
+        action.debt_id = debt_map.get(&a.debt_id).cloned().unwrap_or_default();
+        // payments carry a date suffix after the debt id
+        action.id = match a.id.strip_prefix(&format!("pa_{}_", a.debt_id)) {
+            Some(tail) => format!("pa_{}_{}", action.debt_id, tail),
+            None => crate::penalty::action_id_for(&action.debt_id, action.kind),
+        };
+        let added = !db.penalty_actions.iter().any(|x| x.id == action.id);
+        if added {
+            db.penalty_actions.push(action);
+        }
+        count(report, "penalty_actions", added);
+    }
+
+    // routines
+    let mut routine_map: BTreeMap<String, String> = BTreeMap::new();
+    for r in incoming.routines.iter() {
+        let existing = match_by_name(
+            db.routines.iter().map(|x| (x.id.as_str(), x.name.as_str())),
+            &r.name,
+        );
+        let matched = existing.is_some();
+        let to = match existing {
+            Some(id) => id,
+            None => {
+                let mut routine = r.clone();
+                routine.id = next_routine_id(db);
+                let id = routine.id.clone();
+                db.routines.push(routine);
+                id
+            }
+        };
+        count(report, "routines", !matched);
+        routine_map.insert(r.id.clone(), to.clone());
+        report.routines.push(IdMapping {
+            from: r.id.clone(),
+            to,
+            name: r.name.clone(),
+            matched,
+        });
+    }
+
+    // routine sessions (same routine/date/started_ts = same session, as in `routine start`)
+    for s in incoming.routine_sessions.iter() {
+        let routine_id = routine_map.get(&s.routine_id).cloned().unwrap_or_default();
+        let added = !db.routine_sessions.iter().any(|x| {
+            x.routine_id == routine_id && x.date == s.date && x.started_ts == s.started_ts
+        });
+        if added {
+            let mut session = s.clone();
+            let n = session_number_for(db, &routine_id, &s.date);
+            session.id = format!("rs:{}:{}:{}", routine_id, s.date, n);
+            session.routine_id = routine_id;
+            for a in session.actions.iter_mut() {
+                a.id = crate::routines::action_id_for(&session.id, a.kind, &a.ts);
+            }
+            db.routine_sessions.push(session);
+        }
+        count(report, "routine_sessions", added);
+    }
+
+    if incoming.meta.timezone != db.meta.timezone || incoming.meta.day_start != db.meta.day_start {
+        report
+            .notes
+            .push("timezone/day_start kept from the target DB".to_string());
+    }
+    if serde_json::to_value(&incoming.nag).ok() != serde_json::to_value(&db.nag).ok() {
+        report
+            .notes
+            .push("nag settings kept from the target DB".to_string());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::checkins::{add_quantity, EventInfo};
+    use crate::export::build_export;
+    use crate::habits::make_habit;
+    use crate::model::HabitKind;
+
+    fn sample(names: &[&str]) -> Db {
+        let mut db = default_db();
+        let info = EventInfo {
+            ts: "2026-01-02T08:00:00Z".to_string(),
+            note: None,
+            source: "cli".to_string(),
+        };
+        for name in names {
+            let id = next_habit_id(&mut db);
+            let h = make_habit(
+                id.clone(),
+                name,
+                "everyday",
+                "day",
+                1,
+                None,
+                "2026-01-01",
+                false,
+                2,
+                HabitKind::Goal,
+            )
+            .unwrap();
+            db.habits.push(h);
+            add_quantity(&mut db, &id, "2026-01-02", 1, &info).unwrap();
+        }
+        db
+    }
+
+    #[test]
+    fn restore_keeps_ids_and_roundtrips() {
+        let src = sample(&["Water", "Read"]);
+        let payload = build_export(&src, None, None, true);
+
+        let mut dst = default_db();
+        let report = import(&mut dst, &payload).unwrap();
+        assert_eq!(report.mode, ImportMode::Restore);
+        assert_eq!(
+            serde_json::to_value(build_export(&dst, None, None, true)).unwrap(),
+            serde_json::to_value(&payload).unwrap()
+        );
+    }
+
+    #[test]
+    fn merge_remaps_deterministically_and_is_idempotent() {
+        let src = sample(&["Water", "Read"]);
+        let payload = build_export(&src, None, None, true);
+
+        let mut dst = sample(&["Stretch", "water"]);
+        let report = import(&mut dst, &payload).unwrap();
+        assert_eq!(report.mode, ImportMode::Merge);
+        let mapped: Vec<(&str, &str, bool)> = report
+            .habits
+            .iter()
+            .map(|m| (m.from.as_str(), m.to.as_str(), m.matched))
+            .collect();
+        assert_eq!(
+            mapped,
+            vec![("h0002", "h0003", false), ("h0001", "h0002", true)]
+        );
+        // Water's event is identical except for its id, so it is skipped
+        assert_eq!(report.collections["checkin_events"].added, 1);
+        assert_eq!(report.collections["checkin_events"].skipped, 1);
+        assert_eq!(dst.checkin_events.last().unwrap().id, "c000003");
+        assert_eq!(dst.checkin_events.last().unwrap().habit_id, "h0003");
+
+        let again = import(&mut dst, &payload).unwrap();
+        assert!(again.collections.values().all(|c| c.added == 0));
+    }
+
+    #[test]
+    fn rejects_dangling_references_and_future_versions() {
+        let mut payload = build_export(&sample(&["Water"]), None, None, true);
+        payload.checkin_events[0].habit_id = "h0042".to_string();
+        assert!(import(&mut default_db(), &payload).is_err());
+
+        assert!(parse_payload(r#"{"version": 99, "habits": []}"#).is_err());
+    }
+}
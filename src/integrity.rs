@@ -0,0 +1,487 @@
+use crate::checkins::next_checkin_event_id;
+use crate::declarations::next_declaration_id;
+use crate::excuses::next_excuse_id;
+use crate::model::{CheckinEventKind, Db};
+use std::collections::BTreeSet;
+
+/// One integrity violation found by `habit db check`.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+pub struct Issue {
+    /// `orphan_reference`, `duplicate_id`, `duplicate_checkin` or `counter_behind`.
+    pub kind: String,
+    /// DB collection (or `meta`) holding the offending record.
+    pub collection: String,
+    /// Id of the offending record (counter name for `counter_behind`).
+    pub id: String,
+    pub detail: String,
+    /// Whether `--repair` fixes this kind of issue.
+    pub repairable: bool,
+    pub repaired: bool,
+}
+
+fn issue(kind: &str, collection: &str, id: &str, detail: String, repairable: bool) -> Issue {
+    Issue {
+        kind: kind.to_string(),
+        collection: collection.to_string(),
+        id: id.to_string(),
+        detail,
+        repairable,
+        repaired: false,
+    }
+}
+
+fn orphans<'a>(
+    out: &mut Vec<Issue>,
+    collection: &str,
+    field: &str,
+    known: &BTreeSet<&str>,
+    rows: impl Iterator<Item = (&'a str, &'a str)>,
+) {
+    for (id, target) in rows {
+        if !known.contains(target) {
+            out.push(issue(
+                "orphan_reference",
+                collection,
+                id,
+                format!("{} {} does not exist", field, target),
+                false,
+            ));
+        }
+    }
+}
+
+/// Ids referenced elsewhere can't be renumbered safely; the rest can.
+fn duplicates<'a>(
+    out: &mut Vec<Issue>,
+    collection: &str,
+    repairable: bool,
+    ids: impl Iterator<Item = &'a str>,
+) {
+    let mut seen: BTreeSet<&str> = BTreeSet::new();
+    for id in ids {
+        if !seen.insert(id) {
+            out.push(issue(
+                "duplicate_id",
+                collection,
+                id,
+                format!("id {} is used more than once", id),
+                repairable,
+            ));
+        }
+    }
+}
+
+fn id_number(id: &str, prefix: &str) -> Option<u32> {
+    id.strip_prefix(prefix)
+        .filter(|n| !n.is_empty() && n.chars().all(|c| c.is_ascii_digit()))
+        .and_then(|n| n.parse::<u32>().ok())
+}
+
+fn counter(
+    out: &mut Vec<Issue>,
+    name: &str,
+    next: u32,
+    prefix: &str,
+    ids: impl Iterator<Item = String>,
+) {
+    let max = ids.filter_map(|id| id_number(&id, prefix)).max();
+    if let Some(max) = max {
+        if next <= max {
+            out.push(issue(
+                "counter_behind",
+                "meta",
+                name,
+                format!("{} is {} but id {}{} exists", name, next, prefix, max),
+                true,
+            ));
+        }
+    }
+}
+
+/// Indexes (into `checkin_events`) of migrated rows that duplicate an earlier
+/// migrated row for the same (habit_id, date). v1 only ever read the first one.
+fn duplicate_migrated_checkins(db: &Db) -> Vec<usize> {
+    let mut seen: BTreeSet<(&str, &str)> = BTreeSet::new();
+    let mut out: Vec<usize> = Vec::new();
+    for (i, e) in db.checkin_events.iter().enumerate() {
+        if e.source != "migration" || e.kind != CheckinEventKind::Add {
+            continue;
+        }
+        if !seen.insert((e.habit_id.as_str(), e.date.as_str())) {
+            out.push(i);
+        }
+    }
+    out
+}
+
+pub fn check(db: &Db) -> Vec<Issue> {
+    let mut out: Vec<Issue> = Vec::new();
+
+    let habits: BTreeSet<&str> = db.habits.iter().map(|h| h.id.as_str()).collect();
+    let routines: BTreeSet<&str> = db.routines.iter().map(|r| r.id.as_str()).collect();
+    let rules: BTreeSet<&str> = db.penalty_rules.iter().map(|r| r.id.as_str()).collect();
+    let debts: BTreeSet<&str> = db.penalty_debts.iter().map(|d| d.id.as_str()).collect();
+
+    orphans(
+        &mut out,
+        "checkin_events",
+        "habit",
+        &habits,
+        db.checkin_events
+            .iter()
+            .map(|e| (e.id.as_str(), e.habit_id.as_str())),
+    );
+    orphans(
+        &mut out,
+        "declarations",
+        "habit",
+        &habits,
+        db.declarations
+            .iter()
+            .map(|d| (d.id.as_str(), d.habit_id.as_str())),
+    );
+    orphans(
+        &mut out,
+        "excuses",
+        "habit",
+        &habits,
+        db.excuses
+            .iter()
+            .map(|e| (e.id.as_str(), e.habit_id.as_str())),
+    );
+    orphans(
+        &mut out,
+        "penalty_rules",
+        "habit",
+        &habits,
+        db.penalty_rules
+            .iter()
+            .map(|r| (r.id.as_str(), r.habit_id.as_str())),
+    );
+    orphans(
+        &mut out,
+        "penalty_debts",
+        "habit",
+        &habits,
+        db.penalty_debts
+            .iter()
+            .map(|d| (d.id.as_str(), d.habit_id.as_str())),
+    );
+    orphans(
+        &mut out,
+        "penalty_debts",
+        "penalty rule",
+        &rules,
+        db.penalty_debts
+            .iter()
+            .map(|d| (d.id.as_str(), d.rule_id.as_str())),
+    );
+    orphans(
+        &mut out,
+        "penalty_actions",
+        "penalty debt",
+        &debts,
+        db.penalty_actions
+            .iter()
+            .map(|a| (a.id.as_str(), a.debt_id.as_str())),
+    );
+    orphans(
+        &mut out,
+        "routine_sessions",
+        "routine",
+        &routines,
+        db.routine_sessions
+            .iter()
+            .map(|s| (s.id.as_str(), s.routine_id.as_str())),
+    );
+
+    duplicates(
+        &mut out,
+        "habits",
+        false,
+        db.habits.iter().map(|h| h.id.as_str()),
+    );
+    duplicates(
+        &mut out,
+        "checkin_events",
+        true,
+        db.checkin_events.iter().map(|e| e.id.as_str()),
+    );
+    duplicates(
+        &mut out,
+        "declarations",
+        true,
+        db.declarations.iter().map(|d| d.id.as_str()),
+    );
+    duplicates(
+        &mut out,
+        "excuses",
+        true,
+        db.excuses.iter().map(|e| e.id.as_str()),
+    );
+    duplicates(
+        &mut out,
+        "penalty_rules",
+        false,
+        db.penalty_rules.iter().map(|r| r.id.as_str()),
+    );
+    duplicates(
+        &mut out,
+        "penalty_debts",
+        false,
+        db.penalty_debts.iter().map(|d| d.id.as_str()),
+    );
+    duplicates(
+        &mut out,
+        "penalty_actions",
+        false,
+        db.penalty_actions.iter().map(|a| a.id.as_str()),
+    );
+    duplicates(
+        &mut out,
+        "routines",
+        false,
+        db.routines.iter().map(|r| r.id.as_str()),
+    );
+    duplicates(
+        &mut out,
+        "routine_sessions",
+        false,
+        db.routine_sessions.iter().map(|s| s.id.as_str()),
+    );
+
+    for i in duplicate_migrated_checkins(db) {
+        let e = &db.checkin_events[i];
+        out.push(issue(
+            "duplicate_checkin",
+            "checkin_events",
+            &e.id,
+            format!(
+                "habit {} on {} had more than one v1 check-in row (only the first was counted)",
+                e.habit_id, e.date
+            ),
+            true,
+        ));
+    }
+
+    let m = &db.meta;
+    counter(
+        &mut out,
+        "next_habit_number",
+        m.next_habit_number,
+        "h",
+        db.habits.iter().map(|h| h.id.clone()),
+    );
+    counter(
+        &mut out,
+        "next_checkin_number",
+        m.next_checkin_number,
+        "c",
+        db.checkin_events.iter().map(|e| e.id.clone()),
+    );
+    counter(
+        &mut out,
+        "next_declaration_number",
+        m.next_declaration_number,
+        "d",
+        db.declarations.iter().map(|d| d.id.clone()),
+    );
+    counter(
+        &mut out,
+        "next_excuse_number",
+        m.next_excuse_number,
+        "e",
+        db.excuses.iter().map(|e| e.id.clone()),
+    );
+    counter(
+        &mut out,
+        "next_penalty_rule_number",
+        m.next_penalty_rule_number,
+        "pr",
+        db.penalty_rules.iter().map(|r| r.id.clone()),
+    );
+    counter(
+        &mut out,
+        "next_routine_number",
+        m.next_routine_number,
+        "r",
+        db.routines.iter().map(|r| r.id.clone()),
+    );
+
+    out
+}
+
+fn bump(next: &mut u32, prefix: &str, ids: impl Iterator<Item = String>) {
+    if let Some(max) = ids.filter_map(|id| id_number(&id, prefix)).max() {
+        if *next <= max {
+            *next = max + 1;
+        }
+    }
+}
+
+/// Moves every `meta.next_*_number` counter past the highest existing id.
+pub fn advance_counters(db: &mut Db) {
+    let m = &mut db.meta;
+    bump(
+        &mut m.next_habit_number,
+        "h",
+        db.habits.iter().map(|h| h.id.clone()),
+    );
+    bump(
+        &mut m.next_checkin_number,
+        "c",
+        db.checkin_events.iter().map(|e| e.id.clone()),
+    );
+    bump(
+        &mut m.next_declaration_number,
+        "d",
+        db.declarations.iter().map(|d| d.id.clone()),
+    );
+    bump(
+        &mut m.next_excuse_number,
+        "e",
+        db.excuses.iter().map(|e| e.id.clone()),
+    );
+    bump(
+        &mut m.next_penalty_rule_number,
+        "pr",
+        db.penalty_rules.iter().map(|r| r.id.clone()),
+    );
+    bump(
+        &mut m.next_routine_number,
+        "r",
+        db.routines.iter().map(|r| r.id.clone()),
+    );
+}
+
+/// Gives every repeat of an already-seen id a fresh one.
+fn renumber<T>(
+    db: &mut Db,
+    rows: fn(&mut Db) -> &mut Vec<T>,
+    id_of: fn(&mut T) -> &mut String,
+    next_id: fn(&mut Db) -> String,
+) {
+    let mut seen: BTreeSet<String> = BTreeSet::new();
+    let mut dupes: Vec<usize> = Vec::new();
+    for (i, row) in rows(db).iter_mut().enumerate() {
+        if !seen.insert(id_of(row).clone()) {
+            dupes.push(i);
+        }
+    }
+    for i in dupes {
+        let id = next_id(db);
+        *id_of(&mut rows(db)[i]) = id;
+    }
+}
+
+/// Fixes the repairable issues in place (deterministically) and returns the
+/// issues found before repair, with `repaired` set on the ones fixed.
+pub fn repair(db: &mut Db) -> Vec<Issue> {
+    let mut issues = check(db);
+
+    // 1) drop v1 duplicate rows (keeps totals as v1 computed them)
+    let drop: BTreeSet<usize> = duplicate_migrated_checkins(db).into_iter().collect();
+    let mut i = 0;
+    db.checkin_events.retain(|_| {
+        let keep = !drop.contains(&i);
+        i += 1;
+        keep
+    });
+
+    // 2) counters past every existing id, so fresh ids below can't collide
+    advance_counters(db);
+
+    // 3) unreferenced records with repeated ids get fresh ids
+    renumber(
+        db,
+        |db| &mut db.checkin_events,
+        |e| &mut e.id,
+        next_checkin_event_id,
+    );
+    renumber(
+        db,
+        |db| &mut db.declarations,
+        |d| &mut d.id,
+        next_declaration_id,
+    );
+    renumber(db, |db| &mut db.excuses, |e| &mut e.id, next_excuse_id);
+
+    let remaining: BTreeSet<(String, String, String)> = check(db)
+        .into_iter()
+        .map(|i| (i.kind, i.collection, i.id))
+        .collect();
+    for i in issues.iter_mut() {
+        if !i.repairable {
+            continue;
+        }
+        let key = (i.kind.clone(), i.collection.clone(), i.id.clone());
+        i.repaired = !remaining.contains(&key);
+    }
+
+    issues
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{default_db, CheckinEvent, Declaration};
+
+    fn event(id: &str, habit_id: &str, date: &str, source: &str) -> CheckinEvent {
+        CheckinEvent {
+            id: id.to_string(),
+            habit_id: habit_id.to_string(),
+            date: date.to_string(),
+            ts: format!("{}T00:00:00Z", date),
+            kind: CheckinEventKind::Add,
+            delta: 1,
+            note: None,
+            source: source.to_string(),
+        }
+    }
+
+    #[test]
+    fn clean_db_has_no_issues() {
+        assert!(check(&default_db()).is_empty());
+    }
+
+    #[test]
+    fn repair_fixes_safe_cases_only() {
+        let mut db = default_db();
+        db.checkin_events = vec![
+            event("c000001", "h0001", "2026-01-01", "migration"),
+            event("c000002", "h0001", "2026-01-01", "migration"),
+            event("c000003", "h0001", "2026-01-02", "cli"),
+            event("c000003", "h0001", "2026-01-03", "cli"),
+        ];
+        db.declarations.push(Declaration {
+            id: "d000001".to_string(),
+            habit_id: "h0009".to_string(),
+            date: "2026-01-01".to_string(),
+            ts: "2026-01-01T00:00:00Z".to_string(),
+            text: "x".to_string(),
+        });
+
+        let kinds: Vec<(String, String)> = check(&db).into_iter().map(|i| (i.kind, i.id)).collect();
+        assert!(kinds.contains(&("orphan_reference".to_string(), "c000001".to_string())));
+        assert!(kinds.contains(&("orphan_reference".to_string(), "d000001".to_string())));
+        assert!(kinds.contains(&("duplicate_id".to_string(), "c000003".to_string())));
+        assert!(kinds.contains(&("duplicate_checkin".to_string(), "c000002".to_string())));
+        assert!(kinds.contains(&(
+            "counter_behind".to_string(),
+            "next_checkin_number".to_string()
+        )));
+
+        let issues = repair(&mut db);
+        assert!(issues.iter().all(|i| i.repaired == i.repairable));
+
+        let ids: Vec<&str> = db.checkin_events.iter().map(|e| e.id.as_str()).collect();
+        assert_eq!(ids, vec!["c000001", "c000003", "c000004"]);
+        assert_eq!(db.meta.next_checkin_number, 5);
+        assert_eq!(db.meta.next_declaration_number, 2);
+
+        // orphans are left for a human (the habit might be restorable)
+        let left: Vec<String> = check(&db).into_iter().map(|i| i.kind).collect();
+        assert!(left.iter().all(|k| k == "orphan_reference"));
+        assert_eq!(left.len(), 4);
+    }
+}
@@ -0,0 +1,553 @@
+use crate::date::system_today;
+use crate::error::CliError;
+use crate::model::{Db, JournalChange, JournalEntry, JournalOp};
+use serde_json::Value;
+use std::collections::BTreeSet;
+use std::sync::OnceLock;
+
+/// Oldest entries beyond this are dropped (and can no longer be undone).
+pub const JOURNAL_LIMIT: usize = 100;
+
+static COMMAND_LABEL: OnceLock<String> = OnceLock::new();
+
+/// The invocation's `--ts` and `--today` override, stamped on its entry.
+static COMMAND_TIME: OnceLock<(Option<String>, Option<String>)> = OnceLock::new();
+
+/// Global options that take a value; they describe *how* a command ran, not what it did.
+const GLOBAL_VALUE_OPTIONS: &[&str] =
+    &["--db", "--today", "--format", "--lock-timeout", "--storage"];
+const GLOBAL_FLAGS: &[&str] = &["--no-color"];
+
+pub fn set_command_label(label: String) {
+    let _ = COMMAND_LABEL.set(label);
+}
+
+pub fn set_command_time(ts: Option<String>, today: Option<String>) {
+    let _ = COMMAND_TIME.set((ts, today));
+}
+
+/// The `--ts` value in argv, if the command was given one.
+pub fn command_ts_from_args(args: &[String]) -> Option<String> {
+    let mut it = args.iter();
+    while let Some(a) = it.next() {
+        if a == "--ts" {
+            return it.next().map(|t| t.trim().to_string());
+        }
+        if let Some(t) = a.strip_prefix("--ts=") {
+            return Some(t.trim().to_string());
+        }
+    }
+    None
+}
+
+/// An entry's `ts`: the command's `--ts`, else midnight UTC of its today
+/// (so replaying a command with the same arguments yields the same history).
+fn entry_ts(db: &Db) -> Result<String, CliError> {
+    let (ts, today) = match COMMAND_TIME.get() {
+        Some((ts, today)) => (ts.clone(), today.clone()),
+        None => (None, None),
+    };
+    if let Some(ts) = ts {
+        return Ok(ts);
+    }
+    let today = match today {
+        Some(t) => t,
+        None => system_today(db.meta.timezone.as_deref(), &db.meta.day_start)?,
+    };
+    Ok(format!("{}T00:00:00Z", today))
+}
+
+fn quote_arg(a: &str) -> String {
+    if a.is_empty() || a.chars().any(|c| c.is_whitespace() || c == '"') {
+        format!("\"{}\"", a.replace('"', "\\\""))
+    } else {
+        a.to_string()
+    }
+}
+
+/// Renders argv (without the program name) as the journal's `command`.
+pub fn command_label_from_args(args: &[String]) -> String {
+    let mut out: Vec<String> = Vec::new();
+    let mut i = 0;
+    while i < args.len() {
+        let a = args[i].as_str();
+        if GLOBAL_FLAGS.contains(&a) {
+            i += 1;
+        } else if GLOBAL_VALUE_OPTIONS.contains(&a) {
+            i += 2;
+        } else if GLOBAL_VALUE_OPTIONS
+            .iter()
+            .any(|o| a.starts_with(o) && a[o.len()..].starts_with('='))
+        {
+            i += 1;
+        } else {
+            out.push(quote_arg(a));
+            i += 1;
+        }
+    }
+    out.join(" ")
+}
+
+/// The DB as JSON, minus the journal itself.
+pub fn snapshot(db: &Db) -> Result<Value, CliError> {
+    let mut v = serde_json::to_value(db).map_err(|_| CliError::io("DB IO error"))?;
+    if let Some(obj) = v.as_object_mut() {
+        obj.remove("journal");
+    }
+    Ok(v)
+}
+
+fn escape_token(k: &str) -> String {
+    k.replace('~', "~0").replace('/', "~1")
+}
+
+fn unescape_token(k: &str) -> String {
+    k.replace("~1", "/").replace("~0", "~")
+}
+
+fn change(op: JournalOp, path: String, before: Value, after: Value) -> JournalChange {
+    JournalChange {
+        op,
+        path,
+        before,
+        after,
+    }
+}
+
+/// `id` of every element, if all elements are objects with distinct string ids.
+fn element_ids(arr: &[Value]) -> Option<Vec<&str>> {
+    let ids: Vec<&str> = arr
+        .iter()
+        .map(|v| v.get("id").and_then(Value::as_str))
+        .collect::<Option<_>>()?;
+    let distinct: BTreeSet<&str> = ids.iter().copied().collect();
+    (distinct.len() == ids.len()).then_some(ids)
+}
+
+/// Diffs two arrays of records by `id`: removed records (descending `before`
+/// index), then added ones (ascending `after` index), then in-place edits.
+/// Returns false (recording nothing) when the arrays aren't keyed or the kept
+/// records were reordered.
+fn diff_keyed(path: &str, b: &[Value], a: &[Value], out: &mut Vec<JournalChange>) -> bool {
+    let (Some(b_ids), Some(a_ids)) = (element_ids(b), element_ids(a)) else {
+        return false;
+    };
+    let b_set: BTreeSet<&str> = b_ids.iter().copied().collect();
+    let a_set: BTreeSet<&str> = a_ids.iter().copied().collect();
+    let kept_before: Vec<&str> = b_ids
+        .iter()
+        .copied()
+        .filter(|id| a_set.contains(id))
+        .collect();
+    let kept_after: Vec<&str> = a_ids
+        .iter()
+        .copied()
+        .filter(|id| b_set.contains(id))
+        .collect();
+    if kept_before != kept_after {
+        return false;
+    }
+
+    for (i, id) in b_ids.iter().enumerate().rev() {
+        if !a_set.contains(id) {
+            out.push(change(
+                JournalOp::Remove,
+                format!("{}/{}", path, i),
+                b[i].clone(),
+                Value::Null,
+            ));
+        }
+    }
+    for (i, id) in a_ids.iter().enumerate() {
+        if !b_set.contains(id) {
+            out.push(change(
+                JournalOp::Add,
+                format!("{}/{}", path, i),
+                Value::Null,
+                a[i].clone(),
+            ));
+        }
+    }
+    let before_index: std::collections::BTreeMap<&str, usize> =
+        b_ids.iter().enumerate().map(|(i, id)| (*id, i)).collect();
+    for (i, id) in a_ids.iter().enumerate() {
+        if let Some(&j) = before_index.get(id) {
+            diff(&format!("{}/{}", path, i), &b[j], &a[i], out);
+        }
+    }
+    true
+}
+
+/// Structural diff as JSON-pointer changes, in replay order.
+///
+/// Arrays of records with an `id` are matched by id, so removing an early
+/// record journals just that record. Other arrays are compared index by
+/// index; tail growth is `add` (ascending), tail shrinkage is `remove`
+/// (descending).
+fn diff(path: &str, before: &Value, after: &Value, out: &mut Vec<JournalChange>) {
+    match (before, after) {
+        (Value::Object(b), Value::Object(a)) => {
+            let keys: BTreeSet<&String> = b.keys().chain(a.keys()).collect();
+            for k in keys {
+                // the journal never journals itself
+                if path.is_empty() && k == "journal" {
+                    continue;
+                }
+                let p = format!("{}/{}", path, escape_token(k));
+                match (b.get(k), a.get(k)) {
+                    (Some(x), Some(y)) => diff(&p, x, y, out),
+                    (Some(x), None) => {
+                        out.push(change(JournalOp::Remove, p, x.clone(), Value::Null))
+                    }
+                    (None, Some(y)) => out.push(change(JournalOp::Add, p, Value::Null, y.clone())),
+                    (None, None) => {}
+                }
+            }
+        }
+        (Value::Array(b), Value::Array(a)) => {
+            if diff_keyed(path, b, a, out) {
+                return;
+            }
+            let common = b.len().min(a.len());
+            for i in 0..common {
+                diff(&format!("{}/{}", path, i), &b[i], &a[i], out);
+            }
+            for (i, y) in a.iter().enumerate().skip(common) {
+                out.push(change(
+                    JournalOp::Add,
+                    format!("{}/{}", path, i),
+                    Value::Null,
+                    y.clone(),
+                ));
+            }
+            for i in (common..b.len()).rev() {
+                out.push(change(
+                    JournalOp::Remove,
+                    format!("{}/{}", path, i),
+                    b[i].clone(),
+                    Value::Null,
+                ));
+            }
+        }
+        _ => {
+            if before != after {
+                out.push(change(
+                    JournalOp::Replace,
+                    path.to_string(),
+                    before.clone(),
+                    after.clone(),
+                ));
+            }
+        }
+    }
+}
+
+fn split_parent(path: &str) -> Option<(&str, String)> {
+    let i = path.rfind('/')?;
+    Some((&path[..i], unescape_token(&path[i + 1..])))
+}
+
+fn insert_at(v: &mut Value, path: &str, val: Value) -> Option<()> {
+    let (parent, key) = split_parent(path)?;
+    match v.pointer_mut(parent)? {
+        Value::Object(m) => {
+            m.insert(key, val);
+            Some(())
+        }
+        Value::Array(arr) => {
+            let i: usize = key.parse().ok()?;
+            if i > arr.len() {
+                return None;
+            }
+            arr.insert(i, val);
+            Some(())
+        }
+        _ => None,
+    }
+}
+
+fn parent_is_array(v: &Value, path: &str) -> bool {
+    split_parent(path)
+        .and_then(|(parent, _)| v.pointer(parent))
+        .is_some_and(Value::is_array)
+}
+
+fn remove_at(v: &mut Value, path: &str) -> Option<()> {
+    let (parent, key) = split_parent(path)?;
+    match v.pointer_mut(parent)? {
+        Value::Object(m) => m.remove(&key).map(|_| ()),
+        Value::Array(arr) => {
+            let i: usize = key.parse().ok()?;
+            if i >= arr.len() {
+                return None;
+            }
+            arr.remove(i);
+            Some(())
+        }
+        _ => None,
+    }
+}
+
+/// Reverts one entry on `v`. Refuses (without touching `v`) if any path no
+/// longer holds the value the entry left there.
+fn revert(v: &mut Value, entry: &JournalEntry) -> Result<(), CliError> {
+    let drifted = |c: &JournalChange| {
+        CliError::usage(format!(
+            "Cannot undo {}: {} changed since",
+            entry.id, c.path
+        ))
+    };
+
+    // Changes are undone newest first, so each is checked against the state
+    // it left behind (array removals shift later elements, so only the
+    // insertion point can be checked for those).
+    let mut next = v.clone();
+    for c in entry.changes.iter().rev() {
+        let intact = match c.op {
+            JournalOp::Add | JournalOp::Replace => next.pointer(&c.path) == Some(&c.after),
+            JournalOp::Remove => next.pointer(&c.path).is_none() || parent_is_array(&next, &c.path),
+        };
+        if !intact {
+            return Err(drifted(c));
+        }
+        let ok = match c.op {
+            JournalOp::Add => remove_at(&mut next, &c.path),
+            JournalOp::Remove => insert_at(&mut next, &c.path, c.before.clone()),
+            JournalOp::Replace => next
+                .pointer_mut(&c.path)
+                .map(|slot| *slot = c.before.clone()),
+        };
+        if ok.is_none() {
+            return Err(drifted(c));
+        }
+    }
+    *v = next;
+    Ok(())
+}
+
+fn next_operation_id(db: &Db) -> String {
+    let n = db
+        .journal
+        .last()
+        .and_then(|e| e.id.strip_prefix("op"))
+        .and_then(|n| n.parse::<u32>().ok())
+        .unwrap_or(0);
+    format!("op{:06}", n + 1)
+}
+
+/// Appends a journal entry for the changes between `before` and `after`
+/// (the DB as JSON before and after the command; their `journal` keys are
+/// ignored). Invocations that change nothing are not recorded.
+pub fn record(db: &mut Db, before: &Value, after: &Value) -> Result<(), CliError> {
+    let mut changes: Vec<JournalChange> = Vec::new();
+    diff("", before, after, &mut changes);
+    if changes.is_empty() {
+        return Ok(());
+    }
+
+    let id = next_operation_id(db);
+    let ts = entry_ts(db)?;
+    // newest first, matching the order `undo` reverted them in
+    let undoes: Vec<String> = db
+        .journal
+        .iter()
+        .rev()
+        .filter(|e| e.undone_by.as_deref() == Some(id.as_str()))
+        .map(|e| e.id.clone())
+        .collect();
+
+    db.journal.push(JournalEntry {
+        id,
+        ts,
+        command: COMMAND_LABEL.get().cloned().unwrap_or_default(),
+        changes,
+        undoes,
+        undone_by: None,
+    });
+
+    if db.journal.len() > JOURNAL_LIMIT {
+        let excess = db.journal.len() - JOURNAL_LIMIT;
+        db.journal.drain(..excess);
+    }
+
+    Ok(())
+}
+
+/// Reverts the `steps` most recent operations that are not undos and not yet undone.
+///
+/// Returns the reverted entries (newest first), already marked `undone_by`.
+pub fn undo(db: &mut Db, steps: usize) -> Result<Vec<JournalEntry>, CliError> {
+    if steps < 1 {
+        return Err(CliError::usage("Invalid steps: must be >= 1"));
+    }
+
+    let targets: Vec<usize> = db
+        .journal
+        .iter()
+        .enumerate()
+        .rev()
+        .filter(|(_, e)| e.undone_by.is_none() && e.undoes.is_empty())
+        .map(|(i, _)| i)
+        .take(steps)
+        .collect();
+    if targets.is_empty() {
+        return Err(CliError::usage("Nothing to undo"));
+    }
+    if targets.len() < steps {
+        return Err(CliError::usage(format!(
+            "Only {} operation(s) can be undone",
+            targets.len()
+        )));
+    }
+
+    let mut v = snapshot(db)?;
+    for &i in targets.iter() {
+        revert(&mut v, &db.journal[i])?;
+    }
+
+    let undo_id = next_operation_id(db);
+    let journal = std::mem::take(&mut db.journal);
+    let mut restored: Db = serde_json::from_value(v).map_err(|_| CliError::io("DB corrupted"))?;
+    restored.journal = journal;
+    *db = restored;
+
+    let mut out: Vec<JournalEntry> = Vec::new();
+    for &i in targets.iter() {
+        db.journal[i].undone_by = Some(undo_id.clone());
+        out.push(db.journal[i].clone());
+    }
+    Ok(out)
+}
+
+/// Most recent entries first.
+pub fn history(db: &Db, limit: usize) -> Vec<JournalEntry> {
+    db.journal.iter().rev().take(limit).cloned().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::default_db;
+
+    fn apply(db: &mut Db, f: impl FnOnce(&mut Db)) {
+        let before = snapshot(db).unwrap();
+        f(db);
+        let after = snapshot(db).unwrap();
+        record(db, &before, &after).unwrap();
+    }
+
+    #[test]
+    fn diff_then_revert_roundtrips() {
+        let before =
+            serde_json::json!({ "a": [1, 2, 3], "b": { "x": "old", "y/z": 1 }, "c": null });
+        let after = serde_json::json!({ "a": [1, 5], "b": { "x": "new", "w": true }, "c": 3 });
+        let mut changes = Vec::new();
+        diff("", &before, &after, &mut changes);
+
+        let paths: Vec<&str> = changes.iter().map(|c| c.path.as_str()).collect();
+        assert_eq!(paths, vec!["/a/1", "/a/2", "/b/w", "/b/x", "/b/y~1z", "/c"]);
+
+        let entry = JournalEntry {
+            id: "op000001".to_string(),
+            ts: String::new(),
+            command: String::new(),
+            changes,
+            undoes: Vec::new(),
+            undone_by: None,
+        };
+        let mut v = after.clone();
+        revert(&mut v, &entry).unwrap();
+        assert_eq!(v, before);
+
+        // a value changed after the operation blocks the revert
+        let mut drifted = after.clone();
+        drifted["b"]["x"] = Value::from("other");
+        assert!(revert(&mut drifted, &entry).is_err());
+        assert_eq!(drifted["b"]["x"], "other");
+    }
+
+    #[test]
+    fn keyed_arrays_diff_by_id() {
+        let rec = |id: &str, n: u32| serde_json::json!({ "id": id, "n": n });
+        let before =
+            serde_json::json!({ "xs": [rec("a", 1), rec("b", 2), rec("c", 3), rec("d", 4)] });
+        let after =
+            serde_json::json!({ "xs": [rec("b", 2), rec("c", 30), rec("e", 5), rec("d", 4)] });
+        let mut changes = Vec::new();
+        diff("", &before, &after, &mut changes);
+
+        // dropping the first record doesn't shift every later one into the entry
+        let summary: Vec<(JournalOp, &str)> =
+            changes.iter().map(|c| (c.op, c.path.as_str())).collect();
+        assert_eq!(
+            summary,
+            vec![
+                (JournalOp::Remove, "/xs/0"),
+                (JournalOp::Add, "/xs/2"),
+                (JournalOp::Replace, "/xs/1/n"),
+            ]
+        );
+
+        let entry = JournalEntry {
+            id: "op000001".to_string(),
+            ts: String::new(),
+            command: String::new(),
+            changes,
+            undoes: Vec::new(),
+            undone_by: None,
+        };
+        let mut v = after.clone();
+        revert(&mut v, &entry).unwrap();
+        assert_eq!(v, before);
+    }
+
+    #[test]
+    fn undo_skips_undo_entries_and_undone_operations() {
+        let mut db = default_db();
+        apply(&mut db, |db| db.meta.day_start = "04:00".to_string());
+        apply(&mut db, |db| db.meta.timezone = Some("UTC".to_string()));
+        assert_eq!(db.journal.len(), 2);
+
+        // no-op invocations are not journaled
+        apply(&mut db, |_| {});
+        assert_eq!(db.journal.len(), 2);
+
+        let before = snapshot(&db).unwrap();
+        let undone = undo(&mut db, 1).unwrap();
+        let after = snapshot(&db).unwrap();
+        record(&mut db, &before, &after).unwrap();
+        assert_eq!(undone[0].id, "op000002");
+        assert!(db.meta.timezone.is_none());
+        assert_eq!(db.journal[2].undoes, vec!["op000002".to_string()]);
+
+        let before = snapshot(&db).unwrap();
+        let undone = undo(&mut db, 1).unwrap();
+        let after = snapshot(&db).unwrap();
+        record(&mut db, &before, &after).unwrap();
+        assert_eq!(undone[0].id, "op000001");
+        assert_eq!(db.meta.day_start, "00:00");
+
+        assert!(undo(&mut db, 1).is_err());
+    }
+
+    #[test]
+    fn global_options_are_stripped_from_labels() {
+        let args: Vec<String> = [
+            "--db",
+            "/tmp/x.json",
+            "checkin",
+            "Morning run",
+            "--format=json",
+            "--set",
+            "0",
+            "--no-color",
+        ]
+        .iter()
+        .map(|s| s.to_string())
+        .collect();
+        assert_eq!(
+            command_label_from_args(&args),
+            "checkin \"Morning run\" --set 0"
+        );
+    }
+}
@@ -0,0 +1,302 @@
+use crate::error::CliError;
+use crate::ts::system_now_rfc3339;
+use std::fs;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant, SystemTime};
+
+#[cfg(unix)]
+use std::os::unix::fs::PermissionsExt;
+
+/// How long to keep retrying a held lock (milliseconds). 0 = fail immediately.
+static LOCK_TIMEOUT_MS: AtomicU64 = AtomicU64::new(0);
+
+/// Lock files that can't be parsed (older builds wrote an empty file) are only
+/// reclaimed once they are this old, so a writer that just created its lock
+/// and hasn't filled in its owner yet is left alone.
+const UNREADABLE_LOCK_GRACE: Duration = Duration::from_secs(30);
+
+/// PIDs get reused, so a lock this old is reclaimed even when its PID is
+/// alive; no write holds the lock anywhere near this long.
+const LIVE_LOCK_MAX_AGE: Duration = Duration::from_secs(600);
+
+/// Immediate retries after a lock vanished or was reclaimed, allowed even
+/// once the timeout has run out (the default timeout is 0).
+const FREE_RETRIES: u32 = 3;
+
+const BACKOFF_START: Duration = Duration::from_millis(10);
+const BACKOFF_MAX: Duration = Duration::from_millis(250);
+
+pub fn set_lock_timeout(timeout: Duration) {
+    LOCK_TIMEOUT_MS.store(timeout.as_millis() as u64, Ordering::Relaxed);
+}
+
+fn lock_timeout() -> Duration {
+    Duration::from_millis(LOCK_TIMEOUT_MS.load(Ordering::Relaxed))
+}
+
+/// Contents of `db.json.lock`.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct LockOwner {
+    pub pid: u32,
+    pub hostname: String,
+    pub ts: String,
+}
+
+impl LockOwner {
+    fn current() -> Self {
+        LockOwner {
+            pid: std::process::id(),
+            hostname: hostname(),
+            ts: system_now_rfc3339(),
+        }
+    }
+}
+
+#[cfg(unix)]
+fn hostname() -> String {
+    let mut buf = [0u8; 256];
+    // SAFETY: buf is valid for buf.len() bytes; gethostname NUL-terminates on success.
+    let rc = unsafe { libc::gethostname(buf.as_mut_ptr() as *mut libc::c_char, buf.len()) };
+    if rc != 0 {
+        return String::new();
+    }
+    let end = buf.iter().position(|b| *b == 0).unwrap_or(buf.len());
+    String::from_utf8_lossy(&buf[..end]).to_string()
+}
+
+#[cfg(not(unix))]
+fn hostname() -> String {
+    std::env::var("COMPUTERNAME").unwrap_or_default()
+}
+
+#[cfg(unix)]
+fn pid_alive(pid: u32) -> bool {
+    let pid = match libc::pid_t::try_from(pid) {
+        Ok(p) if p > 0 => p,
+        _ => return false,
+    };
+    // SAFETY: signal 0 performs permission/existence checks only.
+    let rc = unsafe { libc::kill(pid, 0) };
+    if rc == 0 {
+        return true;
+    }
+    // EPERM: the process exists but belongs to someone else.
+    std::io::Error::last_os_error().raw_os_error() == Some(libc::EPERM)
+}
+
+#[cfg(not(unix))]
+fn pid_alive(_pid: u32) -> bool {
+    true
+}
+
+pub struct WriteLock {
+    path: PathBuf,
+}
+
+impl Drop for WriteLock {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+fn try_create(lock_path: &Path) -> Result<Option<WriteLock>, CliError> {
+    match OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(lock_path)
+    {
+        Ok(mut file) => {
+            #[cfg(unix)]
+            {
+                let _ = file.set_permissions(fs::Permissions::from_mode(0o600));
+            }
+            let guard = WriteLock {
+                path: lock_path.to_path_buf(),
+            };
+            let data = serde_json::to_string(&LockOwner::current())
+                .map_err(|_| CliError::io("DB IO error"))?;
+            file.write_all(data.as_bytes())
+                .map_err(|_| CliError::io("DB IO error"))?;
+            let _ = file.flush();
+            Ok(Some(guard))
+        }
+        Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => Ok(None),
+        Err(_) => Err(CliError::io("DB IO error")),
+    }
+}
+
+enum Holder {
+    /// Lock disappeared between our attempt and the inspection.
+    Gone,
+    Live(Option<LockOwner>),
+    Stale(String),
+}
+
+fn lock_age(lock_path: &Path) -> Duration {
+    fs::metadata(lock_path)
+        .and_then(|m| m.modified())
+        .ok()
+        .and_then(|t| SystemTime::now().duration_since(t).ok())
+        .unwrap_or_default()
+}
+
+fn inspect(lock_path: &Path) -> Holder {
+    let raw = match fs::read_to_string(lock_path) {
+        Ok(raw) => raw,
+        Err(_) => return Holder::Gone,
+    };
+
+    match serde_json::from_str::<LockOwner>(raw.trim()) {
+        Ok(owner) => {
+            // A PID from another host can't be checked; assume it is live
+            // unless the lock is older than any write could be.
+            let dead = owner.hostname == hostname() && !pid_alive(owner.pid);
+            if dead || lock_age(lock_path) >= LIVE_LOCK_MAX_AGE {
+                Holder::Stale(raw)
+            } else {
+                Holder::Live(Some(owner))
+            }
+        }
+        Err(_) => {
+            if lock_age(lock_path) >= UNREADABLE_LOCK_GRACE {
+                Holder::Stale(raw)
+            } else {
+                Holder::Live(None)
+            }
+        }
+    }
+}
+
+/// Removes a stale lock, but only if it still holds the contents we judged
+/// stale (another process may have reclaimed and re-created it meanwhile).
+fn reclaim(lock_path: &Path, stale: &str) -> Result<(), CliError> {
+    if fs::read_to_string(lock_path).ok().as_deref() != Some(stale) {
+        return Ok(());
+    }
+    match fs::remove_file(lock_path) {
+        Ok(()) => Ok(()),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+        Err(e) => Err(CliError::io(format!(
+            "DB is locked by a stale lock that can't be removed ({}): {}",
+            lock_path.display(),
+            e
+        ))),
+    }
+}
+
+fn locked_error(owner: Option<&LockOwner>) -> CliError {
+    match owner {
+        Some(o) => CliError::io(format!(
+            "DB is locked (pid {} on {}, since {})",
+            o.pid, o.hostname, o.ts
+        )),
+        None => CliError::io("DB is locked"),
+    }
+}
+
+/// Acquires `lock_path`, reclaiming locks left behind by dead processes and
+/// retrying with backoff for up to the configured `--lock-timeout`.
+pub fn acquire(lock_path: &Path) -> Result<WriteLock, CliError> {
+    let deadline = Instant::now() + lock_timeout();
+    let mut backoff = BACKOFF_START;
+    let mut free_retries = 0;
+
+    loop {
+        if let Some(guard) = try_create(lock_path)? {
+            return Ok(guard);
+        }
+
+        let owner = match inspect(lock_path) {
+            Holder::Gone => None,
+            Holder::Stale(raw) => {
+                reclaim(lock_path, &raw)?;
+                None
+            }
+            Holder::Live(owner) => owner,
+        };
+        let freed = owner.is_none() && free_retries < FREE_RETRIES;
+        if freed {
+            free_retries += 1;
+            continue;
+        }
+
+        let now = Instant::now();
+        if now >= deadline {
+            return Err(locked_error(owner.as_ref()));
+        }
+        std::thread::sleep(backoff.min(deadline - now));
+        backoff = (backoff * 2).min(BACKOFF_MAX);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dead_owner_lock_is_reclaimed() {
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join("db.json.lock");
+
+        // PIDs this large are never handed out on Linux (pid_max <= 2^22).
+        let dead = LockOwner {
+            pid: 999_999_999,
+            hostname: hostname(),
+            ts: "2026-01-01T00:00:00Z".to_string(),
+        };
+        fs::write(&path, serde_json::to_string(&dead).unwrap()).unwrap();
+
+        let guard = acquire(&path).unwrap();
+        let owner: LockOwner = serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(owner.pid, std::process::id());
+        drop(guard);
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn live_owner_lock_is_respected() {
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join("db.json.lock");
+
+        let live = LockOwner::current();
+        fs::write(&path, serde_json::to_string(&live).unwrap()).unwrap();
+
+        let err = acquire(&path).err().unwrap();
+        assert!(err.message.starts_with("DB is locked"));
+        assert!(path.exists());
+    }
+
+    #[test]
+    fn old_lock_is_reclaimed_even_if_its_pid_is_alive() {
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join("db.json.lock");
+
+        let live = LockOwner::current();
+        fs::write(&path, serde_json::to_string(&live).unwrap()).unwrap();
+        let old = SystemTime::now() - LIVE_LOCK_MAX_AGE - Duration::from_secs(1);
+        fs::File::options()
+            .write(true)
+            .open(&path)
+            .unwrap()
+            .set_modified(old)
+            .unwrap();
+
+        let guard = acquire(&path).unwrap();
+        drop(guard);
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn unreadable_lock_path_times_out() {
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join("db.json.lock");
+        // a directory can be neither created over nor read as a lock file
+        fs::create_dir(&path).unwrap();
+
+        let err = acquire(&path).err().unwrap();
+        assert!(err.message.starts_with("DB is locked"));
+    }
+}
@@ -1,5 +1,7 @@
+mod calendar;
 mod checkins;
 mod completion;
+mod config;
 mod date;
 mod db;
 mod declarations;
@@ -8,39 +10,58 @@ mod error;
 mod excuses;
 mod export;
 mod habits;
+mod ics;
+mod import;
+mod integrity;
+mod journal;
+mod lock;
 mod model;
 mod nag;
 mod output;
 mod penalty;
+mod period;
 mod recap;
+mod report;
 mod routines;
 mod schedule;
+#[cfg(feature = "sqlite")]
+mod sqlite_store;
 mod stable_json;
 mod stats;
 mod status;
+mod storage;
+mod strength;
+mod tracker_import;
 mod ts;
 
 use crate::checkins::{
-    add_quantity, list_checkins_for_habit, list_checkins_in_range, set_quantity,
+    add_quantity, list_checkins_for_habit, list_events_in_range, set_quantity, EventInfo,
+};
+use crate::completion::CompletionIndex;
+use crate::date::{
+    add_days, iso_week_start, month_end, month_start, parse_date_string, system_today,
+};
+use crate::db::{
+    convert_db, list_backups, migrate_db, read_db, resolve_db_path, restore_db, update_db,
 };
-use crate::date::{add_days, iso_week_start, parse_date_string, system_today_utc};
-use crate::db::{read_db, resolve_db_path, update_db};
 use crate::error::CliError;
-use crate::export::export_csv_to_dir;
+use crate::export::{build_export, export_csv_to_dir};
 use crate::habits::{
-    list_habits, make_habit, next_habit_id, select_habit_index, stable_habit_sort,
+    list_habits, make_habit, next_habit_id, select_habit_index, stable_habit_sort, target_label,
 };
 use crate::routines::{
     add_step as add_routine_step, list_routines, make_routine, next_routine_id,
     select_routine_index, select_session_index, start_session as start_routine_session,
 };
 use crate::output::{render_simple_table, Styler};
+use crate::period::period_adverb;
 use crate::schedule::schedule_to_string;
 use crate::stable_json::stable_to_string_pretty;
 use crate::due::build_due;
 use crate::recap::{build_recap, render_progress_bar, RecapRange};
 use crate::stats::build_stats;
 use crate::status::build_status;
+use crate::ts::system_now_rfc3339;
 use clap::{Args, Parser, Subcommand, ValueEnum};
 use std::collections::BTreeSet;
 use std::fs;
@@ -50,12 +71,16 @@ enum Format {
     Table,
     Json,
     Csv,
+    Ics,
+    Html,
+    Md,
 }
 
 #[derive(Copy, Clone, Debug, Eq, PartialEq, ValueEnum)]
 enum Period {
     Day,
     Week,
+    Month,
 }
 
 impl Period {
@@ -63,6 +88,103 @@ impl Period {
         match self {
             Period::Day => "day",
             Period::Week => "week",
+            Period::Month => "month",
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, ValueEnum)]
+enum HabitKindArg {
+    /// Reach at least the target
+    Goal,
+    /// Stay at or under the target
+    Limit,
+}
+
+impl HabitKindArg {
+    fn to_kind(self) -> crate::model::HabitKind {
+        match self {
+            HabitKindArg::Goal => crate::model::HabitKind::Goal,
+            HabitKindArg::Limit => crate::model::HabitKind::Limit,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, ValueEnum)]
+enum ExcusePolicyArg {
+    /// Excused days neither break nor extend a streak
+    Freeze,
+    /// Excused days count as met
+    Count,
+    /// Excused days count as misses
+    Ignore,
+}
+
+impl ExcusePolicyArg {
+    fn to_policy(self) -> crate::model::ExcusePolicy {
+        match self {
+            ExcusePolicyArg::Freeze => crate::model::ExcusePolicy::Freeze,
+            ExcusePolicyArg::Count => crate::model::ExcusePolicy::Count,
+            ExcusePolicyArg::Ignore => crate::model::ExcusePolicy::Ignore,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, ValueEnum)]
+enum DefaultConsequenceArg {
+    /// Only record a `default` action
+    Event,
+    /// Re-issue the debt times the multiplier (capped)
+    Escalate,
+    /// Record new allowed excuses as denied until the debt is settled
+    FreezeExcuses,
+}
+
+impl DefaultConsequenceArg {
+    fn to_consequence(self) -> crate::model::DefaultConsequence {
+        match self {
+            DefaultConsequenceArg::Event => crate::model::DefaultConsequence::Event,
+            DefaultConsequenceArg::Escalate => crate::model::DefaultConsequence::Escalate,
+            DefaultConsequenceArg::FreezeExcuses => {
+                crate::model::DefaultConsequence::FreezeExcuses
+            }
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, ValueEnum)]
+enum ImportFromArg {
+    /// `habit export --format json`
+    Json,
+    /// Loop Habit Tracker export (extracted folder or its Habits.csv)
+    Loop,
+    /// CSV with one row per check-in; columns chosen with --map
+    Csv,
+}
+
+impl ImportFromArg {
+    fn to_source(self) -> crate::import::ImportSource {
+        match self {
+            ImportFromArg::Json => crate::import::ImportSource::Json,
+            ImportFromArg::Loop => crate::import::ImportSource::Loop,
+            ImportFromArg::Csv => crate::import::ImportSource::Csv,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, ValueEnum)]
+enum StorageArg {
+    /// Single pretty-printed JSON file
+    Json,
+    /// SQLite database
+    Sqlite,
+}
+
+impl StorageArg {
+    fn to_kind(self) -> crate::storage::StorageKind {
+        match self {
+            StorageArg::Json => crate::storage::StorageKind::Json,
+            StorageArg::Sqlite => crate::storage::StorageKind::Sqlite,
         }
     }
 }
@@ -78,7 +200,8 @@ struct Cli {
     #[arg(long, global = true)]
     today: Option<String>,
 
-    /// Output format. Most commands support table/json. `export` supports json/csv.
+    /// Output format. Most commands support table/json. `export` supports json/csv/ics;
+    /// `report` supports html/md.
     #[arg(long, global = true, value_enum, default_value = "table")]
     format: Format,
 
@@ -86,6 +209,14 @@ struct Cli {
     #[arg(long, global = true)]
     no_color: bool,
 
+    /// Storage backend for the DB path (default: sqlite for *.sqlite/*.sqlite3/*.db, else json).
+    #[arg(long, global = true, value_enum)]
+    storage: Option<StorageArg>,
+
+    /// Seconds to keep retrying (with backoff) while another process holds the DB lock.
+    #[arg(long, global = true, value_name = "SECONDS", default_value_t = 0.0)]
+    lock_timeout: f64,
+
     #[command(subcommand)]
     command: Command,
 }
@@ -110,14 +241,29 @@ enum Command {
     Recap(RecapArgs),
     /// Show habits that are due (scheduled and not yet complete) for a given date.
     Due(DueArgs),
+    /// Render a self-contained progress report (`--format html|md`).
+    Report(ReportArgs),
+    /// GitHub-style heatmap of one habit's days (a year, a month, or the last 53 weeks).
+    Calendar(CalendarArgs),
     Export(ExportArgs),
+    /// Load a `habit export --format json` file (restore into an empty DB, else merge),
+    /// or history exported by another tracker (`--from loop|csv`).
+    Import(ImportArgs),
+    /// Timezone and day-boundary settings used to derive "today".
+    Config(ConfigArgs),
+    /// Database maintenance (migrate, restore backups, check/repair integrity, convert).
+    Db(DbArgs),
+    /// Revert the most recent operation(s) recorded in the journal.
+    Undo(UndoArgs),
+    /// List recent operations recorded in the journal.
+    History(HistoryArgs),
 }
 
 #[derive(Args, Debug)]
 struct AddArgs {
     name: String,
 
-    /// One of: everyday, weekdays, weekends, mon,tue,...,sun
+    /// One of: everyday, weekdays, weekends, mon,tue,...,sun, every:<N>d[@YYYY-MM-DD], monthly:<d>,...|last
     #[arg(long, default_value = "everyday")]
     schedule: String,
 
@@ -140,6 +286,14 @@ struct AddArgs {
     /// Maximum number of allowed excused days per ISO week.
     #[arg(long, default_value_t = 2)]
     excuse_quota_per_week: u32,
+
+    /// `goal`: reach at least --target. `limit`: stay at or under --target.
+    #[arg(long, value_enum, default_value = "goal")]
+    kind: HabitKindArg,
+
+    /// How allowed excuses count in streaks, success rates and strength.
+    #[arg(long, value_enum, default_value = "freeze")]
+    excuse_policy: ExcusePolicyArg,
 }
 
 #[derive(Args, Debug)]
@@ -150,7 +304,7 @@ struct EditArgs {
     #[arg(long)]
     name: Option<String>,
 
-    /// One of: everyday, weekdays, weekends, mon,tue,...,sun
+    /// One of: everyday, weekdays, weekends, mon,tue,...,sun, every:<N>d[@YYYY-MM-DD], monthly:<d>,...|last
     #[arg(long)]
     schedule: Option<String>,
 
@@ -161,8 +315,14 @@ struct EditArgs {
     #[arg(long)]
     target: Option<u32>,
 
+    #[arg(long, value_enum)]
+    kind: Option<HabitKindArg>,
+
     #[arg(long)]
     notes: Option<String>,
+
+    #[arg(long, value_enum)]
+    excuse_policy: Option<ExcusePolicyArg>,
 }
 
 #[derive(Args, Debug)]
@@ -194,9 +354,21 @@ struct CheckinArgs {
     #[arg(long)]
     set: Option<u32>,
 
-    /// Deletes the check-in record for that date
+    /// Zeroes the total for that date (recorded as a compensating event)
     #[arg(long)]
     delete: bool,
+
+    /// RFC3339 with offset for the event (default: system clock)
+    #[arg(long)]
+    ts: Option<String>,
+
+    /// Optional note stored on the event
+    #[arg(long)]
+    note: Option<String>,
+
+    /// Event source label (default: cli)
+    #[arg(long, default_value = "cli")]
+    source: String,
 }
 
 #[derive(Args, Debug)]
@@ -249,6 +421,8 @@ struct PenaltyArgs {
 #[derive(Subcommand, Debug)]
 enum PenaltyCommand {
     Arm(PenaltyArmArgs),
+    Disarm(PenaltyDisarmArgs),
+    Pause(PenaltyPauseArgs),
     Tick(PenaltyTickArgs),
     Status(PenaltyStatusArgs),
     List(PenaltyStatusArgs),
@@ -267,9 +441,14 @@ struct PenaltyArmArgs {
     #[arg(long, default_value_t = 8)]
     cap: u32,
 
+    /// Days a debt stays payable from its due date
     #[arg(long, default_value_t = 1)]
     deadline_days: u32,
 
+    /// What happens to a debt still unpaid after its deadline
+    #[arg(long, value_enum, default_value = "event")]
+    on_default: DefaultConsequenceArg,
+
     #[arg(long)]
     date: String,
 
@@ -280,10 +459,18 @@ struct PenaltyArmArgs {
 
 #[derive(Args, Debug)]
 struct PenaltyTickArgs {
-    #[arg(long)]
-    date: String,
+    #[arg(long, required_unless_present = "from", conflicts_with_all = ["from", "to"])]
+    date: Option<String>,
 
-    /// RFC3339 with offset (no implicit system clock)
+    /// First day of a catch-up range (walked day by day through `--to`)
+    #[arg(long, requires = "to")]
+    from: Option<String>,
+
+    /// Last day of a catch-up range (inclusive)
+    #[arg(long, requires = "from")]
+    to: Option<String>,
+
+    /// RFC3339 with offset (no implicit system clock); a range stamps every day with it
     #[arg(long)]
     ts: String,
 
@@ -304,6 +491,38 @@ struct PenaltyStatusArgs {
     include_archived: bool,
 }
 
+#[derive(Args, Debug)]
+struct PenaltyDisarmArgs {
+    /// Habit selector: exact id (h0001) or unique name prefix (case-insensitive)
+    habit: String,
+
+    /// First date the rule no longer applies
+    #[arg(long)]
+    date: String,
+
+    /// RFC3339 with offset (no implicit system clock)
+    #[arg(long)]
+    ts: String,
+}
+
+#[derive(Args, Debug)]
+struct PenaltyPauseArgs {
+    /// Habit selector: exact id (h0001) or unique name prefix (case-insensitive)
+    habit: String,
+
+    /// First paused date
+    #[arg(long)]
+    from: String,
+
+    /// Last paused date (inclusive)
+    #[arg(long)]
+    to: String,
+
+    /// RFC3339 with offset (no implicit system clock)
+    #[arg(long)]
+    ts: String,
+}
+
 #[derive(Args, Debug)]
 struct PenaltyResolveArgs {
     debt_id: String,
@@ -564,6 +783,39 @@ struct RecapArgs {
     behind_first: bool,
 }
 
+#[derive(Args, Debug)]
+struct CalendarArgs {
+    /// Habit selector (id or name)
+    habit: String,
+
+    /// Calendar year (YYYY)
+    #[arg(long, conflicts_with = "month")]
+    year: Option<String>,
+
+    /// Calendar month (YYYY-MM)
+    #[arg(long)]
+    month: Option<String>,
+}
+
+#[derive(Args, Debug)]
+struct ReportArgs {
+    /// First day of the report (default: 6 days before --to)
+    #[arg(long)]
+    from: Option<String>,
+
+    /// Last day of the report (default: today)
+    #[arg(long)]
+    to: Option<String>,
+
+    /// Include archived habits
+    #[arg(long)]
+    include_archived: bool,
+
+    /// Write the report to a file instead of stdout
+    #[arg(long)]
+    out: Option<String>,
+}
+
 #[derive(Args, Debug)]
 struct DueArgs {
     /// The date to check (defaults to today)
@@ -590,6 +842,118 @@ struct ExportArgs {
     include_archived: bool,
 }
 
+#[derive(Args, Debug)]
+struct ImportArgs {
+    /// Path to a JSON export, an extracted Loop export folder, or a CSV file
+    file: String,
+
+    #[arg(long, value_enum, default_value = "json")]
+    from: ImportFromArg,
+
+    /// `--from csv`: read <field> from <column> (repeatable).
+    /// Fields: habit, date, quantity, schedule, period, target, kind, notes
+    #[arg(long = "map", value_name = "FIELD=COLUMN")]
+    maps: Vec<String>,
+
+    /// `--from csv`: chrono/strftime format of the date column (default YYYY-MM-DD)
+    #[arg(long)]
+    date_format: Option<String>,
+
+    /// Report what would be imported without changing the DB
+    #[arg(long)]
+    dry_run: bool,
+}
+
+#[derive(Args, Debug)]
+struct ConfigArgs {
+    #[command(subcommand)]
+    command: ConfigCommand,
+}
+
+#[derive(Subcommand, Debug)]
+enum ConfigCommand {
+    Show,
+    Set(ConfigSetArgs),
+}
+
+#[derive(Args, Debug)]
+struct ConfigSetArgs {
+    /// IANA timezone (e.g. Asia/Seoul) or fixed offset (e.g. +09:00)
+    #[arg(long)]
+    timezone: Option<String>,
+
+    /// Local time (HH:MM) at which a new logical day starts (e.g. 04:00)
+    #[arg(long = "day-start")]
+    day_start: Option<String>,
+}
+
+#[derive(Args, Debug)]
+struct UndoArgs {
+    /// Number of operations to revert (newest first)
+    #[arg(long, default_value_t = 1)]
+    steps: usize,
+}
+
+#[derive(Args, Debug)]
+struct HistoryArgs {
+    /// Maximum number of operations to list (newest first)
+    #[arg(long, default_value_t = 20)]
+    limit: usize,
+}
+
+#[derive(Args, Debug)]
+struct DbArgs {
+    #[command(subcommand)]
+    command: DbCommand,
+}
+
+#[derive(Subcommand, Debug)]
+enum DbCommand {
+    /// Upgrade the DB file to the current schema version (keeps a backup).
+    Migrate(DbMigrateArgs),
+    /// Replace the DB with one of the rolling backups (newest by default).
+    Restore(DbRestoreArgs),
+    /// Verify referential integrity, duplicate ids and meta counters (--repair fixes the safe cases).
+    Check(DbCheckArgs),
+    /// Copy the DB into a new file, optionally switching storage backend.
+    Convert(DbConvertArgs),
+}
+
+#[derive(Args, Debug)]
+struct DbConvertArgs {
+    /// Destination path (must not exist)
+    #[arg(long)]
+    to: String,
+
+    /// Destination backend (default: inferred from the --to extension)
+    #[arg(long, value_enum)]
+    to_storage: Option<StorageArg>,
+}
+
+#[derive(Args, Debug)]
+struct DbCheckArgs {
+    /// Fix the safe cases (counters, duplicate ids, duplicate migrated check-ins)
+    #[arg(long)]
+    repair: bool,
+}
+
+#[derive(Args, Debug)]
+struct DbRestoreArgs {
+    /// Backup file name (from --list) or path; default: the newest backup
+    backup: Option<String>,
+
+    /// List available backups instead of restoring
+    #[arg(long)]
+    list: bool,
+}
+
+#[derive(Args, Debug)]
+struct DbMigrateArgs {
+    /// Show pending migration steps without writing anything
+    #[arg(long)]
+    dry_run: bool,
+}
+
 fn main() {
     let cli = match Cli::try_parse() {
         Ok(c) => c,
@@ -620,21 +984,43 @@ fn print_json<T: serde::Serialize>(obj: &T) -> Result<(), CliError> {
     Ok(())
 }
 
-fn resolve_today(cli_today: Option<&str>) -> Result<String, CliError> {
-    if let Some(t) = cli_today {
-        parse_date_string(t, "today")?;
-        return Ok(t.to_string());
+/// The command's "today": `--today`/`HABITCLI_TODAY` when given, otherwise
+/// the system date in the DB's timezone and day start. The fallback is
+/// resolved against the DB the command loads anyway, never a separate read.
+struct Today(Option<String>);
+
+impl Today {
+    fn resolve(cli_today: Option<&str>) -> Result<Today, CliError> {
+        if let Some(t) = cli_today {
+            parse_date_string(t, "today")?;
+            return Ok(Today(Some(t.to_string())));
+        }
+
+        if let Ok(t) = std::env::var("HABITCLI_TODAY") {
+            let tt = t.trim();
+            if !tt.is_empty() {
+                parse_date_string(tt, "today")?;
+                return Ok(Today(Some(tt.to_string())));
+            }
+        }
+
+        Ok(Today(None))
     }
 
-    if let Ok(t) = std::env::var("HABITCLI_TODAY") {
-        let tt = t.trim();
-        if !tt.is_empty() {
-            parse_date_string(tt, "today")?;
-            return Ok(tt.to_string());
+    fn on(&self, meta: &crate::model::Meta) -> Result<String, CliError> {
+        match self.0.as_deref() {
+            Some(t) => Ok(t.to_string()),
+            None => system_today(meta.timezone.as_deref(), &meta.day_start),
         }
     }
 
-    Ok(system_today_utc())
+    /// `date` when given, else today.
+    fn or(&self, date: Option<&str>, meta: &crate::model::Meta) -> Result<String, CliError> {
+        match date {
+            Some(d) => Ok(d.to_string()),
+            None => self.on(meta),
+        }
+    }
 }
 
 fn resolve_color_enabled(no_color_flag: bool) -> bool {
@@ -647,18 +1033,113 @@ fn resolve_color_enabled(no_color_flag: bool) -> bool {
     true
 }
 
+/// `, N excused` for ratio cells; empty when no excuse was used.
+fn excused_suffix(excused_days: u32) -> String {
+    if excused_days == 0 {
+        String::new()
+    } else {
+        format!(", {} excused", excused_days)
+    }
+}
+
+/// Table cell for a habit strength score ("n/a" when there is none).
+fn strength_label(strength: Option<&crate::strength::Strength>) -> String {
+    match strength {
+        Some(s) => format!("{}%", s.percent),
+        None => "n/a".to_string(),
+    }
+}
+
+/// `penalty tick` output. A single `--date` prints that day's outcome
+/// (JSON: the day itself); a `--from`/`--to` range prints a summary line per
+/// day (JSON: `{from, to, days}`), then the total.
+fn print_tick(
+    format: Format,
+    range: Option<(&str, &str)>,
+    days: Vec<crate::penalty::DayTick>,
+) -> Result<(), CliError> {
+    if format == Format::Json {
+        return match range {
+            Some((from, to)) => {
+                #[derive(serde::Serialize)]
+                struct Out<'a> {
+                    from: &'a str,
+                    to: &'a str,
+                    days: Vec<crate::penalty::DayTick>,
+                }
+                print_json(&Out { from, to, days })
+            }
+            None => match days.first() {
+                Some(day) => print_json(day),
+                None => Ok(()),
+            },
+        };
+    }
+
+    let indent = if range.is_some() { "  " } else { "" };
+    for d in days.iter() {
+        if range.is_some() {
+            let ids: Vec<&str> = d.outcome.created.iter().map(|c| c.id.as_str()).collect();
+            let mut line = format!("{}: created {} debt(s)", d.date, d.outcome.created.len());
+            if !ids.is_empty() {
+                line.push_str(&format!(" ({})", ids.join(", ")));
+            }
+            print_line(&line);
+        }
+        for a in d.outcome.payments.iter() {
+            print_line(&format!("{}Paid: {} ({})", indent, a.debt_id, a.reason));
+        }
+        for a in d.outcome.defaulted.iter() {
+            print_line(&format!("{}Defaulted: {} ({})", indent, a.debt_id, a.reason));
+        }
+    }
+
+    let total: usize = days.iter().map(|d| d.outcome.created.len()).sum();
+    match range {
+        Some(_) => print_line(&format!(
+            "Penalty tick complete. Created {} debt(s) over {} day(s).",
+            total,
+            days.len()
+        )),
+        None => print_line(&format!("Penalty tick complete. Created {} debt(s).", total)),
+    }
+    Ok(())
+}
+
 fn ensure_format_supported(format: Format, allow_csv: bool) -> Result<(), CliError> {
     if format == Format::Csv && !allow_csv {
         return Err(CliError::usage(
             "--format csv is only supported by `habit export`",
         ));
     }
+    if format == Format::Ics {
+        return Err(CliError::usage(
+            "--format ics is only supported by `habit export`",
+        ));
+    }
+    if format == Format::Html || format == Format::Md {
+        return Err(CliError::usage(
+            "--format html|md is only supported by `habit report`",
+        ));
+    }
     Ok(())
 }
 
 fn run(cli: Cli) -> Result<(), CliError> {
     let db_path = resolve_db_path(cli.db.as_deref())?;
-    let today = resolve_today(cli.today.as_deref())?;
+    let lock_timeout = std::time::Duration::try_from_secs_f64(cli.lock_timeout)
+        .map_err(|_| CliError::usage(format!("Invalid lock-timeout: {}", cli.lock_timeout)))?;
+    crate::lock::set_lock_timeout(lock_timeout);
+    if let Some(st) = cli.storage {
+        crate::storage::set_storage_override(st.to_kind());
+    }
+    let argv: Vec<String> = std::env::args().skip(1).collect();
+    crate::journal::set_command_label(crate::journal::command_label_from_args(&argv));
+    let today = Today::resolve(cli.today.as_deref())?;
+    crate::journal::set_command_time(
+        crate::journal::command_ts_from_args(&argv),
+        today.0.clone(),
+    );
 
     let styler = Styler::new(resolve_color_enabled(cli.no_color));
 
@@ -668,17 +1149,19 @@ fn run(cli: Cli) -> Result<(), CliError> {
 
             let created = update_db(&db_path, |db| {
                 let id = next_habit_id(db);
-                let habit = make_habit(
+                let mut habit = make_habit(
                     id,
                     &args.name,
                     &args.schedule,
                     args.period.as_str(),
                     args.target,
                     args.notes.as_deref(),
-                    &today,
+                    &today.on(&db.meta)?,
                     args.needs_declaration,
                     args.excuse_quota_per_week,
+                    args.kind.to_kind(),
                 )?;
+                habit.excuse_policy = args.excuse_policy.to_policy();
                 db.habits.push(habit.clone());
                 Ok(habit)
             })?;
@@ -694,7 +1177,7 @@ fn run(cli: Cli) -> Result<(), CliError> {
                     created.id.clone(),
                     created.name.clone(),
                     schedule_to_string(&created.schedule),
-                    format!("{}/{}", created.target.quantity, created.target.period),
+                    target_label(&created),
                 ];
                 print_line(&render_simple_table(
                     &["id", "name", "schedule", "target"],
@@ -712,7 +1195,9 @@ fn run(cli: Cli) -> Result<(), CliError> {
                 && args.schedule.is_none()
                 && args.period.is_none()
                 && args.target.is_none()
+                && args.kind.is_none()
                 && args.notes.is_none()
+                && args.excuse_policy.is_none()
             {
                 return Err(CliError::usage("No updates specified"));
             }
@@ -726,7 +1211,9 @@ fn run(cli: Cli) -> Result<(), CliError> {
                 }
 
                 if let Some(ref s) = args.schedule {
-                    let schedule = crate::schedule::parse_schedule_pattern(s)?;
+                    let schedule =
+                        crate::schedule::parse_schedule_pattern(s)?
+                        .with_default_anchor(&today.on(&db.meta)?);
                     crate::schedule::validate_schedule(&schedule)?;
                     habit.schedule = schedule;
                 }
@@ -742,10 +1229,18 @@ fn run(cli: Cli) -> Result<(), CliError> {
                     habit.target.quantity = t;
                 }
 
+                if let Some(k) = args.kind {
+                    habit.kind = k.to_kind();
+                }
+
                 if let Some(ref notes) = args.notes {
                     habit.notes = Some(notes.to_string());
                 }
 
+                if let Some(p) = args.excuse_policy {
+                    habit.excuse_policy = p.to_policy();
+                }
+
                 Ok(habit.clone())
             })?;
 
@@ -760,7 +1255,7 @@ fn run(cli: Cli) -> Result<(), CliError> {
                     updated.id.clone(),
                     updated.name.clone(),
                     schedule_to_string(&updated.schedule),
-                    format!("{}/{}", updated.target.quantity, updated.target.period),
+                    target_label(&updated),
                 ];
                 print_line(&render_simple_table(
                     &["id", "name", "schedule", "target"],
@@ -791,7 +1286,7 @@ fn run(cli: Cli) -> Result<(), CliError> {
                             h.id.clone(),
                             h.name.clone(),
                             schedule_to_string(&h.schedule),
-                            format!("{}/{}", h.target.quantity, h.target.period),
+                            target_label(h),
                             if h.archived {
                                 "yes".to_string()
                             } else {
@@ -817,24 +1312,29 @@ fn run(cli: Cli) -> Result<(), CliError> {
             let idx = select_habit_index(&db, &args.habit, true)?;
             let habit = db.habits[idx].clone();
             let checkins = list_checkins_for_habit(&db, &habit.id);
+            let ids: BTreeSet<String> = [habit.id.clone()].into_iter().collect();
+            let checkin_events = list_events_in_range(&db, None, None, Some(&ids));
 
             if cli.format == Format::Json {
                 #[derive(serde::Serialize)]
                 struct Out {
                     habit: crate::model::Habit,
                     checkins: Vec<crate::model::Checkin>,
+                    checkin_events: Vec<crate::model::CheckinEvent>,
                 }
-                print_json(&Out { habit, checkins })?;
+                print_json(&Out {
+                    habit,
+                    checkins,
+                    checkin_events,
+                })?;
             } else {
                 print_line(&format!("{} ({})", habit.name, habit.id));
                 print_line(&format!(
                     "schedule: {}",
                     schedule_to_string(&habit.schedule)
                 ));
-                print_line(&format!(
-                    "target: {}/{}",
-                    habit.target.quantity, habit.target.period
-                ));
+                print_line(&format!("target: {}", target_label(&habit)));
+                print_line(&format!("excuse_policy: {}", habit.excuse_policy.as_str()));
                 print_line(&format!(
                     "archived: {}",
                     if habit.archived { "yes" } else { "no" }
@@ -866,7 +1366,7 @@ fn run(cli: Cli) -> Result<(), CliError> {
 
                 habit.archived = true;
                 if habit.archived_date.is_none() {
-                    habit.archived_date = Some(today.clone());
+                    habit.archived_date = Some(today.on(&db.meta)?);
                 }
 
                 Ok(habit.clone())
@@ -914,8 +1414,9 @@ fn run(cli: Cli) -> Result<(), CliError> {
         Command::Checkin(args) => {
             ensure_format_supported(cli.format, false)?;
 
-            let date = args.date.as_deref().unwrap_or(&today);
-            parse_date_string(date, "date")?;
+            if let Some(d) = args.date.as_deref() {
+                parse_date_string(d, "date")?;
+            }
 
             if args.delete && (args.qty.is_some() || args.set.is_some()) {
                 return Err(CliError::usage(
@@ -928,6 +1429,11 @@ fn run(cli: Cli) -> Result<(), CliError> {
 
             let qty = args.qty.unwrap_or(1);
             let set = args.set.unwrap_or(0);
+            let info = EventInfo {
+                ts: args.ts.clone().unwrap_or_else(system_now_rfc3339),
+                note: args.note.clone(),
+                source: args.source.clone(),
+            };
 
             #[derive(Debug)]
             struct ResultRow {
@@ -937,14 +1443,17 @@ fn run(cli: Cli) -> Result<(), CliError> {
                 action: String,
                 delta: Option<u32>,
                 quantity: u32,
+                event_id: Option<String>,
             }
 
             let result = update_db(&db_path, |db| {
                 let idx = select_habit_index(db, &args.habit, true)?;
                 let habit = db.habits[idx].clone();
+                let date = today.or(args.date.as_deref(), &db.meta)?;
+                let date = date.as_str();
 
                 if args.delete {
-                    set_quantity(db, &habit.id, date, 0)?;
+                    let ev = set_quantity(db, &habit.id, date, 0, &info)?;
                     return Ok(ResultRow {
                         habit_id: habit.id,
                         habit_name: habit.name,
@@ -952,11 +1461,12 @@ fn run(cli: Cli) -> Result<(), CliError> {
                         action: "delete".to_string(),
                         delta: None,
                         quantity: 0,
+                        event_id: ev.map(|e| e.id),
                     });
                 }
 
                 if args.set.is_some() {
-                    set_quantity(db, &habit.id, date, set)?;
+                    let ev = set_quantity(db, &habit.id, date, set, &info)?;
                     return Ok(ResultRow {
                         habit_id: habit.id,
                         habit_name: habit.name,
@@ -964,10 +1474,11 @@ fn run(cli: Cli) -> Result<(), CliError> {
                         action: "set".to_string(),
                         delta: None,
                         quantity: set,
+                        event_id: ev.map(|e| e.id),
                     });
                 }
 
-                let total = add_quantity(db, &habit.id, date, qty)?;
+                let (total, ev) = add_quantity(db, &habit.id, date, qty, &info)?;
                 Ok(ResultRow {
                     habit_id: habit.id,
                     habit_name: habit.name,
@@ -975,6 +1486,7 @@ fn run(cli: Cli) -> Result<(), CliError> {
                     action: "add".to_string(),
                     delta: Some(qty),
                     quantity: total,
+                    event_id: Some(ev.id),
                 })
             })?;
 
@@ -992,6 +1504,7 @@ fn run(cli: Cli) -> Result<(), CliError> {
                     action: String,
                     quantity: u32,
                     delta: Option<u32>,
+                    event_id: Option<String>,
                 }
 
                 print_json(&Out {
@@ -1003,6 +1516,7 @@ fn run(cli: Cli) -> Result<(), CliError> {
                     action: result.action,
                     quantity: result.quantity,
                     delta: result.delta,
+                    event_id: result.event_id,
                 })?;
             } else if result.action == "delete" {
                 print_line(&format!(
@@ -1075,7 +1589,12 @@ fn run(cli: Cli) -> Result<(), CliError> {
                 let idx = select_habit_index(db, &args.habit, true)?;
                 let habit = db.habits[idx].clone();
 
-                let quota = habit.excuse_quota_per_week;
+                // a defaulted debt under a `freeze_excuses` rule leaves no quota
+                let quota = if crate::penalty::excuses_frozen(db, &habit.id, &args.date) {
+                    0
+                } else {
+                    habit.excuse_quota_per_week
+                };
                 let (excuse, used, remaining) = crate::excuses::excuse(
                     db,
                     &habit.id,
@@ -1134,7 +1653,7 @@ fn run(cli: Cli) -> Result<(), CliError> {
                     let rule = update_db(&db_path, |db| {
                         let idx = select_habit_index(db, &a.habit, true)?;
                         let habit = db.habits[idx].clone();
-                        crate::penalty::upsert_rule(
+                        crate::penalty::arm_rule(
                             db,
                             &habit.id,
                             &a.date,
@@ -1142,6 +1661,7 @@ fn run(cli: Cli) -> Result<(), CliError> {
                             a.multiplier,
                             a.cap,
                             a.deadline_days,
+                            a.on_default.to_consequence(),
                         )
                     })?;
 
@@ -1157,38 +1677,74 @@ fn run(cli: Cli) -> Result<(), CliError> {
                     Ok(())
                 }
 
-                PenaltyCommand::Tick(t) => {
-                    let created = update_db(&db_path, |db| {
-                        let _ = t.idempotency_key.as_deref();
-                        crate::penalty::tick(db, &t.date, &t.ts, t.include_archived)
+                PenaltyCommand::Disarm(a) => {
+                    let rule = update_db(&db_path, |db| {
+                        let idx = select_habit_index(db, &a.habit, true)?;
+                        let habit = db.habits[idx].clone();
+                        crate::penalty::disarm_rule(db, &habit.id, &a.date, &a.ts)
                     })?;
-                    let created_len = created.len();
 
                     if cli.format == Format::Json {
                         #[derive(serde::Serialize)]
                         struct Out {
-                            date: String,
-                            created: Vec<crate::model::PenaltyDebt>,
+                            rule: crate::model::PenaltyRule,
                         }
-                        print_json(&Out {
-                            date: t.date.clone(),
-                            created,
-                        })?;
+                        print_json(&Out { rule: rule.clone() })?;
+                    } else {
+                        print_line(&format!(
+                            "Disarmed penalty rule for {} ({}) from {}",
+                            a.habit,
+                            rule.id,
+                            rule.disarmed_date.as_deref().unwrap_or(&a.date)
+                        ));
+                    }
+                    Ok(())
+                }
+
+                PenaltyCommand::Pause(p) => {
+                    let rule = update_db(&db_path, |db| {
+                        let idx = select_habit_index(db, &p.habit, true)?;
+                        let habit = db.habits[idx].clone();
+                        crate::penalty::pause_rule(db, &habit.id, &p.from, &p.to, &p.ts)
+                    })?;
+
+                    if cli.format == Format::Json {
+                        #[derive(serde::Serialize)]
+                        struct Out {
+                            rule: crate::model::PenaltyRule,
+                        }
+                        print_json(&Out { rule: rule.clone() })?;
                     } else {
                         print_line(&format!(
-                            "Penalty tick complete. Created {} debt(s).",
-                            created_len
+                            "Paused penalties for {} from {} to {}",
+                            p.habit, p.from, p.to
                         ));
                     }
                     Ok(())
                 }
 
+                PenaltyCommand::Tick(t) => {
+                    let range = t.from.as_deref().zip(t.to.as_deref());
+                    let days = update_db(&db_path, |db| match range {
+                        Some((from, to)) => {
+                            crate::penalty::tick_range(db, from, to, &t.ts, t.include_archived)
+                        }
+                        None => {
+                            let date = t.date.clone().unwrap_or_default();
+                            let outcome =
+                                crate::penalty::tick(db, &date, &t.ts, t.include_archived)?;
+                            Ok(vec![crate::penalty::DayTick { date, outcome }])
+                        }
+                    })?;
+                    print_tick(cli.format, range, days)
+                }
+
                 PenaltyCommand::Status(s) | PenaltyCommand::List(s) => {
                     let db = read_db(&db_path)?;
-                    let date = s.date.as_deref().unwrap_or(&today);
-                    parse_date_string(date, "date")?;
+                    let date = today.or(s.date.as_deref(), &db.meta)?;
+                    parse_date_string(&date, "date")?;
 
-                    let mut debts = crate::penalty::outstanding_debts_as_of(&db, date)?;
+                    let mut debts = crate::penalty::debt_statuses_as_of(&db, &date)?;
 
                     if !s.include_archived {
                         let mut archived: std::collections::BTreeSet<String> =
@@ -1198,14 +1754,14 @@ fn run(cli: Cli) -> Result<(), CliError> {
                                 archived.insert(h.id.clone());
                             }
                         }
-                        debts.retain(|d| !archived.contains(&d.habit_id));
+                        debts.retain(|d| !archived.contains(&d.debt.habit_id));
                     }
 
                     if cli.format == Format::Json {
                         #[derive(serde::Serialize)]
                         struct Out {
                             date: String,
-                            debts: Vec<crate::model::PenaltyDebt>,
+                            debts: Vec<crate::penalty::DebtStatus>,
                         }
                         print_json(&Out {
                             date: date.to_string(),
@@ -1214,10 +1770,32 @@ fn run(cli: Cli) -> Result<(), CliError> {
                     } else if debts.is_empty() {
                         print_line("(no outstanding penalty debts)");
                     } else {
-                        for d in debts.iter() {
+                        for s in debts.iter() {
+                            let d = &s.debt;
+                            let window = match (s.days_remaining, s.days_overdue) {
+                                (Some(n), _) => format!("{} day(s) left", n),
+                                (_, Some(n)) => format!("{} day(s) overdue", n),
+                                _ => String::new(),
+                            };
+                            let defaulted = if s.status == "defaulted" {
+                                ", defaulted"
+                            } else {
+                                ""
+                            };
+                            let qty = if s.paid > 0 {
+                                format!("{} (paid {})", d.quantity, s.paid)
+                            } else {
+                                d.quantity.to_string()
+                            };
                             print_line(&format!(
-                                "- {} {} due {} qty {}",
-                                d.id, d.habit_id, d.due_date, d.quantity
+                                "- {} {} due {} qty {} (deadline {}, {}{})",
+                                d.id,
+                                d.habit_id,
+                                d.due_date,
+                                qty,
+                                s.deadline_date,
+                                window,
+                                defaulted
                             ));
                         }
                     }
@@ -1282,6 +1860,7 @@ fn run(cli: Cli) -> Result<(), CliError> {
                 RoutineCommand::Add(a) => {
                     let created = update_db(&db_path, |db| {
                         let id = next_routine_id(db);
+                        let today = today.on(&db.meta)?;
                         let routine = make_routine(id, &a.name, a.at.as_deref(), &today)?;
                         db.routines.push(routine.clone());
                         Ok(routine)
@@ -1374,7 +1953,7 @@ fn run(cli: Cli) -> Result<(), CliError> {
                         let r = &mut db.routines[idx];
                         if !r.archived {
                             r.archived = true;
-                            r.archived_date = Some(today.clone());
+                            r.archived_date = Some(today.on(&db.meta)?);
                         }
                         Ok(r.clone())
                     })?;
@@ -1719,15 +2298,16 @@ fn run(cli: Cli) -> Result<(), CliError> {
         Command::Status(args) => {
             ensure_format_supported(cli.format, false)?;
 
-            let date = args.date.as_deref().unwrap_or(&today);
-            parse_date_string(date, "date")?;
-
+            if let Some(d) = args.date.as_deref() {
+                parse_date_string(d, "date")?;
+            }
             if let Some(wo) = args.week_of.as_deref() {
                 parse_date_string(wo, "week-of")?;
             }
 
             let db = read_db(&db_path)?;
-            let data = build_status(&db, date, args.week_of.as_deref(), args.include_archived)?;
+            let date = today.or(args.date.as_deref(), &db.meta)?;
+            let data = build_status(&db, &date, args.week_of.as_deref(), args.include_archived)?;
 
             if cli.format == Format::Json {
                 print_json(&data)?;
@@ -1739,13 +2319,20 @@ fn run(cli: Cli) -> Result<(), CliError> {
                     for h in data.today.habits.iter() {
                         let mark = if h.done {
                             styler.green("[x]")
+                        } else if h.excused {
+                            styler.yellow("[E]")
                         } else {
                             "[ ]".to_string()
                         };
                         let progress = if h.period == "day" {
                             format!("{}/{}", h.quantity, h.target)
                         } else {
-                            format!("{}/{} (weekly)", h.quantity, h.target)
+                            format!(
+                                "{}/{} ({})",
+                                h.quantity,
+                                h.target,
+                                period_adverb(&h.period)
+                            )
                         };
                         print_line(&format!("- {} {} {}", mark, h.name, progress));
                     }
@@ -1756,9 +2343,14 @@ fn run(cli: Cli) -> Result<(), CliError> {
                 for h in data.week.habits.iter() {
                     match h {
                         crate::status::WeekHabitRow::Day(r) => {
+                            let excused = if r.excused_days > 0 {
+                                format!(" ({} excused)", r.excused_days)
+                            } else {
+                                String::new()
+                            };
                             print_line(&format!(
-                                "- {} {}/{} scheduled days done",
-                                r.name, r.done_scheduled_days, r.scheduled_days
+                                "- {} {}/{} scheduled days done{}",
+                                r.name, r.done_scheduled_days, r.scheduled_days, excused
                             ));
                         }
                         crate::status::WeekHabitRow::Week(r) => {
@@ -1767,6 +2359,12 @@ fn run(cli: Cli) -> Result<(), CliError> {
                                 r.name, r.quantity, r.target
                             ));
                         }
+                        crate::status::WeekHabitRow::Month(r) => {
+                            print_line(&format!(
+                                "- {} {}/{} (monthly, {})",
+                                r.name, r.quantity, r.target, r.month
+                            ));
+                        }
                     }
                 }
             }
@@ -1789,9 +2387,11 @@ fn run(cli: Cli) -> Result<(), CliError> {
             let mut habits_sorted = habits;
             habits_sorted.sort_by(stable_habit_sort);
 
-            let to_eff = args.to.unwrap_or_else(|| today.clone());
+            let to_eff = today.or(args.to.as_deref(), &db.meta)?;
             parse_date_string(&to_eff, "to")?;
 
+            let completion = CompletionIndex::new(&db);
+
             if let Some(ref f) = args.from {
                 parse_date_string(f, "from")?;
                 if f > &to_eff {
@@ -1800,7 +2400,7 @@ fn run(cli: Cli) -> Result<(), CliError> {
             }
 
             let rows: Vec<crate::stats::StatsRow> = if let Some(from_eff) = args.from.as_deref() {
-                build_stats(&db, &habits_sorted, from_eff, &to_eff)?
+                build_stats(&completion, &habits_sorted, from_eff, &to_eff)?
             } else {
                 // Per-habit default windows.
                 let mut out: Vec<crate::stats::StatsRow> = Vec::new();
@@ -1809,13 +2409,24 @@ fn run(cli: Cli) -> Result<(), CliError> {
                         let end_week = iso_week_start(&to_eff)?;
                         let from2 = add_days(&end_week, -7 * (12 - 1))?;
                         let to2 = add_days(&end_week, 6)?;
-                        let mut one = build_stats(&db, std::slice::from_ref(h), &from2, &to2)?;
+                        let mut one = build_stats(&completion, std::slice::from_ref(h), &from2, &to2)?;
+                        if let Some(r) = one.pop() {
+                            out.push(r);
+                        }
+                    } else if h.target.period == "month" {
+                        // Last 12 calendar months ending this month.
+                        let mut from2 = month_start(&to_eff)?;
+                        for _ in 0..(12 - 1) {
+                            from2 = month_start(&add_days(&from2, -1)?)?;
+                        }
+                        let to2 = month_end(&to_eff)?;
+                        let mut one = build_stats(&completion, std::slice::from_ref(h), &from2, &to2)?;
                         if let Some(r) = one.pop() {
                             out.push(r);
                         }
                     } else {
                         let from2 = add_days(&to_eff, -29)?;
-                        let mut one = build_stats(&db, std::slice::from_ref(h), &from2, &to_eff)?;
+                        let mut one = build_stats(&completion, std::slice::from_ref(h), &from2, &to_eff)?;
                         if let Some(r) = one.pop() {
                             out.push(r);
                         }
@@ -1847,14 +2458,18 @@ fn run(cli: Cli) -> Result<(), CliError> {
                         r.current_streak.to_string(),
                         r.longest_streak.to_string(),
                         format!(
-                            "{} ({}/{})",
-                            rate, r.success_rate.successes, r.success_rate.eligible
+                            "{} ({}/{}{})",
+                            rate,
+                            r.success_rate.successes,
+                            r.success_rate.eligible,
+                            excused_suffix(r.excused_days)
                         ),
+                        strength_label(r.strength.as_ref()),
                     ]);
                 }
 
                 print_line(&render_simple_table(
-                    &["id", "name", "period", "current", "longest", "success"],
+                    &["id", "name", "period", "current", "longest", "success", "strength"],
                     &table_rows,
                 ));
             }
@@ -1873,7 +2488,14 @@ fn run(cli: Cli) -> Result<(), CliError> {
                 .cloned()
                 .collect();
 
-            let rows = build_recap(&db, &habits, args.range.to_recap_range(), &today, args.behind_first)?;
+            let completion = CompletionIndex::new(&db);
+            let rows = build_recap(
+                &completion,
+                &habits,
+                args.range.to_recap_range(),
+                &today.on(&db.meta)?,
+                args.behind_first,
+            )?;
 
             if cli.format == Format::Json {
                 #[derive(serde::Serialize)]
@@ -1895,7 +2517,12 @@ fn run(cli: Cli) -> Result<(), CliError> {
                             None => "n/a".to_string(),
                         };
                         let bar = render_progress_bar(r.percent, bar_width);
-                        let ratio = format!("{}/{}", r.successes, r.eligible);
+                        let ratio = format!(
+                            "{}/{}{}",
+                            r.successes,
+                            r.eligible,
+                            excused_suffix(r.excused_days)
+                        );
 
                         table_rows.push(vec![
                             r.name.clone(),
@@ -1903,6 +2530,7 @@ fn run(cli: Cli) -> Result<(), CliError> {
                             pct_str,
                             bar,
                             ratio,
+                            strength_label(r.strength.as_ref()),
                         ]);
                     }
 
@@ -1916,7 +2544,7 @@ fn run(cli: Cli) -> Result<(), CliError> {
                     }
 
                     print_line(&render_simple_table(
-                        &["name", "target", "%", "progress", "ratio"],
+                        &["name", "target", "%", "progress", "ratio", "strength"],
                         &table_rows,
                     ));
                 }
@@ -1925,14 +2553,98 @@ fn run(cli: Cli) -> Result<(), CliError> {
             Ok(())
         }
 
+        Command::Report(args) => {
+            let format = match cli.format {
+                Format::Html => crate::report::ReportFormat::Html,
+                Format::Md => crate::report::ReportFormat::Markdown,
+                _ => return Err(CliError::usage("`habit report` requires --format html|md")),
+            };
+
+            let db = read_db(&db_path)?;
+            let today = today.on(&db.meta)?;
+            let to = args.to.unwrap_or_else(|| today.clone());
+            parse_date_string(&to, "to")?;
+            let from = match args.from {
+                Some(f) => {
+                    parse_date_string(&f, "from")?;
+                    f
+                }
+                None => add_days(&to, -6)?,
+            };
+            if from > to {
+                return Err(CliError::usage("Invalid range: from > to"));
+            }
+
+            let habits: Vec<crate::model::Habit> = db
+                .habits
+                .iter()
+                .filter(|h| args.include_archived || !h.archived)
+                .cloned()
+                .collect();
+            let completion = CompletionIndex::new(&db);
+            let report =
+                crate::report::build_report(&db, &completion, &habits, &from, &to, &today)?;
+            let doc = crate::report::render(&report, format);
+
+            match args.out.as_deref() {
+                Some(p) => fs::write(p, doc.as_bytes()).map_err(|_| CliError::io("DB IO error"))?,
+                None => print!("{}", doc),
+            }
+            Ok(())
+        }
+
+        Command::Calendar(args) => {
+            ensure_format_supported(cli.format, false)?;
+
+            let db = read_db(&db_path)?;
+            let today = today.on(&db.meta)?;
+            let (from, to) = match (args.year.as_deref(), args.month.as_deref()) {
+                (Some(y), _) => {
+                    let from = format!("{}-01-01", y);
+                    if y.len() != 4 || parse_date_string(&from, "year").is_err() {
+                        return Err(CliError::usage("Invalid year (expected YYYY)"));
+                    }
+                    (from, format!("{}-12-31", y))
+                }
+                (None, Some(m)) => {
+                    let from = format!("{}-01", m);
+                    if m.len() != 7 || parse_date_string(&from, "month").is_err() {
+                        return Err(CliError::usage("Invalid month (expected YYYY-MM)"));
+                    }
+                    let to = month_end(&from)?;
+                    (from, to)
+                }
+                (None, None) => (add_days(&iso_week_start(&today)?, -52 * 7)?, today.clone()),
+            };
+
+            let idx = select_habit_index(&db, &args.habit, true)?;
+            let completion = CompletionIndex::new(&db);
+            let calendar = crate::calendar::build_calendar(
+                &completion,
+                &db.habits[idx],
+                &from,
+                &to,
+                &today,
+            )?;
+
+            if cli.format == Format::Json {
+                print_json(&calendar)?;
+            } else {
+                print_line(&crate::calendar::render_heatmap(&calendar, &styler)?);
+            }
+            Ok(())
+        }
+
         Command::Due(args) => {
             ensure_format_supported(cli.format, false)?;
 
-            let date = args.date.as_deref().unwrap_or(&today);
-            parse_date_string(date, "date")?;
+            if let Some(d) = args.date.as_deref() {
+                parse_date_string(d, "date")?;
+            }
 
             let db = read_db(&db_path)?;
-            let data = build_due(&db, date, args.include_archived)?;
+            let date = today.or(args.date.as_deref(), &db.meta)?;
+            let data = build_due(&db, &date, args.include_archived)?;
 
             if cli.format == Format::Json {
                 print_json(&data)?;
@@ -1948,7 +2660,12 @@ fn run(cli: Cli) -> Result<(), CliError> {
                             let progress = if h.period == "day" {
                                 format!("{}/{}", h.quantity, h.target)
                             } else {
-                                format!("{}/{} (weekly)", h.quantity, h.target)
+                                format!(
+                                    "{}/{} ({})",
+                                    h.quantity,
+                                    h.target,
+                                    period_adverb(&h.period)
+                                )
                             };
                             vec![
                                 h.id.clone(),
@@ -1971,9 +2688,9 @@ fn run(cli: Cli) -> Result<(), CliError> {
         }
 
         Command::Export(args) => {
-            // `export` supports json/csv; `table` is invalid.
+            // `export` supports json/csv/ics; `table` is invalid.
             if cli.format == Format::Table {
-                return Err(CliError::usage("`habit export` requires --format json|csv"));
+                return Err(CliError::usage("`habit export` requires --format json|csv|ics"));
             }
 
             let from = args.from.as_deref();
@@ -1992,26 +2709,15 @@ fn run(cli: Cli) -> Result<(), CliError> {
             }
 
             let db = read_db(&db_path)?;
-            let habits = list_habits(&db, args.include_archived);
-            let habit_ids: BTreeSet<String> = habits.iter().map(|h| h.id.clone()).collect();
-            let checkins = list_checkins_in_range(&db, from, to, Some(&habit_ids));
+            let payload = build_export(&db, from, to, args.include_archived);
 
-            if cli.format == Format::Json {
-                #[derive(serde::Serialize)]
-                struct Payload {
-                    version: u32,
-                    habits: Vec<crate::model::Habit>,
-                    checkins: Vec<crate::model::Checkin>,
-                }
-
-                let payload = Payload {
-                    version: 1,
-                    habits,
-                    checkins,
+            if cli.format == Format::Json || cli.format == Format::Ics {
+                let data = if cli.format == Format::Ics {
+                    crate::ics::build_ics(&payload)
+                } else {
+                    stable_to_string_pretty(&payload).map_err(|_| CliError::io("DB IO error"))?
+                        + "\n"
                 };
-                let data = stable_to_string_pretty(&payload)
-                    .map_err(|_| CliError::io("DB IO error"))?
-                    + "\n";
 
                 if let Some(p) = args.out.as_deref() {
                     fs::write(p, data.as_bytes()).map_err(|_| CliError::io("DB IO error"))?;
@@ -2028,7 +2734,345 @@ fn run(cli: Cli) -> Result<(), CliError> {
                     .out
                     .as_deref()
                     .ok_or_else(|| CliError::usage("CSV export requires --out <dir>"))?;
-                export_csv_to_dir(out_dir, &habits, &checkins)?;
+                export_csv_to_dir(out_dir, &payload)?;
+            }
+
+            Ok(())
+        }
+
+        Command::Import(args) => {
+            ensure_format_supported(cli.format, false)?;
+
+            let csv_options = !args.maps.is_empty() || args.date_format.is_some();
+            if args.from != ImportFromArg::Csv && csv_options {
+                return Err(CliError::usage("--map and --date-format only apply to --from csv"));
+            }
+            let read_text = |path: &str| {
+                fs::read_to_string(path).map_err(|e| {
+                    if e.kind() == std::io::ErrorKind::NotFound {
+                        CliError::not_found(format!("File not found: {}", path))
+                    } else {
+                        CliError::io("DB IO error")
+                    }
+                })
+            };
+
+            let run = |db: &mut crate::model::Db| -> Result<crate::import::ImportReport, CliError> {
+                match args.from {
+                    ImportFromArg::Json => {
+                        let payload = crate::import::parse_payload(&read_text(&args.file)?)?;
+                        crate::import::import(db, &payload)
+                    }
+                    ImportFromArg::Loop | ImportFromArg::Csv => {
+                        let data = if args.from == ImportFromArg::Loop {
+                            crate::tracker_import::read_loop(std::path::Path::new(&args.file))?
+                        } else {
+                            crate::tracker_import::read_csv(
+                                &read_text(&args.file)?,
+                                &args.maps,
+                                args.date_format.as_deref(),
+                            )?
+                        };
+                        let today = today.on(&db.meta)?;
+                        crate::tracker_import::import(db, &data, args.from.to_source(), &today)
+                    }
+                }
+            };
+
+            let report = if args.dry_run {
+                let mut db = read_db(&db_path)?;
+                run(&mut db)?
+            } else {
+                update_db(&db_path, run)?
+            };
+
+            if cli.format == Format::Json {
+                #[derive(serde::Serialize)]
+                struct Out {
+                    dry_run: bool,
+                    #[serde(flatten)]
+                    report: crate::import::ImportReport,
+                }
+                print_json(&Out {
+                    dry_run: args.dry_run,
+                    report,
+                })?;
+            } else {
+                let verb = if args.dry_run { "Would import" } else { "Imported" };
+                let mode = match (report.source, report.mode) {
+                    (crate::import::ImportSource::Loop, _) => "loop",
+                    (crate::import::ImportSource::Csv, _) => "csv",
+                    (_, crate::import::ImportMode::Restore) => "restore",
+                    (_, crate::import::ImportMode::Merge) => "merge",
+                };
+                print_line(&format!("{} {} ({})", verb, args.file, mode));
+                let rows: Vec<Vec<String>> = report
+                    .collections
+                    .iter()
+                    .map(|(name, c)| vec![name.clone(), c.added.to_string(), c.skipped.to_string()])
+                    .collect();
+                print_line(&render_simple_table(&["collection", "added", "skipped"], &rows));
+                for m in report.habits.iter().filter(|m| m.from != m.to || m.matched) {
+                    let how = if m.matched { "matched" } else { "new" };
+                    print_line(&format!("habit {} -> {} ({}, {})", m.from, m.to, m.name, how));
+                }
+                for m in report.routines.iter().filter(|m| m.from != m.to || m.matched) {
+                    let how = if m.matched { "matched" } else { "new" };
+                    print_line(&format!("routine {} -> {} ({}, {})", m.from, m.to, m.name, how));
+                }
+                if !report.unmapped.is_empty() {
+                    print_line(&format!("unmapped: {}", report.unmapped.join(", ")));
+                }
+                for n in report.notes.iter() {
+                    print_line(&format!("note: {}", n));
+                }
+            }
+            Ok(())
+        }
+
+        Command::Config(args) => {
+            ensure_format_supported(cli.format, false)?;
+
+            let cfg = match args.command {
+                ConfigCommand::Show => {
+                    let db = read_db(&db_path)?;
+                    crate::config::day_config(&db)
+                }
+                ConfigCommand::Set(s) => {
+                    if s.timezone.is_none() && s.day_start.is_none() {
+                        return Err(CliError::usage("No updates specified"));
+                    }
+                    update_db(&db_path, |db| {
+                        crate::config::set_day_config(
+                            db,
+                            s.timezone.as_deref(),
+                            s.day_start.as_deref(),
+                        )
+                    })?
+                }
+            };
+
+            if cli.format == Format::Json {
+                #[derive(serde::Serialize)]
+                struct Out {
+                    config: crate::config::DayConfig,
+                }
+                print_json(&Out { config: cfg })?;
+            } else {
+                print_line(&format!(
+                    "timezone: {}  day_start: {}",
+                    cfg.timezone.as_deref().unwrap_or("UTC"),
+                    cfg.day_start
+                ));
+            }
+            Ok(())
+        }
+
+        Command::Db(args) => {
+            ensure_format_supported(cli.format, false)?;
+
+            match args.command {
+                DbCommand::Migrate(m) => {
+                    let outcome = migrate_db(&db_path, m.dry_run)?;
+
+                    if cli.format == Format::Json {
+                        print_json(&outcome)?;
+                    } else {
+                        let report = &outcome.report;
+                        if report.is_noop() {
+                            print_line(&format!(
+                                "DB is up to date (version {})",
+                                report.to_version
+                            ));
+                        } else {
+                            let verb = if outcome.dry_run {
+                                "Would migrate"
+                            } else {
+                                "Migrated"
+                            };
+                            print_line(&format!(
+                                "{} DB v{} -> v{}",
+                                verb, report.from_version, report.to_version
+                            ));
+                            for step in report.steps.iter() {
+                                print_line(&format!(
+                                    "- v{} -> v{}: {}",
+                                    step.from_version, step.to_version, step.description
+                                ));
+                                for c in step.changes.iter() {
+                                    print_line(&format!("  - {}", c));
+                                }
+                            }
+                            if let Some(ref b) = outcome.backup {
+                                print_line(&format!("backup: {}", b));
+                            }
+                        }
+                    }
+                    Ok(())
+                }
+
+                DbCommand::Restore(r) => {
+                    if r.list {
+                        if r.backup.is_some() {
+                            return Err(CliError::usage(
+                                "Invalid flags: --list conflicts with <backup>",
+                            ));
+                        }
+                        let backups = list_backups(&db_path)?;
+                        if cli.format == Format::Json {
+                            #[derive(serde::Serialize)]
+                            struct Out {
+                                backups: Vec<crate::db::BackupInfo>,
+                            }
+                            print_json(&Out { backups })?;
+                        } else {
+                            let rows: Vec<Vec<String>> = backups
+                                .iter()
+                                .map(|b| vec![b.name.clone(), b.bytes.to_string()])
+                                .collect();
+                            print_line(&render_simple_table(&["name", "bytes"], &rows));
+                        }
+                        return Ok(());
+                    }
+
+                    let outcome = restore_db(&db_path, r.backup.as_deref())?;
+                    if cli.format == Format::Json {
+                        print_json(&outcome)?;
+                    } else {
+                        print_line(&format!("Restored DB from {}", outcome.restored_from));
+                        if let Some(ref b) = outcome.backup {
+                            print_line(&format!("previous DB saved as {}", b));
+                        }
+                    }
+                    Ok(())
+                }
+
+                DbCommand::Convert(c) => {
+                    let outcome =
+                        convert_db(&db_path, &c.to, c.to_storage.map(StorageArg::to_kind))?;
+                    if cli.format == Format::Json {
+                        print_json(&outcome)?;
+                    } else {
+                        print_line(&format!(
+                            "Converted {} ({}) -> {} ({}), version {}",
+                            outcome.from,
+                            outcome.from_storage.as_str(),
+                            outcome.to,
+                            outcome.to_storage.as_str(),
+                            outcome.version
+                        ));
+                    }
+                    Ok(())
+                }
+
+                DbCommand::Check(c) => {
+                    let issues = if c.repair {
+                        update_db(&db_path, |db| Ok(crate::integrity::repair(db)))?
+                    } else {
+                        crate::integrity::check(&read_db(&db_path)?)
+                    };
+                    let ok = issues.iter().all(|i| i.repaired);
+
+                    if cli.format == Format::Json {
+                        #[derive(serde::Serialize)]
+                        struct Out {
+                            ok: bool,
+                            repair: bool,
+                            issues: Vec<crate::integrity::Issue>,
+                        }
+                        print_json(&Out {
+                            ok,
+                            repair: c.repair,
+                            issues,
+                        })?;
+                    } else if issues.is_empty() {
+                        print_line("DB OK");
+                    } else {
+                        let rows: Vec<Vec<String>> = issues
+                            .iter()
+                            .map(|i| {
+                                let state = if i.repaired {
+                                    "repaired"
+                                } else if i.repairable {
+                                    "repairable"
+                                } else {
+                                    ""
+                                };
+                                vec![
+                                    i.kind.clone(),
+                                    i.collection.clone(),
+                                    i.id.clone(),
+                                    i.detail.clone(),
+                                    state.to_string(),
+                                ]
+                            })
+                            .collect();
+                        print_line(&render_simple_table(
+                            &["kind", "collection", "id", "detail", "state"],
+                            &rows,
+                        ));
+                    }
+                    Ok(())
+                }
+            }
+        }
+
+        Command::Undo(args) => {
+            ensure_format_supported(cli.format, false)?;
+
+            let undone = update_db(&db_path, |db| crate::journal::undo(db, args.steps))?;
+
+            if cli.format == Format::Json {
+                #[derive(serde::Serialize)]
+                struct Out {
+                    undone: Vec<crate::model::JournalEntry>,
+                }
+                print_json(&Out { undone })?;
+            } else {
+                for e in undone.iter() {
+                    print_line(&format!("Undone: {} ({})", e.id, e.command));
+                }
+            }
+
+            Ok(())
+        }
+
+        Command::History(args) => {
+            ensure_format_supported(cli.format, false)?;
+
+            let db = read_db(&db_path)?;
+            let operations = crate::journal::history(&db, args.limit);
+
+            if cli.format == Format::Json {
+                #[derive(serde::Serialize)]
+                struct Out {
+                    operations: Vec<crate::model::JournalEntry>,
+                }
+                print_json(&Out { operations })?;
+            } else {
+                let rows: Vec<Vec<String>> = operations
+                    .iter()
+                    .map(|e| {
+                        let state = if let Some(by) = e.undone_by.as_deref() {
+                            format!("undone by {}", by)
+                        } else if !e.undoes.is_empty() {
+                            format!("undo of {}", e.undoes.join(","))
+                        } else {
+                            String::new()
+                        };
+                        vec![
+                            e.id.clone(),
+                            e.ts.clone(),
+                            e.command.clone(),
+                            e.changes.len().to_string(),
+                            state,
+                        ]
+                    })
+                    .collect();
+                print_line(&render_simple_table(
+                    &["id", "ts", "command", "changes", "state"],
+                    &rows,
+                ));
             }
 
             Ok(())
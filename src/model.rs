@@ -12,6 +12,10 @@ fn default_cadence_minutes() -> u32 {
     180
 }
 
+fn default_day_start() -> String {
+    "00:00".to_string()
+}
+
 fn default_quiet_start() -> String {
     "23:00".to_string()
 }
@@ -25,7 +29,10 @@ pub struct Db {
     pub version: u32,
     pub meta: Meta,
     pub habits: Vec<Habit>,
-    pub checkins: Vec<Checkin>,
+
+    /// Append-only check-in events; per-day totals are derived from these.
+    #[serde(default)]
+    pub checkin_events: Vec<CheckinEvent>,
 
     /// Append-only declarations.
     #[serde(default)]
@@ -35,7 +42,8 @@ pub struct Db {
     #[serde(default)]
     pub excuses: Vec<Excuse>,
 
-    /// Penalty rules (state).
+    /// Penalty rule versions; each `penalty arm` adds one, the latest armed
+    /// on or before a date is in force on it.
     #[serde(default)]
     pub penalty_rules: Vec<PenaltyRule>,
 
@@ -58,6 +66,10 @@ pub struct Db {
     /// Nag configuration + state (automation-facing; messaging is handled by OpenClaw).
     #[serde(default)]
     pub nag: Nag,
+
+    /// Recent mutations (one entry per command invocation), oldest first.
+    #[serde(default)]
+    pub journal: Vec<JournalEntry>,
 }
 
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
@@ -75,6 +87,17 @@ pub struct Meta {
 
     #[serde(default = "default_next_counter")]
     pub next_routine_number: u32,
+
+    #[serde(default = "default_next_counter")]
+    pub next_checkin_number: u32,
+
+    /// Timezone used to derive "today": IANA name or fixed offset (`+09:00`). None = UTC.
+    #[serde(default)]
+    pub timezone: Option<String>,
+
+    /// Local time (HH:MM) at which a new logical day starts.
+    #[serde(default = "default_day_start")]
+    pub day_start: String,
 }
 
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
@@ -95,6 +118,46 @@ pub struct Habit {
     /// Maximum number of allowed excused days per ISO week.
     #[serde(default = "default_excuse_quota_per_week")]
     pub excuse_quota_per_week: u32,
+
+    /// `goal` (reach at least the target) or `limit` (stay at or under it).
+    #[serde(default)]
+    pub kind: HabitKind,
+
+    /// How allowed excuses count in streaks, success rates and strength.
+    #[serde(default)]
+    pub excuse_policy: ExcusePolicy,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HabitKind {
+    #[default]
+    Goal,
+    Limit,
+}
+
+/// Effect of an allowed excuse on an unmet scheduled day (day-period habits).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExcusePolicy {
+    /// Streak freeze: the day neither breaks nor extends a streak and is left
+    /// out of success rates.
+    #[default]
+    Freeze,
+    /// The day counts as met.
+    Count,
+    /// Excuses are ignored: the day is a miss.
+    Ignore,
+}
+
+impl ExcusePolicy {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ExcusePolicy::Freeze => "freeze",
+            ExcusePolicy::Count => "count",
+            ExcusePolicy::Ignore => "ignore",
+        }
+    }
 }
 
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
@@ -103,6 +166,7 @@ pub struct Target {
     pub quantity: u32,
 }
 
+/// Per-day check-in total (derived from `CheckinEvent`s, never stored).
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct Checkin {
     pub habit_id: String,
@@ -110,6 +174,29 @@ pub struct Checkin {
     pub quantity: u32,
 }
 
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct CheckinEvent {
+    pub id: String,
+    pub habit_id: String,
+    pub date: String,
+    pub ts: String,
+    pub kind: CheckinEventKind,
+    /// Signed change to the day's total; `set`/`delete` carry the compensating delta.
+    pub delta: i64,
+    #[serde(default)]
+    pub note: Option<String>,
+    /// Who recorded the event (`cli`, `migration`, `import`, ...).
+    pub source: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CheckinEventKind {
+    Add,
+    Set,
+    Delete,
+}
+
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct Declaration {
     pub id: String,
@@ -144,11 +231,55 @@ pub struct PenaltyRule {
     pub multiplier: u32,
     /// Maximum debt quantity.
     pub cap: u32,
-    /// Deadline window (days) for the debt; informational for MVP.
+    /// Days a debt stays payable, starting at its due date (0 is treated as 1).
     pub deadline_days: u32,
 
     pub armed_date: String,
     pub armed_ts: String,
+
+    /// What happens when a debt passes its deadline unpaid.
+    #[serde(default)]
+    pub on_default: DefaultConsequence,
+
+    /// Set by `penalty disarm`: the rule no longer applies from this date.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub disarmed_date: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub disarmed_ts: Option<String>,
+
+    /// Date ranges with penalties paused (set by `penalty pause`); they apply
+    /// to the habit whichever version is in force.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub pauses: Vec<PenaltyPause>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct PenaltyPause {
+    pub from: String,
+    pub to: String,
+    pub ts: String,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DefaultConsequence {
+    /// Only record the `default` action.
+    #[default]
+    Event,
+    /// Re-issue the debt as a new one, times the multiplier (capped).
+    Escalate,
+    /// Allowed excuses are recorded as denied while a defaulted debt is open.
+    FreezeExcuses,
+}
+
+impl DefaultConsequence {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            DefaultConsequence::Event => "event",
+            DefaultConsequence::Escalate => "escalate",
+            DefaultConsequence::FreezeExcuses => "freeze_excuses",
+        }
+    }
 }
 
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
@@ -163,6 +294,16 @@ pub struct PenaltyDebt {
     pub rule_id: String,
     pub created_date: String,
     pub created_ts: String,
+    /// Last day the debt can be paid; debts created before deadlines were
+    /// enforced have none and use `due_date`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub deadline_date: Option<String>,
+}
+
+impl PenaltyDebt {
+    pub fn deadline(&self) -> &str {
+        self.deadline_date.as_deref().unwrap_or(&self.due_date)
+    }
 }
 
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
@@ -173,6 +314,9 @@ pub struct PenaltyAction {
     pub date: String,
     pub ts: String,
     pub reason: String,
+    /// Quantity paid down; only set on `payment` actions.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub quantity: Option<u32>,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
@@ -180,6 +324,17 @@ pub struct PenaltyAction {
 pub enum PenaltyActionKind {
     Resolve,
     Void,
+    /// The debt passed its deadline unpaid; it stays open.
+    Default,
+    /// Surplus check-ins paid part of the debt; it stays open until fully paid.
+    Payment,
+}
+
+impl PenaltyActionKind {
+    /// Whether the action settles the debt.
+    pub fn closes(self) -> bool {
+        matches!(self, PenaltyActionKind::Resolve | PenaltyActionKind::Void)
+    }
 }
 
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
@@ -268,7 +423,7 @@ pub enum RoutineActionKind {
     Done,
 }
 
-#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
 pub struct Nag {
     #[serde(default)]
     pub config: NagConfig,
@@ -276,15 +431,6 @@ pub struct Nag {
     pub state: NagState,
 }
 
-impl Default for Nag {
-    fn default() -> Self {
-        Self {
-            config: NagConfig::default(),
-            state: NagState::default(),
-        }
-    }
-}
-
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct NagConfig {
     #[serde(default = "default_quiet_start")]
@@ -305,7 +451,7 @@ impl Default for NagConfig {
     }
 }
 
-#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
 pub struct NagState {
     #[serde(default)]
     pub snoozed_until: Option<String>,
@@ -315,28 +461,52 @@ pub struct NagState {
     pub last_sent_ts: Option<String>,
 }
 
-impl Default for NagState {
-    fn default() -> Self {
-        Self {
-            snoozed_until: None,
-            snooze_reason: None,
-            last_sent_ts: None,
-        }
-    }
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct JournalEntry {
+    pub id: String,
+    pub ts: String,
+    /// The invocation that produced the change (global options stripped).
+    pub command: String,
+    pub changes: Vec<JournalChange>,
+    /// For `habit undo` entries: the operations it reverted.
+    #[serde(default)]
+    pub undoes: Vec<String>,
+    #[serde(default)]
+    pub undone_by: Option<String>,
+}
+
+/// One JSON-pointer level change. `before` is null for `add`, `after` is null for `remove`.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct JournalChange {
+    pub op: JournalOp,
+    pub path: String,
+    pub before: serde_json::Value,
+    pub after: serde_json::Value,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JournalOp {
+    Add,
+    Remove,
+    Replace,
 }
 
 pub fn default_db() -> Db {
     Db {
-        version: 1,
+        version: crate::db::CURRENT_DB_VERSION,
         meta: Meta {
             next_habit_number: 1,
             next_declaration_number: 1,
             next_excuse_number: 1,
             next_penalty_rule_number: 1,
             next_routine_number: 1,
+            next_checkin_number: 1,
+            timezone: None,
+            day_start: default_day_start(),
         },
         habits: Vec::new(),
-        checkins: Vec::new(),
+        checkin_events: Vec::new(),
         declarations: Vec::new(),
         excuses: Vec::new(),
         penalty_rules: Vec::new(),
@@ -345,5 +515,6 @@ pub fn default_db() -> Db {
         routines: Vec::new(),
         routine_sessions: Vec::new(),
         nag: Nag::default(),
+        journal: Vec::new(),
     }
 }
@@ -38,12 +38,10 @@ fn quiet_end_at(now: DateTime<FixedOffset>, start: NaiveTime, end: NaiveTime) ->
     let wraps = start > end;
 
     let base_date: NaiveDate = now.date_naive();
-    let end_date = if !wraps {
-        base_date
-    } else if now_t < end {
-        base_date
-    } else {
+    let end_date = if wraps && now_t >= end {
         base_date + Duration::days(1)
+    } else {
+        base_date
     };
 
     let naive = end_date.and_time(end);
@@ -21,6 +21,14 @@ impl Styler {
     pub fn gray(&self, s: &str) -> String {
         self.wrap("\u{001b}[90m", s)
     }
+
+    pub fn yellow(&self, s: &str) -> String {
+        self.wrap("\u{001b}[33m", s)
+    }
+
+    pub fn red(&self, s: &str) -> String {
+        self.wrap("\u{001b}[31m", s)
+    }
 }
 
 /// Calculate display width of a string, accounting for Unicode/emoji.
@@ -1,9 +1,15 @@
-use crate::completion::{counted_quantity, is_declared};
-use crate::date::{add_days, parse_date_string};
+use crate::completion::CompletionIndex;
+use crate::date::{
+    add_days, date_range_inclusive, days_between, iso_week_end, iso_week_id, iso_week_start,
+    parse_date_string,
+};
 use crate::error::CliError;
-use crate::excuses::has_allowed_excuse;
 use crate::habits::is_scheduled_on;
-use crate::model::{Db, PenaltyAction, PenaltyActionKind, PenaltyDebt, PenaltyRule};
+use crate::model::{
+    Db, DefaultConsequence, Habit, HabitKind, PenaltyAction, PenaltyActionKind, PenaltyDebt,
+    PenaltyPause, PenaltyRule,
+};
+use crate::period::{window_starts, window_sum};
 use crate::ts::validate_rfc3339;
 use std::collections::{BTreeMap, BTreeSet};
 
@@ -18,7 +24,22 @@ pub fn next_penalty_rule_id(db: &mut Db) -> String {
     id
 }
 
-pub fn upsert_rule(
+/// Latest rule version of `habit_id`, by armed date then id.
+fn latest_rule_index(db: &Db, habit_id: &str) -> Option<usize> {
+    db.penalty_rules
+        .iter()
+        .enumerate()
+        .filter(|(_, r)| r.habit_id == habit_id)
+        .max_by(|(_, a), (_, b)| (&a.armed_date, &a.id).cmp(&(&b.armed_date, &b.id)))
+        .map(|(i, _)| i)
+}
+
+/// Arms `habit_id` with a new rule version; earlier versions stay on record
+/// for the dates they covered. Re-arming with the latest version's settings
+/// is a no-op whatever `armed_date` says (the version already applies), as
+/// long as that version hasn't been disarmed.
+#[allow(clippy::too_many_arguments)]
+pub fn arm_rule(
     db: &mut Db,
     habit_id: &str,
     armed_date: &str,
@@ -26,6 +47,7 @@ pub fn upsert_rule(
     multiplier: u32,
     cap: u32,
     deadline_days: u32,
+    on_default: DefaultConsequence,
 ) -> Result<PenaltyRule, CliError> {
     parse_date_string(armed_date, "date")?;
     validate_rfc3339(armed_ts, "ts")?;
@@ -37,13 +59,16 @@ pub fn upsert_rule(
         return Err(CliError::usage("Invalid cap"));
     }
 
-    if let Some(i) = db.penalty_rules.iter().position(|r| r.habit_id == habit_id) {
-        db.penalty_rules[i].multiplier = multiplier;
-        db.penalty_rules[i].cap = cap;
-        db.penalty_rules[i].deadline_days = deadline_days;
-        db.penalty_rules[i].armed_date = armed_date.to_string();
-        db.penalty_rules[i].armed_ts = armed_ts.trim().to_string();
-        return Ok(db.penalty_rules[i].clone());
+    if let Some(i) = latest_rule_index(db, habit_id) {
+        let r = &db.penalty_rules[i];
+        if r.disarmed_date.is_none()
+            && r.multiplier == multiplier
+            && r.cap == cap
+            && r.deadline_days == deadline_days
+            && r.on_default == on_default
+        {
+            return Ok(r.clone());
+        }
     }
 
     let rule = PenaltyRule {
@@ -54,31 +79,200 @@ pub fn upsert_rule(
         deadline_days,
         armed_date: armed_date.to_string(),
         armed_ts: armed_ts.trim().to_string(),
+        on_default,
+        disarmed_date: None,
+        disarmed_ts: None,
+        pauses: Vec::new(),
     };
     db.penalty_rules.push(rule.clone());
     Ok(rule)
 }
 
+/// Ends the latest rule version of `habit_id` on `date`. Open debts stay
+/// open; disarming again returns the version unchanged.
+pub fn disarm_rule(
+    db: &mut Db,
+    habit_id: &str,
+    date: &str,
+    ts: &str,
+) -> Result<PenaltyRule, CliError> {
+    parse_date_string(date, "date")?;
+    validate_rfc3339(ts, "ts")?;
+
+    let i = latest_rule_index(db, habit_id).ok_or_else(|| {
+        CliError::not_found(format!("No penalty rule armed for habit: {}", habit_id))
+    })?;
+    let rule = &mut db.penalty_rules[i];
+    if rule.disarmed_date.is_some() {
+        return Ok(rule.clone());
+    }
+    if date < rule.armed_date.as_str() {
+        return Err(CliError::usage(format!(
+            "Cannot disarm before the rule was armed ({})",
+            rule.armed_date
+        )));
+    }
+    rule.disarmed_date = Some(date.to_string());
+    rule.disarmed_ts = Some(ts.trim().to_string());
+    Ok(rule.clone())
+}
+
+/// Pauses penalties for `habit_id` over `from..=to` (recorded on the latest
+/// rule version). Adding the same range twice is a no-op.
+pub fn pause_rule(
+    db: &mut Db,
+    habit_id: &str,
+    from: &str,
+    to: &str,
+    ts: &str,
+) -> Result<PenaltyRule, CliError> {
+    parse_date_string(from, "from")?;
+    parse_date_string(to, "to")?;
+    validate_rfc3339(ts, "ts")?;
+    if from > to {
+        return Err(CliError::usage("Invalid range: from > to"));
+    }
+
+    let i = latest_rule_index(db, habit_id).ok_or_else(|| {
+        CliError::not_found(format!("No penalty rule armed for habit: {}", habit_id))
+    })?;
+    let rule = &mut db.penalty_rules[i];
+    if !rule.pauses.iter().any(|p| p.from == from && p.to == to) {
+        rule.pauses.push(PenaltyPause {
+            from: from.to_string(),
+            to: to.to_string(),
+            ts: ts.trim().to_string(),
+        });
+    }
+    Ok(rule.clone())
+}
+
+/// Rule version of `habit_id` in force on `date`: the latest armed on or
+/// before it, unless that version was disarmed by then.
+pub fn rule_in_force<'a>(db: &'a Db, habit_id: &str, date: &str) -> Option<&'a PenaltyRule> {
+    db.penalty_rules
+        .iter()
+        .filter(|r| r.habit_id == habit_id && r.armed_date.as_str() <= date)
+        .max_by(|a, b| (&a.armed_date, &a.id).cmp(&(&b.armed_date, &b.id)))
+        .filter(|r| r.disarmed_date.as_deref().is_none_or(|d| d > date))
+}
+
+/// Whether any rule version of `habit_id` pauses penalties on `date`.
+pub fn penalties_paused(db: &Db, habit_id: &str, date: &str) -> bool {
+    db.penalty_rules
+        .iter()
+        .filter(|r| r.habit_id == habit_id)
+        .flat_map(|r| r.pauses.iter())
+        .any(|p| p.from.as_str() <= date && date <= p.to.as_str())
+}
+
 pub fn debt_id_for(habit_id: &str, trigger_date: &str) -> String {
     format!("pd_{}_{}", habit_id, compact_date(trigger_date))
 }
 
-fn action_id_for(debt_id: &str, kind: PenaltyActionKind) -> String {
+pub fn action_id_for(debt_id: &str, kind: PenaltyActionKind) -> String {
     let k = match kind {
         PenaltyActionKind::Resolve => "resolve",
         PenaltyActionKind::Void => "void",
+        PenaltyActionKind::Default => "default",
+        PenaltyActionKind::Payment => "pay",
     };
     format!("pa_{}_{}", debt_id, k)
 }
 
+/// Debts settled by a resolve/void action.
 pub fn debt_closed_map(db: &Db) -> BTreeSet<String> {
     let mut closed: BTreeSet<String> = BTreeSet::new();
-    for a in db.penalty_actions.iter() {
+    for a in db.penalty_actions.iter().filter(|a| a.kind.closes()) {
         closed.insert(a.debt_id.clone());
     }
     closed
 }
 
+/// Debts with a `default` action.
+fn defaulted_set(db: &Db) -> BTreeSet<String> {
+    db.penalty_actions
+        .iter()
+        .filter(|a| a.kind == PenaltyActionKind::Default)
+        .map(|a| a.debt_id.clone())
+        .collect()
+}
+
+/// Quantity paid so far per debt, from `payment` actions.
+fn paid_map(db: &Db) -> BTreeMap<String, u32> {
+    let mut paid: BTreeMap<String, u32> = BTreeMap::new();
+    for a in db.penalty_actions.iter() {
+        if a.kind == PenaltyActionKind::Payment {
+            let e = paid.entry(a.debt_id.clone()).or_insert(0);
+            *e = e.saturating_add(a.quantity.unwrap_or(0));
+        }
+    }
+    paid
+}
+
+/// Last payable day for a debt due on `due_date`. Weekly debts always get at
+/// least the whole following week.
+fn deadline_for(rule: &PenaltyRule, period: &str, due_date: &str) -> Result<String, CliError> {
+    let mut days = rule.deadline_days.max(1);
+    if period == "week" {
+        days = days.max(7);
+    }
+    add_days(due_date, days as i32 - 1)
+}
+
+/// An outstanding debt with its deadline position relative to a date.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct DebtStatus {
+    #[serde(flatten)]
+    pub debt: PenaltyDebt,
+    pub deadline_date: String,
+    /// `open` or `defaulted`
+    pub status: String,
+    /// Quantity already paid down by check-ins.
+    pub paid: u32,
+    /// Days left until the deadline (0 on the deadline itself); None once overdue.
+    pub days_remaining: Option<u32>,
+    /// Days past the deadline; None while still payable.
+    pub days_overdue: Option<u32>,
+}
+
+pub fn debt_statuses_as_of(db: &Db, date: &str) -> Result<Vec<DebtStatus>, CliError> {
+    let defaulted = defaulted_set(db);
+    let paid = paid_map(db);
+    let mut out: Vec<DebtStatus> = Vec::new();
+    for d in outstanding_debts_as_of(db, date)? {
+        let left = days_between(date, d.deadline())?;
+        out.push(DebtStatus {
+            deadline_date: d.deadline().to_string(),
+            status: if defaulted.contains(&d.id) {
+                "defaulted".to_string()
+            } else {
+                "open".to_string()
+            },
+            paid: paid.get(&d.id).copied().unwrap_or(0),
+            days_remaining: (left >= 0).then_some(left as u32),
+            days_overdue: (left < 0).then_some(left.unsigned_abs()),
+            debt: d,
+        });
+    }
+    Ok(out)
+}
+
+/// Whether `habit_id` has an open defaulted debt while a `freeze_excuses`
+/// rule is in force on `date`.
+pub fn excuses_frozen(db: &Db, habit_id: &str, date: &str) -> bool {
+    let frozen_rule = rule_in_force(db, habit_id, date)
+        .is_some_and(|r| r.on_default == DefaultConsequence::FreezeExcuses);
+    if !frozen_rule {
+        return false;
+    }
+    let closed = debt_closed_map(db);
+    let defaulted = defaulted_set(db);
+    db.penalty_debts
+        .iter()
+        .any(|d| d.habit_id == habit_id && defaulted.contains(&d.id) && !closed.contains(&d.id))
+}
+
 pub fn outstanding_debts_as_of(db: &Db, date: &str) -> Result<Vec<PenaltyDebt>, CliError> {
     parse_date_string(date, "date")?;
     let closed = debt_closed_map(db);
@@ -104,103 +298,462 @@ pub fn outstanding_debts_as_of(db: &Db, date: &str) -> Result<Vec<PenaltyDebt>,
     Ok(out)
 }
 
-fn rule_map(db: &Db) -> BTreeMap<String, PenaltyRule> {
+/// Rules in force on `date` per habit id, leaving out paused habits.
+fn active_rules(db: &Db, date: &str) -> BTreeMap<String, PenaltyRule> {
     let mut m = BTreeMap::new();
-    for r in db.penalty_rules.iter() {
-        m.insert(r.habit_id.clone(), r.clone());
+    for h in db.habits.iter() {
+        if penalties_paused(db, &h.id, date) {
+            continue;
+        }
+        if let Some(r) = rule_in_force(db, &h.id, date) {
+            m.insert(h.id.clone(), r.clone());
+        }
     }
     m
 }
 
-pub fn tick(
-    db: &mut Db,
+/// Debt quantity for a base amount under `rule`.
+fn sized(rule: &PenaltyRule, base_qty: u32) -> u32 {
+    base_qty.saturating_mul(rule.multiplier).min(rule.cap)
+}
+
+/// Largest open debt of `habit_id` whose deadline falls in `from..=to`.
+fn open_debt_due(
+    db: &Db,
+    closed: &BTreeSet<String>,
+    habit_id: &str,
+    from: &str,
+    to: &str,
+) -> Option<PenaltyDebt> {
+    db.penalty_debts
+        .iter()
+        .filter(|d| d.habit_id == habit_id)
+        .filter(|d| d.deadline() >= from && d.deadline() <= to)
+        .filter(|d| !closed.contains(&d.id))
+        .cloned()
+        .max_by(|a, b| a.quantity.cmp(&b.quantity))
+}
+
+/// Day-period habits: a scheduled, unexcused day that is not met (or on
+/// which an open debt reaches its deadline) owes debt due the next day.
+fn day_debt(
+    db: &Db,
+    idx: &CompletionIndex,
+    closed: &BTreeSet<String>,
+    h: &Habit,
+    rule: &PenaltyRule,
     date: &str,
     ts: &str,
-    include_archived: bool,
-) -> Result<Vec<PenaltyDebt>, CliError> {
-    parse_date_string(date, "date")?;
-    validate_rfc3339(ts, "ts")?;
+) -> Result<Option<PenaltyDebt>, CliError> {
+    if !is_scheduled_on(h, date)? {
+        return Ok(None);
+    }
 
-    let rules = rule_map(db);
-    let closed = debt_closed_map(db);
+    if idx.has_allowed_excuse(&h.id, date) {
+        return Ok(None);
+    }
 
-    let mut created: Vec<PenaltyDebt> = Vec::new();
+    let habit_done = idx.day_met(h, date);
 
-    for h in db.habits.iter() {
+    // If outstanding debt reaches its deadline today unpaid, treat it as a miss.
+    let outstanding_due_today = open_debt_due(db, closed, &h.id, date, date);
+
+    let missed_debt = outstanding_due_today.is_some();
+    if habit_done && !missed_debt {
+        return Ok(None);
+    }
+
+    let debt_id = debt_id_for(&h.id, date);
+    if db.penalty_debts.iter().any(|d| d.id == debt_id) {
+        // idempotent
+        return Ok(None);
+    }
+
+    // Goal habits owe the target; limit habits owe the overage.
+    let miss_qty = match h.kind {
+        HabitKind::Goal => h.target.quantity,
+        HabitKind::Limit => idx
+            .counted_quantity(h, date)
+            .saturating_sub(h.target.quantity)
+            .max(1),
+    };
+    let base_qty = match outstanding_due_today {
+        Some(d) => d.quantity.max(miss_qty),
+        None => miss_qty,
+    };
+
+    let due_date = add_days(date, 1)?;
+    Ok(Some(PenaltyDebt {
+        id: debt_id,
+        habit_id: h.id.clone(),
+        trigger_date: date.to_string(),
+        deadline_date: Some(deadline_for(rule, "day", &due_date)?),
+        due_date,
+        quantity: sized(rule, base_qty),
+        rule_id: rule.id.clone(),
+        created_date: date.to_string(),
+        created_ts: ts.trim().to_string(),
+    }))
+}
+
+/// Week-period habits are evaluated when `date` closes their ISO week
+/// (Sunday), if both the habit and the rule cover the whole week and no day
+/// of it is paused. A shortfall (goals) or overage (limits), or an open debt whose
+/// deadline fell in that week, owes debt due the following Monday, keyed by
+/// the ISO week id.
+fn week_debt(
+    db: &Db,
+    idx: &CompletionIndex,
+    closed: &BTreeSet<String>,
+    h: &Habit,
+    rule: &PenaltyRule,
+    date: &str,
+    ts: &str,
+) -> Result<Option<PenaltyDebt>, CliError> {
+    if iso_week_end(date)? != date {
+        return Ok(None);
+    }
+    let week_start = iso_week_start(date)?;
+    if h.created_date.as_str() > week_start.as_str() {
+        // the week the habit was added in is a partial week: never owed
+        return Ok(None);
+    }
+    for d in date_range_inclusive(&week_start, date)? {
+        if penalties_paused(db, &h.id, &d) || rule_in_force(db, &h.id, &d).is_none() {
+            // likewise a week the rule did not cover every day of (armed
+            // mid-week, disarmed or paused on any day)
+            return Ok(None);
+        }
+    }
+
+    let (_, counted) = window_sum(idx, h, "week", &week_start)?;
+    let shortfall = match h.kind {
+        HabitKind::Goal => h.target.quantity.saturating_sub(counted),
+        HabitKind::Limit => counted.saturating_sub(h.target.quantity),
+    };
+    let outstanding = open_debt_due(db, closed, &h.id, &week_start, date);
+    if shortfall == 0 && outstanding.is_none() {
+        return Ok(None);
+    }
+
+    let debt_id = debt_id_for(&h.id, &iso_week_id(&week_start)?);
+    if db.penalty_debts.iter().any(|d| d.id == debt_id) {
+        // idempotent
+        return Ok(None);
+    }
+
+    let base_qty = match outstanding {
+        Some(d) => d.quantity.max(shortfall),
+        None => shortfall,
+    };
+
+    let due_date = add_days(&week_start, 7)?;
+    Ok(Some(PenaltyDebt {
+        id: debt_id,
+        habit_id: h.id.clone(),
+        trigger_date: date.to_string(),
+        deadline_date: Some(deadline_for(rule, "week", &due_date)?),
+        due_date,
+        quantity: sized(rule, base_qty),
+        rule_id: rule.id.clone(),
+        created_date: date.to_string(),
+        created_ts: ts.trim().to_string(),
+    }))
+}
+
+/// What one `tick` date produced.
+#[derive(Debug, Clone, Default, serde::Serialize)]
+pub struct TickOutcome {
+    pub created: Vec<PenaltyDebt>,
+    pub defaulted: Vec<PenaltyAction>,
+    /// Check-in payments, each followed by a `resolve` when it settles the debt.
+    pub payments: Vec<PenaltyAction>,
+}
+
+/// Marks open debts whose deadline is before `date` as defaulted and applies
+/// the consequence of the rule in force. Escalated debts are returned, not yet
+/// stored.
+fn default_overdue(
+    db: &mut Db,
+    rules: &BTreeMap<String, PenaltyRule>,
+    closed: &BTreeSet<String>,
+    date: &str,
+    ts: &str,
+    include_archived: bool,
+) -> Result<(Vec<PenaltyAction>, Vec<PenaltyDebt>), CliError> {
+    let defaulted = defaulted_set(db);
+    let mut actions: Vec<PenaltyAction> = Vec::new();
+    let mut escalated: Vec<PenaltyDebt> = Vec::new();
+
+    for d in db.penalty_debts.iter() {
+        if closed.contains(&d.id) || defaulted.contains(&d.id) || d.deadline() >= date {
+            continue;
+        }
+        let Some(h) = db.habits.iter().find(|h| h.id == d.habit_id) else {
+            continue;
+        };
         if !include_archived && h.archived {
             continue;
         }
-        if h.target.period != "day" {
-            // MVP: penalty tick only evaluates day-period habits.
+        let Some(rule) = rules.get(&d.habit_id) else {
             continue;
+        };
+
+        actions.push(PenaltyAction {
+            id: action_id_for(&d.id, PenaltyActionKind::Default),
+            debt_id: d.id.clone(),
+            kind: PenaltyActionKind::Default,
+            date: date.to_string(),
+            ts: ts.trim().to_string(),
+            reason: format!(
+                "deadline {} passed; {}",
+                d.deadline(),
+                rule.on_default.as_str()
+            ),
+            quantity: None,
+        });
+
+        if rule.on_default == DefaultConsequence::Escalate {
+            let due_date = add_days(date, 1)?;
+            escalated.push(PenaltyDebt {
+                id: format!("{}_x", d.id),
+                habit_id: d.habit_id.clone(),
+                trigger_date: date.to_string(),
+                deadline_date: Some(deadline_for(rule, &h.target.period, &due_date)?),
+                due_date,
+                quantity: sized(rule, d.quantity),
+                rule_id: rule.id.clone(),
+                created_date: date.to_string(),
+                created_ts: ts.trim().to_string(),
+            });
         }
+    }
 
-        let rule = match rules.get(&h.id) {
-            Some(r) => r.clone(),
-            None => continue,
-        };
+    db.penalty_actions.extend(actions.iter().cloned());
+    Ok((actions, escalated))
+}
 
-        if !is_scheduled_on(h, date)? {
-            continue;
+/// Check-in surplus of a goal habit: `(from, to, surplus)` per day (day
+/// habits) or per ISO week clipped to `date` (week habits), from `from` on.
+fn surplus_windows(
+    idx: &CompletionIndex,
+    h: &Habit,
+    from: &str,
+    date: &str,
+) -> Result<Vec<(String, String, u32)>, CliError> {
+    let mut out: Vec<(String, String, u32)> = Vec::new();
+    match h.target.period.as_str() {
+        "day" => {
+            for d in date_range_inclusive(from, date)? {
+                let surplus = idx
+                    .counted_quantity(h, &d)
+                    .saturating_sub(h.target.quantity);
+                out.push((d.clone(), d, surplus));
+            }
+        }
+        "week" => {
+            for ws in window_starts("week", from, date)? {
+                let end = iso_week_end(&ws)?.min(date.to_string());
+                let (_, counted) = idx.range_sums(h, &ws, &end);
+                out.push((ws, end, counted.saturating_sub(h.target.quantity)));
+            }
         }
+        _ => {}
+    }
+    Ok(out)
+}
 
-        if has_allowed_excuse(db, &h.id, date) {
+/// Pays open debts of goal habits from check-ins above the target, logged on
+/// or after each debt's due date, oldest debt first. Each day's (or week's)
+/// surplus is spent once: payments already recorded against it are
+/// subtracted, so repeated ticks only record what is new. A debt paid in
+/// full is resolved. Payments continue while penalties are paused or
+/// disarmed.
+fn pay_from_checkins(
+    db: &mut Db,
+    idx: &CompletionIndex,
+    date: &str,
+    ts: &str,
+    include_archived: bool,
+) -> Result<Vec<PenaltyAction>, CliError> {
+    let mut recorded: Vec<PenaltyAction> = Vec::new();
+    let habits: Vec<Habit> = db.habits.clone();
+
+    for h in habits.iter() {
+        if (!include_archived && h.archived) || h.kind != HabitKind::Goal {
             continue;
         }
 
-        let done_qty = counted_quantity(db, h, date);
-        let declared = is_declared(db, h, date);
-        let habit_done = declared && done_qty >= h.target.quantity;
-
-        // If there is outstanding debt due today and it's not resolved/voided, treat it as a miss.
-        let outstanding_due_today: Option<PenaltyDebt> = db
+        let closed = debt_closed_map(db);
+        let mut open: Vec<PenaltyDebt> = db
             .penalty_debts
             .iter()
-            .filter(|d| d.habit_id == h.id)
-            .filter(|d| d.due_date == date)
-            .filter(|d| !closed.contains(&d.id))
+            .filter(|d| d.habit_id == h.id && !closed.contains(&d.id))
+            .filter(|d| d.due_date.as_str() <= date)
             .cloned()
-            .max_by(|a, b| a.quantity.cmp(&b.quantity));
-
-        let missed_debt = outstanding_due_today.is_some();
-        if habit_done && !missed_debt {
+            .collect();
+        if open.is_empty() {
             continue;
         }
+        open.sort_by(|a, b| (&a.due_date, &a.id).cmp(&(&b.due_date, &b.id)));
+
+        let debt_ids: BTreeSet<String> = db
+            .penalty_debts
+            .iter()
+            .filter(|d| d.habit_id == h.id)
+            .map(|d| d.id.clone())
+            .collect();
+        let mut paid = paid_map(db);
+        let mut settled: BTreeSet<String> = BTreeSet::new();
+
+        for (from, to, surplus) in surplus_windows(idx, h, &open[0].due_date, date)? {
+            let spent: u32 = db
+                .penalty_actions
+                .iter()
+                .filter(|a| a.kind == PenaltyActionKind::Payment && debt_ids.contains(&a.debt_id))
+                .filter(|a| a.date.as_str() >= from.as_str() && a.date.as_str() <= to.as_str())
+                .map(|a| a.quantity.unwrap_or(0))
+                .sum();
+            let mut available = surplus.saturating_sub(spent);
+
+            for d in open.iter() {
+                if available == 0 {
+                    break;
+                }
+                if settled.contains(&d.id) || d.due_date.as_str() > from.as_str() {
+                    continue;
+                }
+                let already = paid.get(&d.id).copied().unwrap_or(0);
+                let amount = available.min(d.quantity.saturating_sub(already));
+                if amount == 0 {
+                    continue;
+                }
+                available -= amount;
+                let total = already + amount;
+                paid.insert(d.id.clone(), total);
+
+                let base = format!("pa_{}_pay_{}", d.id, compact_date(&to));
+                let mut id = base.clone();
+                let mut n = 2;
+                while db.penalty_actions.iter().any(|a| a.id == id) {
+                    id = format!("{}_{}", base, n);
+                    n += 1;
+                }
+                let payment = PenaltyAction {
+                    id,
+                    debt_id: d.id.clone(),
+                    kind: PenaltyActionKind::Payment,
+                    date: to.clone(),
+                    ts: ts.trim().to_string(),
+                    reason: format!("paid {} ({}/{}) from check-ins", amount, total, d.quantity),
+                    quantity: Some(amount),
+                };
+                db.penalty_actions.push(payment.clone());
+                recorded.push(payment);
+
+                if total >= d.quantity {
+                    settled.insert(d.id.clone());
+                    let resolve = PenaltyAction {
+                        id: action_id_for(&d.id, PenaltyActionKind::Resolve),
+                        debt_id: d.id.clone(),
+                        kind: PenaltyActionKind::Resolve,
+                        date: to.clone(),
+                        ts: ts.trim().to_string(),
+                        reason: "paid in full by check-ins".to_string(),
+                        quantity: None,
+                    };
+                    db.penalty_actions.push(resolve.clone());
+                    recorded.push(resolve);
+                }
+            }
+        }
+    }
+
+    Ok(recorded)
+}
+
+pub fn tick(
+    db: &mut Db,
+    date: &str,
+    ts: &str,
+    include_archived: bool,
+) -> Result<TickOutcome, CliError> {
+    parse_date_string(date, "date")?;
+    validate_rfc3339(ts, "ts")?;
+
+    let rules = active_rules(db, date);
+    let idx = CompletionIndex::new(db);
+
+    // Payments come first so a debt paid off today neither defaults nor rolls forward.
+    let payments = pay_from_checkins(db, &idx, date, ts, include_archived)?;
+    let closed = debt_closed_map(db);
+
+    let (defaulted, mut created) =
+        default_overdue(db, &rules, &closed, date, ts, include_archived)?;
 
-        let debt_id = debt_id_for(&h.id, date);
-        if db.penalty_debts.iter().any(|d| d.id == debt_id) {
-            // idempotent
+    for h in db.habits.iter() {
+        if !include_archived && h.archived {
             continue;
         }
 
-        let base_qty = match outstanding_due_today {
-            Some(d) => d.quantity.max(h.target.quantity),
-            None => h.target.quantity,
+        let rule = match rules.get(&h.id) {
+            Some(r) => r,
+            None => continue,
         };
 
-        let mut qty = base_qty.saturating_mul(rule.multiplier);
-        qty = qty.min(rule.cap);
-
-        let due_date = add_days(date, 1)?;
-        let debt = PenaltyDebt {
-            id: debt_id,
-            habit_id: h.id.clone(),
-            trigger_date: date.to_string(),
-            due_date,
-            quantity: qty,
-            rule_id: rule.id.clone(),
-            created_date: date.to_string(),
-            created_ts: ts.trim().to_string(),
+        let debt = match h.target.period.as_str() {
+            "day" => day_debt(db, &idx, &closed, h, rule, date, ts)?,
+            "week" => week_debt(db, &idx, &closed, h, rule, date, ts)?,
+            // Month-period habits are not evaluated.
+            _ => None,
         };
-
-        db.penalty_debts.push(debt.clone());
-        created.push(debt);
+        if let Some(d) = debt {
+            created.push(d);
+        }
     }
 
+    db.penalty_debts.extend(created.iter().cloned());
+
     // Stable sort output.
     created.sort_by(|a, b| a.id.cmp(&b.id));
 
-    Ok(created)
+    Ok(TickOutcome {
+        created,
+        defaulted,
+        payments,
+    })
+}
+
+/// One day of a `tick_range` walk.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct DayTick {
+    pub date: String,
+    #[serde(flatten)]
+    pub outcome: TickOutcome,
+}
+
+/// Ticks every day of `from..=to` in order, so each day sees the debts and
+/// payments of the days before it (catch-up after missed runs).
+///
+/// Every day is stamped with the same `ts` (the time of the catch-up run):
+/// the dates say which day a debt or action belongs to, `ts` when it was
+/// recorded.
+pub fn tick_range(
+    db: &mut Db,
+    from: &str,
+    to: &str,
+    ts: &str,
+    include_archived: bool,
+) -> Result<Vec<DayTick>, CliError> {
+    parse_date_string(from, "from")?;
+    parse_date_string(to, "to")?;
+
+    let mut days: Vec<DayTick> = Vec::new();
+    for date in date_range_inclusive(from, to)? {
+        let outcome = tick(db, &date, ts, include_archived)?;
+        days.push(DayTick { date, outcome });
+    }
+    Ok(days)
 }
 
 pub fn resolve_or_void(
@@ -231,8 +784,12 @@ pub fn resolve_or_void(
         return Ok(a.clone());
     }
 
-    // If any action already exists for this debt, treat as idempotent (return the first).
-    if let Some(a) = db.penalty_actions.iter().find(|a| a.debt_id == debt_id) {
+    // If the debt is already settled, treat as idempotent (return the first).
+    if let Some(a) = db
+        .penalty_actions
+        .iter()
+        .find(|a| a.debt_id == debt_id && a.kind.closes())
+    {
         return Ok(a.clone());
     }
 
@@ -243,6 +800,7 @@ pub fn resolve_or_void(
         date: date.to_string(),
         ts: ts.trim().to_string(),
         reason: r.to_string(),
+        quantity: None,
     };
     db.penalty_actions.push(action.clone());
     Ok(action)
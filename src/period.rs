@@ -0,0 +1,70 @@
+//! Aggregation windows for week- and month-period targets.
+//!
+//! A `week` window is an ISO week (Mon..Sun); a `month` window is a calendar month.
+
+use crate::completion::CompletionIndex;
+use crate::date::{
+    add_days, iso_week_end, iso_week_id, iso_week_start, month_end, month_id, month_start,
+};
+use crate::error::CliError;
+use crate::model::Habit;
+
+/// First day of the window containing `date`.
+pub fn window_start(period: &str, date: &str) -> Result<String, CliError> {
+    match period {
+        "month" => month_start(date),
+        _ => iso_week_start(date),
+    }
+}
+
+/// Last day of the window containing `date`.
+pub fn window_end(period: &str, date: &str) -> Result<String, CliError> {
+    match period {
+        "month" => month_end(date),
+        _ => iso_week_end(date),
+    }
+}
+
+/// Window id: ISO week (`2026-W05`) or month (`2026-01`).
+pub fn window_id(period: &str, date: &str) -> Result<String, CliError> {
+    match period {
+        "month" => month_id(date),
+        _ => iso_week_id(&iso_week_start(date)?),
+    }
+}
+
+/// Start dates of every window overlapping `from..=to`, in order.
+pub fn window_starts(period: &str, from: &str, to: &str) -> Result<Vec<String>, CliError> {
+    let last = window_start(period, to)?;
+    let mut out: Vec<String> = Vec::new();
+    let mut cur = window_start(period, from)?;
+    while cur.as_str() <= last.as_str() {
+        let next = add_days(&window_end(period, &cur)?, 1)?;
+        out.push(cur);
+        cur = next;
+    }
+    Ok(out)
+}
+
+/// Sum of (raw, counted) quantities over the window starting at `start`.
+///
+/// Days before the habit's `created_date` are ignored.
+pub fn window_sum(
+    idx: &CompletionIndex,
+    habit: &Habit,
+    period: &str,
+    start: &str,
+) -> Result<(u32, u32), CliError> {
+    let end = window_end(period, start)?;
+    let from = start.max(habit.created_date.as_str());
+    Ok(idx.range_sums(habit, from, &end))
+}
+
+/// Label suffix used in tables, e.g. `(weekly)`.
+pub fn period_adverb(period: &str) -> &'static str {
+    match period {
+        "day" => "daily",
+        "month" => "monthly",
+        _ => "weekly",
+    }
+}
@@ -3,14 +3,19 @@
 //! Completion is calculated as:
 //! - **Daily habits**: successes / eligible_days where success = counted_quantity >= target
 //! - **Weekly habits**: successful_weeks / eligible_weeks where success = week_sum >= target
+//! - **Monthly habits**: successful_months / eligible_months where success = month_sum >= target
+//!
+//! For limit habits "success" means staying at or under the target instead.
 //!
 //! This matches the semantics used in `stats.rs`.
 
-use crate::completion::counted_quantity;
-use crate::date::{add_days, date_range_inclusive, iso_week_end, iso_week_start};
+use crate::completion::{meets_target, CompletionIndex};
+use crate::date::{add_days, date_range_inclusive};
 use crate::error::CliError;
-use crate::habits::{is_scheduled_on, stable_habit_sort};
-use crate::model::{Db, Habit};
+use crate::habits::{is_scheduled_on, stable_habit_sort, target_label};
+use crate::model::Habit;
+use crate::period::{window_end, window_starts, window_sum};
+use crate::strength::{habit_strength, Strength};
 
 /// Supported recap time ranges.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -38,22 +43,27 @@ impl RecapRange {
 pub struct RecapRow {
     pub habit_id: String,
     pub name: String,
-    /// "day" or "week"
+    /// "day", "week" or "month"
     pub period: String,
-    /// Human-readable target label, e.g. "8 cups/day" or "3/week"
+    /// Human-readable target label, e.g. "8/day", "3/week" or "<=2/day" for limits
     pub target_label: String,
     /// Raw target quantity
     pub target: u32,
-    /// Number of successes (days or weeks where target was met)
+    /// Number of successes (days, weeks or months where target was met)
     pub successes: u32,
-    /// Number of eligible periods (scheduled days or eligible weeks)
+    /// Number of eligible periods (scheduled days or eligible weeks/months)
     pub eligible: u32,
+    /// Unmet scheduled days covered by an allowed excuse; left out of
+    /// `eligible` under the `freeze` policy, counted as successes under `count`
+    pub excused_days: u32,
     /// Completion percentage as 0.0-1.0 (None if eligible=0)
     pub rate: Option<f64>,
     /// Completion percentage as 0-100 integer (None if eligible=0)
     pub percent: Option<u32>,
     /// The time range used
     pub range: RecapRangeInfo,
+    /// Habit strength as of the end of the range (None for monthly habits)
+    pub strength: Option<Strength>,
 }
 
 #[derive(Debug, Clone, serde::Serialize)]
@@ -87,11 +97,11 @@ pub fn compute_range_dates(range: RecapRange, today: &str) -> Result<(String, St
 
 /// Compute daily habit recap stats over a date range.
 fn compute_daily_recap(
-    db: &Db,
+    idx: &CompletionIndex,
     habit: &Habit,
     from: &str,
     to: &str,
-    range: RecapRange,
+    kind: &str,
 ) -> Result<RecapRow, CliError> {
     let days = date_range_inclusive(from, to)?;
 
@@ -104,11 +114,19 @@ fn compute_daily_recap(
         })
         .collect();
 
-    let eligible = scheduled_days.len() as u32;
-    let successes = scheduled_days
-        .iter()
-        .filter(|d| counted_quantity(db, habit, d) >= habit.target.quantity)
-        .count() as u32;
+    let mut eligible = 0u32;
+    let mut successes = 0u32;
+    let mut excused_days = 0u32;
+    for d in scheduled_days.iter() {
+        let credit = idx.day_credit(habit, d);
+        if credit.excused() {
+            excused_days += 1;
+        }
+        if let Some(met) = credit.counted() {
+            eligible += 1;
+            successes += u32::from(met);
+        }
+    }
 
     let rate = if eligible == 0 {
         None
@@ -118,11 +136,7 @@ fn compute_daily_recap(
 
     let percent = rate.map(|r| (r * 100.0).round() as u32);
 
-    let target_label = if habit.target.quantity == 1 {
-        "1/day".to_string()
-    } else {
-        format!("{}/day", habit.target.quantity)
-    };
+    let target_label = target_label(habit);
 
     Ok(RecapRow {
         habit_id: habit.id.clone(),
@@ -132,66 +146,45 @@ fn compute_daily_recap(
         target: habit.target.quantity,
         successes,
         eligible,
+        excused_days,
         rate,
         percent,
         range: RecapRangeInfo {
-            kind: range.as_str().to_string(),
+            kind: kind.to_string(),
             from: from.to_string(),
             to: to.to_string(),
         },
+        strength: habit_strength(idx, habit, to, None)?,
     })
 }
 
-/// Sum of counted quantities for a habit over a week.
-fn week_sum_for_habit(db: &Db, habit: &Habit, week_start_date: &str) -> Result<u32, CliError> {
-    let end = iso_week_end(week_start_date)?;
-    let days = date_range_inclusive(week_start_date, &end)?;
-
-    let mut sum = 0u32;
-    for d in days {
-        if d < habit.created_date {
-            continue;
-        }
-        sum = sum.saturating_add(counted_quantity(db, habit, &d));
-    }
-    Ok(sum)
-}
-
-/// Generate all ISO week start dates from `from_week_start` to `to_week_start`.
-fn week_range_inclusive(from_week_start: &str, to_week_start: &str) -> Result<Vec<String>, CliError> {
-    let mut weeks = Vec::new();
-    let mut cur = from_week_start.to_string();
-    while cur.as_str() <= to_week_start {
-        weeks.push(cur.clone());
-        cur = add_days(&cur, 7)?;
-    }
-    Ok(weeks)
-}
-
-/// Compute weekly habit recap stats over a date range.
-fn compute_weekly_recap(
-    db: &Db,
+/// Compute week- or month-period habit recap stats over a date range.
+fn compute_period_recap(
+    idx: &CompletionIndex,
     habit: &Habit,
     from: &str,
     to: &str,
-    range: RecapRange,
+    kind: &str,
 ) -> Result<RecapRow, CliError> {
-    let start_week = iso_week_start(from)?;
-    let end_week = iso_week_start(to)?;
-    let all_week_starts = week_range_inclusive(&start_week, &end_week)?;
+    let period = habit.target.period.as_str();
+    let all_window_starts = window_starts(period, from, to)?;
 
-    // Filter to weeks where the habit existed by end of week
-    let eligible_week_starts: Vec<String> = all_week_starts
+    // Filter to windows where the habit existed by the end of the window
+    let eligible_window_starts: Vec<String> = all_window_starts
         .into_iter()
-        .filter(|ws| iso_week_end(ws).map(|e| e >= habit.created_date).unwrap_or(false))
+        .filter(|ws| {
+            window_end(period, ws)
+                .map(|e| e >= habit.created_date)
+                .unwrap_or(false)
+        })
         .collect();
 
-    let eligible = eligible_week_starts.len() as u32;
-    let successes = eligible_week_starts
+    let eligible = eligible_window_starts.len() as u32;
+    let successes = eligible_window_starts
         .iter()
         .filter(|ws| {
-            week_sum_for_habit(db, habit, ws)
-                .map(|sum| sum >= habit.target.quantity)
+            window_sum(idx, habit, period, ws)
+                .map(|(_, sum)| meets_target(habit, sum))
                 .unwrap_or(false)
         })
         .count() as u32;
@@ -204,36 +197,49 @@ fn compute_weekly_recap(
 
     let percent = rate.map(|r| (r * 100.0).round() as u32);
 
-    let target_label = if habit.target.quantity == 1 {
-        "1/week".to_string()
-    } else {
-        format!("{}/week", habit.target.quantity)
-    };
+    let target_label = target_label(habit);
 
     Ok(RecapRow {
         habit_id: habit.id.clone(),
         name: habit.name.clone(),
-        period: "week".to_string(),
+        period: period.to_string(),
         target_label,
         target: habit.target.quantity,
         successes,
         eligible,
+        excused_days: 0,
         rate,
         percent,
         range: RecapRangeInfo {
-            kind: range.as_str().to_string(),
+            kind: kind.to_string(),
             from: from.to_string(),
             to: to.to_string(),
         },
+        strength: habit_strength(idx, habit, to, None)?,
     })
 }
 
+/// Recap for one habit over `from..=to`; `kind` labels the range in the row.
+pub fn recap_row(
+    idx: &CompletionIndex,
+    habit: &Habit,
+    from: &str,
+    to: &str,
+    kind: &str,
+) -> Result<RecapRow, CliError> {
+    if habit.target.period == "day" {
+        compute_daily_recap(idx, habit, from, to, kind)
+    } else {
+        compute_period_recap(idx, habit, from, to, kind)
+    }
+}
+
 /// Build recap rows for given habits over the specified range.
 ///
 /// If `behind_first` is true, habits are sorted by completion % ascending
 /// (lowest/behind schedule first). Otherwise, sorted descending (best first).
 pub fn build_recap(
-    db: &Db,
+    idx: &CompletionIndex,
     habits: &[Habit],
     range: RecapRange,
     today: &str,
@@ -246,12 +252,7 @@ pub fn build_recap(
 
     let mut rows = Vec::new();
     for h in sorted.iter() {
-        let row = if h.target.period == "day" {
-            compute_daily_recap(db, h, &from, &to, range)?
-        } else {
-            compute_weekly_recap(db, h, &from, &to, range)?
-        };
-        rows.push(row);
+        rows.push(recap_row(idx, h, &from, &to, range.as_str())?);
     }
 
     // Sort by percentage (ascending if behind_first, descending otherwise)
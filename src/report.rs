@@ -0,0 +1,501 @@
+//! `habit report`: a self-contained HTML or Markdown progress report.
+//!
+//! The report covers one date range: a recap table (progress bars, ratios,
+//! streaks), one calendar per habit, excuse usage and the penalty ledger.
+//! It only reads the DB and has no timestamps of its own, so the same DB and
+//! range always render the same document (safe to commit or diff).
+
+use crate::calendar::{day_mark, DayMark, LEGEND};
+use crate::completion::CompletionIndex;
+use crate::date::{add_days, iso_week_id, iso_week_start};
+use crate::error::CliError;
+use crate::habits::{stable_habit_sort, target_label};
+use crate::model::{Db, ExcuseKind, Habit, PenaltyActionKind};
+use crate::recap::{recap_row, render_progress_bar, RecapRow};
+use crate::stats::{build_stats, StatsRow};
+
+const BAR_WIDTH: usize = 10;
+const WEEKDAY_LABELS: [&str; 7] = ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReportFormat {
+    Html,
+    Markdown,
+}
+
+pub struct CalendarWeek {
+    /// ISO week id, e.g. `2026-W02`.
+    pub week: String,
+    pub days: [DayMark; 7],
+}
+
+pub struct ExcuseLine {
+    pub date: String,
+    pub kind: ExcuseKind,
+    pub reason: String,
+}
+
+pub struct HabitSection {
+    pub habit: Habit,
+    pub recap: RecapRow,
+    pub stats: StatsRow,
+    pub weeks: Vec<CalendarWeek>,
+    pub excuses: Vec<ExcuseLine>,
+}
+
+pub struct LedgerRow {
+    pub debt_id: String,
+    pub habit: String,
+    pub trigger_date: String,
+    pub due_date: String,
+    pub quantity: u32,
+    /// `open`, or `resolved <date>` / `voided <date>`.
+    pub status: String,
+}
+
+pub struct Report {
+    pub from: String,
+    pub to: String,
+    pub habits: Vec<HabitSection>,
+    pub ledger: Vec<LedgerRow>,
+}
+
+pub fn build_report(
+    db: &Db,
+    idx: &CompletionIndex,
+    habits: &[Habit],
+    from: &str,
+    to: &str,
+    today: &str,
+) -> Result<Report, CliError> {
+    let mut sorted: Vec<Habit> = habits.to_vec();
+    sorted.sort_by(stable_habit_sort);
+
+    let mut weeks_starts: Vec<String> = Vec::new();
+    let mut ws = iso_week_start(from)?;
+    while ws.as_str() <= to {
+        weeks_starts.push(ws.clone());
+        ws = add_days(&ws, 7)?;
+    }
+
+    let mut sections: Vec<HabitSection> = Vec::new();
+    for h in sorted.iter() {
+        let recap = recap_row(idx, h, from, to, "custom")?;
+        let stats = build_stats(idx, std::slice::from_ref(h), from, to)?
+            .pop()
+            .ok_or_else(|| CliError::io("DB corrupted"))?;
+
+        let mut weeks: Vec<CalendarWeek> = Vec::new();
+        for start in weeks_starts.iter() {
+            let mut days = [DayMark::Outside; 7];
+            for (i, slot) in days.iter_mut().enumerate() {
+                let d = add_days(start, i as i32)?;
+                if d.as_str() >= from && d.as_str() <= to {
+                    *slot = day_mark(idx, h, &d, today);
+                }
+            }
+            weeks.push(CalendarWeek {
+                week: iso_week_id(start)?,
+                days,
+            });
+        }
+
+        let mut excuses: Vec<ExcuseLine> = db
+            .excuses
+            .iter()
+            .filter(|e| e.habit_id == h.id && e.date.as_str() >= from && e.date.as_str() <= to)
+            .map(|e| ExcuseLine {
+                date: e.date.clone(),
+                kind: e.kind,
+                reason: e.reason.clone(),
+            })
+            .collect();
+        excuses.sort_by(|a, b| a.date.cmp(&b.date));
+
+        sections.push(HabitSection {
+            habit: h.clone(),
+            recap,
+            stats,
+            weeks,
+            excuses,
+        });
+    }
+
+    // Debts triggered in the range, plus older ones still open at its start.
+    let mut ledger: Vec<LedgerRow> = Vec::new();
+    for d in db.penalty_debts.iter() {
+        let Some(habit) = sorted.iter().find(|h| h.id == d.habit_id) else {
+            continue;
+        };
+        if d.trigger_date.as_str() > to {
+            continue;
+        }
+        let closed = db
+            .penalty_actions
+            .iter()
+            .filter(|a| a.debt_id == d.id && a.kind.closes() && a.date.as_str() <= to)
+            .min_by(|a, b| a.date.cmp(&b.date));
+        let defaulted = db.penalty_actions.iter().find(|a| {
+            a.debt_id == d.id && a.kind == PenaltyActionKind::Default && a.date.as_str() <= to
+        });
+        let in_range = d.trigger_date.as_str() >= from;
+        let carried = closed.is_none_or(|a| a.date.as_str() >= from);
+        if !in_range && !carried {
+            continue;
+        }
+        let status = match closed {
+            Some(a) if a.kind == PenaltyActionKind::Resolve => format!("resolved {}", a.date),
+            Some(a) => format!("voided {}", a.date),
+            None => match defaulted {
+                Some(a) => format!("defaulted {}", a.date),
+                None => "open".to_string(),
+            },
+        };
+        ledger.push(LedgerRow {
+            debt_id: d.id.clone(),
+            habit: habit.name.clone(),
+            trigger_date: d.trigger_date.clone(),
+            due_date: d.due_date.clone(),
+            quantity: d.quantity,
+            status,
+        });
+    }
+    ledger.sort_by(|a, b| (&a.trigger_date, &a.debt_id).cmp(&(&b.trigger_date, &b.debt_id)));
+
+    Ok(Report {
+        from: from.to_string(),
+        to: to.to_string(),
+        habits: sections,
+        ledger,
+    })
+}
+
+fn percent_label(r: &RecapRow) -> String {
+    match r.percent {
+        Some(p) => format!("{}%", p),
+        None => "n/a".to_string(),
+    }
+}
+
+fn excuse_counts(section: &HabitSection) -> (usize, usize) {
+    let allowed = section
+        .excuses
+        .iter()
+        .filter(|e| e.kind == ExcuseKind::Allowed)
+        .count();
+    (allowed, section.excuses.len() - allowed)
+}
+
+fn excuse_kind(kind: ExcuseKind) -> &'static str {
+    match kind {
+        ExcuseKind::Allowed => "allowed",
+        ExcuseKind::Denied => "denied",
+    }
+}
+
+pub fn render(report: &Report, format: ReportFormat) -> String {
+    match format {
+        ReportFormat::Markdown => render_markdown(report),
+        ReportFormat::Html => render_html(report),
+    }
+}
+
+fn md_cell(s: &str) -> String {
+    s.replace('|', "\\|").replace('\n', " ")
+}
+
+fn md_table(out: &mut String, headers: &[&str], rows: &[Vec<String>]) {
+    out.push_str(&format!("| {} |\n", headers.join(" | ")));
+    out.push_str(&format!("|{}\n", "---|".repeat(headers.len())));
+    for row in rows {
+        let cells: Vec<String> = row.iter().map(|c| md_cell(c)).collect();
+        out.push_str(&format!("| {} |\n", cells.join(" | ")));
+    }
+}
+
+fn render_markdown(report: &Report) -> String {
+    let mut out = format!("# Habit report: {} to {}\n\n", report.from, report.to);
+
+    if report.habits.is_empty() {
+        out.push_str("(no habits to report)\n");
+    } else {
+        let rows: Vec<Vec<String>> = report
+            .habits
+            .iter()
+            .map(|s| {
+                vec![
+                    s.habit.name.clone(),
+                    s.recap.target_label.clone(),
+                    percent_label(&s.recap),
+                    format!("`{}`", render_progress_bar(s.recap.percent, BAR_WIDTH)),
+                    format!("{}/{}", s.recap.successes, s.recap.eligible),
+                    s.stats.current_streak.to_string(),
+                    s.stats.longest_streak.to_string(),
+                ]
+            })
+            .collect();
+        md_table(
+            &mut out,
+            &[
+                "Habit", "Target", "%", "Progress", "Ratio", "Streak", "Longest",
+            ],
+            &rows,
+        );
+    }
+
+    for s in report.habits.iter() {
+        out.push_str(&format!(
+            "\n## {} ({})\n\n",
+            md_cell(&s.habit.name),
+            s.habit.id
+        ));
+        out.push_str(&format!(
+            "Target {} · {} · {}/{} · current streak {} · longest {}\n\n",
+            target_label(&s.habit),
+            percent_label(&s.recap),
+            s.recap.successes,
+            s.recap.eligible,
+            s.stats.current_streak,
+            s.stats.longest_streak
+        ));
+
+        let mut headers = vec!["Week"];
+        headers.extend(WEEKDAY_LABELS);
+        let rows: Vec<Vec<String>> = s
+            .weeks
+            .iter()
+            .map(|w| {
+                let mut row = vec![w.week.clone()];
+                row.extend(w.days.iter().map(|d| d.glyph().trim().to_string()));
+                row
+            })
+            .collect();
+        md_table(&mut out, &headers, &rows);
+
+        let (allowed, denied) = excuse_counts(s);
+        out.push_str(&format!(
+            "\nExcuses: {} allowed, {} denied (quota {}/week)\n",
+            allowed, denied, s.habit.excuse_quota_per_week
+        ));
+        for e in s.excuses.iter() {
+            out.push_str(&format!(
+                "- {} {}: {}\n",
+                e.date,
+                excuse_kind(e.kind),
+                md_cell(&e.reason)
+            ));
+        }
+    }
+
+    out.push_str("\n## Penalty ledger\n\n");
+    if report.ledger.is_empty() {
+        out.push_str("(no penalty debts)\n");
+    } else {
+        let rows: Vec<Vec<String>> = report
+            .ledger
+            .iter()
+            .map(|l| {
+                vec![
+                    l.debt_id.clone(),
+                    l.habit.clone(),
+                    l.trigger_date.clone(),
+                    l.due_date.clone(),
+                    l.quantity.to_string(),
+                    l.status.clone(),
+                ]
+            })
+            .collect();
+        md_table(
+            &mut out,
+            &["Debt", "Habit", "Triggered", "Due", "Qty", "Status"],
+            &rows,
+        );
+    }
+
+    let legend: Vec<String> = LEGEND
+        .iter()
+        .map(|(m, label)| format!("{} {}", m.glyph(), label))
+        .collect();
+    out.push_str(&format!("\nLegend: {}\n", legend.join(" · ")));
+    out
+}
+
+fn html_escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
+
+const HTML_STYLE: &str = "body{font-family:-apple-system,Segoe UI,Helvetica,Arial,sans-serif;max-width:56rem;margin:2rem auto;padding:0 1rem;color:#222}
+table{border-collapse:collapse;margin:.5rem 0 1rem}
+th,td{border:1px solid #ddd;padding:.25rem .5rem;text-align:left}
+th{background:#f5f5f5}
+.bar{font-family:monospace;white-space:pre}
+.cal td{width:2rem;height:1.5rem;text-align:center;font-family:monospace}
+.met{background:#2da44e;color:#fff}
+.partial{background:#9be9a8}
+.missed{background:#ebedf0}
+.excused{background:#f2cc60}
+.unscheduled{color:#999}
+.undeclared{background:#f9c4c4}
+.outside{background:#fff;border-color:#f5f5f5}
+.muted{color:#666}";
+
+fn html_table(out: &mut String, class: Option<&str>, headers: &[&str], rows: &[Vec<String>]) {
+    match class {
+        Some(c) => out.push_str(&format!("<table class=\"{}\">\n", c)),
+        None => out.push_str("<table>\n"),
+    }
+    let ths: Vec<String> = headers
+        .iter()
+        .map(|h| format!("<th>{}</th>", html_escape(h)))
+        .collect();
+    out.push_str(&format!("<tr>{}</tr>\n", ths.concat()));
+    for row in rows {
+        out.push_str(&format!("<tr>{}</tr>\n", row.concat()));
+    }
+    out.push_str("</table>\n");
+}
+
+fn td(s: &str) -> String {
+    format!("<td>{}</td>", html_escape(s))
+}
+
+fn render_html(report: &Report) -> String {
+    let title = format!("Habit report: {} to {}", report.from, report.to);
+    let mut out = String::new();
+    out.push_str("<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n");
+    out.push_str(&format!("<title>{}</title>\n", html_escape(&title)));
+    out.push_str(&format!(
+        "<style>\n{}\n</style>\n</head>\n<body>\n",
+        HTML_STYLE
+    ));
+    out.push_str(&format!("<h1>{}</h1>\n", html_escape(&title)));
+
+    if report.habits.is_empty() {
+        out.push_str("<p class=\"muted\">(no habits to report)</p>\n");
+    } else {
+        let rows: Vec<Vec<String>> = report
+            .habits
+            .iter()
+            .map(|s| {
+                vec![
+                    td(&s.habit.name),
+                    td(&s.recap.target_label),
+                    td(&percent_label(&s.recap)),
+                    format!(
+                        "<td class=\"bar\">{}</td>",
+                        render_progress_bar(s.recap.percent, BAR_WIDTH)
+                    ),
+                    td(&format!("{}/{}", s.recap.successes, s.recap.eligible)),
+                    td(&s.stats.current_streak.to_string()),
+                    td(&s.stats.longest_streak.to_string()),
+                ]
+            })
+            .collect();
+        html_table(
+            &mut out,
+            None,
+            &[
+                "Habit", "Target", "%", "Progress", "Ratio", "Streak", "Longest",
+            ],
+            &rows,
+        );
+    }
+
+    for s in report.habits.iter() {
+        out.push_str(&format!(
+            "<h2>{} <span class=\"muted\">({})</span></h2>\n",
+            html_escape(&s.habit.name),
+            s.habit.id
+        ));
+        out.push_str(&format!(
+            "<p>Target {} · {} · {}/{} · current streak {} · longest {}</p>\n",
+            html_escape(&target_label(&s.habit)),
+            percent_label(&s.recap),
+            s.recap.successes,
+            s.recap.eligible,
+            s.stats.current_streak,
+            s.stats.longest_streak
+        ));
+
+        let mut headers = vec!["Week"];
+        headers.extend(WEEKDAY_LABELS);
+        let rows: Vec<Vec<String>> =
+            s.weeks
+                .iter()
+                .map(|w| {
+                    let mut row = vec![td(&w.week)];
+                    row.extend(w.days.iter().map(|d| {
+                        format!("<td class=\"{}\">{}</td>", d.as_str(), d.glyph().trim())
+                    }));
+                    row
+                })
+                .collect();
+        html_table(&mut out, Some("cal"), &headers, &rows);
+
+        let (allowed, denied) = excuse_counts(s);
+        out.push_str(&format!(
+            "<p>Excuses: {} allowed, {} denied (quota {}/week)</p>\n",
+            allowed, denied, s.habit.excuse_quota_per_week
+        ));
+        if !s.excuses.is_empty() {
+            out.push_str("<ul>\n");
+            for e in s.excuses.iter() {
+                out.push_str(&format!(
+                    "<li>{} {}: {}</li>\n",
+                    e.date,
+                    excuse_kind(e.kind),
+                    html_escape(&e.reason)
+                ));
+            }
+            out.push_str("</ul>\n");
+        }
+    }
+
+    out.push_str("<h2>Penalty ledger</h2>\n");
+    if report.ledger.is_empty() {
+        out.push_str("<p class=\"muted\">(no penalty debts)</p>\n");
+    } else {
+        let rows: Vec<Vec<String>> = report
+            .ledger
+            .iter()
+            .map(|l| {
+                vec![
+                    td(&l.debt_id),
+                    td(&l.habit),
+                    td(&l.trigger_date),
+                    td(&l.due_date),
+                    td(&l.quantity.to_string()),
+                    td(&l.status),
+                ]
+            })
+            .collect();
+        html_table(
+            &mut out,
+            None,
+            &["Debt", "Habit", "Triggered", "Due", "Qty", "Status"],
+            &rows,
+        );
+    }
+
+    let legend: Vec<String> = LEGEND
+        .iter()
+        .map(|(m, label)| {
+            format!(
+                "<span class=\"{}\">&nbsp;{}&nbsp;</span> {}",
+                m.as_str(),
+                m.glyph(),
+                label
+            )
+        })
+        .collect();
+    out.push_str(&format!(
+        "<p class=\"muted\">Legend: {}</p>\n",
+        legend.join(" · ")
+    ));
+    out.push_str("</body>\n</html>\n");
+    out
+}
@@ -169,7 +169,7 @@ fn local_date_from_ts(ts: &str) -> Result<String, CliError> {
     Ok(dt.date_naive().format("%Y-%m-%d").to_string())
 }
 
-fn session_number_for(db: &Db, routine_id: &str, date: &str) -> u32 {
+pub fn session_number_for(db: &Db, routine_id: &str, date: &str) -> u32 {
     db.routine_sessions
         .iter()
         .filter(|s| s.routine_id == routine_id && s.date == date)
@@ -243,7 +243,7 @@ fn compact_ts(ts: &str) -> String {
     ts.chars().filter(|c| c.is_ascii_alphanumeric()).collect()
 }
 
-fn action_id_for(session_id: &str, kind: RoutineActionKind, ts: &str) -> String {
+pub fn action_id_for(session_id: &str, kind: RoutineActionKind, ts: &str) -> String {
     let k = match kind {
         RoutineActionKind::Next => "next",
         RoutineActionKind::Skip => "skip",
@@ -1,3 +1,4 @@
+use crate::date::{day_of_month, days_between, iso_weekday, parse_date_string};
 use crate::error::CliError;
 
 const DAY_NAME_TO_ISO: [(&str, u8); 7] = [
@@ -23,11 +24,107 @@ fn iso_to_day_name(d: u8) -> Option<&'static str> {
     }
 }
 
+fn is_false(b: &bool) -> bool {
+    !*b
+}
+
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct Schedule {
+    /// `days_of_week`, `days_of_month` or `every_n_days`.
     #[serde(rename = "type")]
     pub kind: String,
+
+    /// ISO weekdays (Mon=1..Sun=7) for `days_of_week`; days of month (1..=31) for
+    /// `days_of_month`; empty otherwise.
+    #[serde(default)]
     pub days: Vec<u8>,
+
+    /// For `days_of_month`: also scheduled on the last day of every month.
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub last_day: bool,
+
+    /// Interval in days for `every_n_days`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub every: Option<u32>,
+
+    /// First scheduled date for `every_n_days`; later occurrences are `anchor + k * every`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub anchor: Option<String>,
+}
+
+impl Schedule {
+    /// Fills in a missing `every_n_days` anchor (patterns like `every:3d` leave it unset).
+    pub fn with_default_anchor(mut self, date: &str) -> Self {
+        if self.kind == "every_n_days" && self.anchor.is_none() {
+            self.anchor = Some(date.to_string());
+        }
+        self
+    }
+}
+
+/// Parses `every:<N>d` or `every:<N>d@YYYY-MM-DD`.
+fn parse_every_pattern(pattern: &str, pattern_raw: &str) -> Result<Schedule, CliError> {
+    let invalid = || CliError::usage(format!("Invalid schedule pattern: {}", pattern_raw));
+
+    let rest = pattern.strip_prefix("every:").ok_or_else(invalid)?;
+    let (interval, anchor) = match rest.split_once('@') {
+        Some((i, a)) => (i, Some(a.trim())),
+        None => (rest, None),
+    };
+
+    let n: u32 = interval
+        .trim()
+        .strip_suffix('d')
+        .and_then(|n| n.parse().ok())
+        .filter(|n| *n >= 1)
+        .ok_or_else(invalid)?;
+
+    if let Some(a) = anchor {
+        parse_date_string(a, "anchor")?;
+    }
+
+    Ok(Schedule {
+        kind: "every_n_days".to_string(),
+        days: Vec::new(),
+        last_day: false,
+        every: Some(n),
+        anchor: anchor.map(|a| a.to_string()),
+    })
+}
+
+/// Parses `monthly:<d>[,<d>...]` where each `<d>` is 1..=31 or `last`.
+fn parse_monthly_pattern(pattern: &str, pattern_raw: &str) -> Result<Schedule, CliError> {
+    let invalid = || CliError::usage(format!("Invalid schedule pattern: {}", pattern_raw));
+
+    let rest = pattern.strip_prefix("monthly:").ok_or_else(invalid)?;
+    let mut days: Vec<u8> = Vec::new();
+    let mut last_day = false;
+    for p in rest.split(',').map(|p| p.trim()).filter(|p| !p.is_empty()) {
+        if p == "last" {
+            last_day = true;
+            continue;
+        }
+        let d: u8 = p
+            .parse()
+            .ok()
+            .filter(|d| (1..=31).contains(d))
+            .ok_or_else(invalid)?;
+        if !days.contains(&d) {
+            days.push(d);
+        }
+    }
+    if days.is_empty() && !last_day {
+        return Err(invalid());
+    }
+    days.sort();
+
+    Ok(Schedule {
+        kind: "days_of_month".to_string(),
+        days,
+        last_day,
+        every: None,
+        anchor: None,
+    })
 }
 
 pub fn parse_schedule_pattern(pattern_raw: &str) -> Result<Schedule, CliError> {
@@ -36,6 +133,13 @@ pub fn parse_schedule_pattern(pattern_raw: &str) -> Result<Schedule, CliError> {
         return Err(CliError::usage("Invalid schedule pattern"));
     }
 
+    if pattern.starts_with("every:") {
+        return parse_every_pattern(&pattern, pattern_raw);
+    }
+    if pattern.starts_with("monthly:") {
+        return parse_monthly_pattern(&pattern, pattern_raw);
+    }
+
     let mut days: Vec<u8> = if pattern == "everyday" {
         vec![1, 2, 3, 4, 5, 6, 7]
     } else if pattern == "weekdays" {
@@ -76,10 +180,31 @@ pub fn parse_schedule_pattern(pattern_raw: &str) -> Result<Schedule, CliError> {
     Ok(Schedule {
         kind: "days_of_week".to_string(),
         days,
+        last_day: false,
+        every: None,
+        anchor: None,
     })
 }
 
 pub fn schedule_to_string(schedule: &Schedule) -> String {
+    if schedule.kind == "every_n_days" {
+        let n = schedule.every.unwrap_or(1);
+        return match schedule.anchor.as_deref() {
+            Some(a) => format!("every:{}d@{}", n, a),
+            None => format!("every:{}d", n),
+        };
+    }
+
+    if schedule.kind == "days_of_month" {
+        let mut days = schedule.days.clone();
+        days.sort();
+        let mut parts: Vec<String> = days.iter().map(|d| d.to_string()).collect();
+        if schedule.last_day {
+            parts.push("last".to_string());
+        }
+        return format!("monthly:{}", parts.join(","));
+    }
+
     let mut days = schedule.days.clone();
     days.sort();
 
@@ -104,6 +229,26 @@ pub fn schedule_to_string(schedule: &Schedule) -> String {
 }
 
 pub fn validate_schedule(schedule: &Schedule) -> Result<(), CliError> {
+    if schedule.kind == "every_n_days" {
+        if schedule.every.unwrap_or(0) < 1 {
+            return Err(CliError::usage("Invalid schedule"));
+        }
+        let anchor = schedule
+            .anchor
+            .as_deref()
+            .ok_or_else(|| CliError::usage("Invalid schedule"))?;
+        parse_date_string(anchor, "anchor")?;
+        return Ok(());
+    }
+    if schedule.kind == "days_of_month" {
+        if schedule.days.is_empty() && !schedule.last_day {
+            return Err(CliError::usage("Invalid schedule"));
+        }
+        if schedule.days.iter().any(|d| !(1..=31).contains(d)) {
+            return Err(CliError::usage("Invalid schedule"));
+        }
+        return Ok(());
+    }
     if schedule.kind != "days_of_week" {
         return Err(CliError::usage("Invalid schedule"));
     }
@@ -118,6 +263,28 @@ pub fn validate_schedule(schedule: &Schedule) -> Result<(), CliError> {
     Ok(())
 }
 
+/// Whether the schedule (ignoring habit lifetime) expects the habit on `date`.
+pub fn schedule_matches(schedule: &Schedule, date: &str) -> Result<bool, CliError> {
+    if schedule.kind == "every_n_days" {
+        let (n, anchor) = match (schedule.every, schedule.anchor.as_deref()) {
+            (Some(n), Some(a)) if n >= 1 => (n as i32, a),
+            _ => return Err(CliError::io("DB corrupted")),
+        };
+        let delta = days_between(anchor, date)?;
+        return Ok(delta >= 0 && delta % n == 0);
+    }
+
+    if schedule.kind == "days_of_month" {
+        // Days that don't exist in a month (e.g. 31 in April) are simply skipped;
+        // use `last` for month-end habits.
+        let (d, is_last) = day_of_month(date)?;
+        return Ok(schedule.days.contains(&(d as u8)) || (schedule.last_day && is_last));
+    }
+
+    let wd = iso_weekday(date)?;
+    Ok(schedule.days.contains(&wd))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -140,5 +307,44 @@ mod tests {
             schedule_to_string(&parse_schedule_pattern("mon,wed,fri").unwrap()),
             "mon,wed,fri"
         );
+        assert_eq!(
+            schedule_to_string(&parse_schedule_pattern("every:3d@2026-01-05").unwrap()),
+            "every:3d@2026-01-05"
+        );
+    }
+
+    #[test]
+    fn every_n_days_matches_from_anchor() {
+        let s = parse_schedule_pattern("every:3d")
+            .unwrap()
+            .with_default_anchor("2026-01-05");
+        validate_schedule(&s).unwrap();
+        assert!(!schedule_matches(&s, "2026-01-02").unwrap());
+        assert!(schedule_matches(&s, "2026-01-05").unwrap());
+        assert!(!schedule_matches(&s, "2026-01-06").unwrap());
+        assert!(schedule_matches(&s, "2026-01-08").unwrap());
+        assert!(schedule_matches(&s, "2026-02-01").unwrap());
+
+        assert!(parse_schedule_pattern("every:0d").is_err());
+        assert!(parse_schedule_pattern("every:3").is_err());
+        assert!(parse_schedule_pattern("every:3d@2026-02-30").is_err());
+    }
+
+    #[test]
+    fn days_of_month_matches_listed_days_and_month_end() {
+        let s = parse_schedule_pattern("monthly:15,1,last").unwrap();
+        validate_schedule(&s).unwrap();
+        assert_eq!(schedule_to_string(&s), "monthly:1,15,last");
+        assert!(schedule_matches(&s, "2026-02-01").unwrap());
+        assert!(schedule_matches(&s, "2026-02-15").unwrap());
+        assert!(schedule_matches(&s, "2026-02-28").unwrap());
+        assert!(!schedule_matches(&s, "2026-03-28").unwrap());
+
+        let s31 = parse_schedule_pattern("monthly:31").unwrap();
+        assert!(!schedule_matches(&s31, "2026-04-30").unwrap());
+        assert!(schedule_matches(&s31, "2026-05-31").unwrap());
+
+        assert!(parse_schedule_pattern("monthly:").is_err());
+        assert!(parse_schedule_pattern("monthly:32").is_err());
     }
 }
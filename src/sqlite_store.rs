@@ -0,0 +1,292 @@
+use crate::db::ensure_parent_dir;
+use crate::error::CliError;
+use crate::storage::{Storage, StorageKind};
+use rusqlite::{params, Connection, OpenFlags, OptionalExtension};
+use serde_json::{Map, Value};
+use std::path::Path;
+
+#[cfg(unix)]
+use std::os::unix::fs::PermissionsExt;
+
+/// Top-level arrays (habits, checkin_events, ...) are stored one element per
+/// row in `records`, keyed by position so order and duplicates survive a
+/// round trip. Every other top-level value is one row in `doc`.
+///
+/// `id`, `habit_id` and `date` are copied out of each record for ad-hoc
+/// queries with the sqlite3 shell; the CLI itself always loads the whole DB,
+/// so they carry no indexes (earlier builds created two, dropped here).
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS doc (
+    key TEXT PRIMARY KEY,
+    kind TEXT NOT NULL,
+    data TEXT
+);
+CREATE TABLE IF NOT EXISTS records (
+    collection TEXT NOT NULL,
+    seq INTEGER NOT NULL,
+    id TEXT,
+    habit_id TEXT,
+    date TEXT,
+    data TEXT NOT NULL,
+    PRIMARY KEY (collection, seq)
+);
+DROP INDEX IF EXISTS records_habit_date;
+DROP INDEX IF EXISTS records_id;
+";
+
+const KIND_VALUE: &str = "value";
+const KIND_RECORDS: &str = "records";
+
+fn io_err(_: rusqlite::Error) -> CliError {
+    CliError::io("DB IO error")
+}
+
+fn corrupted<E>(_: E) -> CliError {
+    CliError::io("DB corrupted")
+}
+
+pub struct SqliteStorage {
+    path: String,
+}
+
+impl SqliteStorage {
+    pub fn new(db_path: &str) -> Self {
+        SqliteStorage {
+            path: db_path.to_string(),
+        }
+    }
+
+    fn open_rw(&self) -> Result<Connection, CliError> {
+        ensure_parent_dir(&self.path)?;
+        let existed = Path::new(&self.path).exists();
+        let conn = Connection::open(&self.path).map_err(io_err)?;
+
+        #[cfg(unix)]
+        {
+            if !existed {
+                let _ =
+                    std::fs::set_permissions(&self.path, std::fs::Permissions::from_mode(0o600));
+            }
+        }
+        #[cfg(not(unix))]
+        {
+            let _ = existed;
+        }
+
+        conn.execute_batch("PRAGMA synchronous = FULL;")
+            .map_err(io_err)?;
+        conn.execute_batch(SCHEMA).map_err(io_err)?;
+        Ok(conn)
+    }
+}
+
+fn str_field<'a>(v: &'a Value, key: &str) -> Option<&'a str> {
+    v.get(key).and_then(|x| x.as_str())
+}
+
+fn put_record(
+    conn: &Connection,
+    collection: &str,
+    seq: usize,
+    item: &Value,
+) -> Result<(), CliError> {
+    let data = serde_json::to_string(item).map_err(|_| CliError::io("DB IO error"))?;
+    conn.execute(
+        "INSERT OR REPLACE INTO records (collection, seq, id, habit_id, date, data)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        params![
+            collection,
+            seq as i64,
+            str_field(item, "id"),
+            str_field(item, "habit_id"),
+            str_field(item, "date"),
+            data
+        ],
+    )
+    .map_err(io_err)?;
+    Ok(())
+}
+
+fn put_doc(conn: &Connection, key: &str, kind: &str, data: Option<String>) -> Result<(), CliError> {
+    conn.execute(
+        "INSERT OR REPLACE INTO doc (key, kind, data) VALUES (?1, ?2, ?3)",
+        params![key, kind, data],
+    )
+    .map_err(io_err)?;
+    Ok(())
+}
+
+impl Storage for SqliteStorage {
+    fn kind(&self) -> StorageKind {
+        StorageKind::Sqlite
+    }
+
+    fn load(&self) -> Result<Option<Value>, CliError> {
+        if !Path::new(&self.path).exists() {
+            return Ok(None);
+        }
+        let conn = Connection::open_with_flags(&self.path, OpenFlags::SQLITE_OPEN_READ_ONLY)
+            .map_err(io_err)?;
+
+        let has_doc: Option<String> = conn
+            .query_row(
+                "SELECT name FROM sqlite_master WHERE type = 'table' AND name = 'doc'",
+                [],
+                |r| r.get(0),
+            )
+            .optional()
+            .map_err(corrupted)?;
+        if has_doc.is_none() {
+            return Err(CliError::io("DB corrupted"));
+        }
+
+        let mut obj = Map::new();
+        {
+            let mut stmt = conn
+                .prepare("SELECT key, kind, data FROM doc ORDER BY key")
+                .map_err(corrupted)?;
+            let rows = stmt
+                .query_map([], |r| {
+                    Ok((
+                        r.get::<_, String>(0)?,
+                        r.get::<_, String>(1)?,
+                        r.get::<_, Option<String>>(2)?,
+                    ))
+                })
+                .map_err(corrupted)?;
+            for row in rows {
+                let (key, kind, data) = row.map_err(corrupted)?;
+                let v = match (kind.as_str(), data) {
+                    (KIND_RECORDS, _) => Value::Array(Vec::new()),
+                    (KIND_VALUE, Some(d)) => serde_json::from_str(&d).map_err(corrupted)?,
+                    _ => return Err(CliError::io("DB corrupted")),
+                };
+                obj.insert(key, v);
+            }
+        }
+        if obj.is_empty() {
+            return Ok(None);
+        }
+
+        let mut stmt = conn
+            .prepare("SELECT collection, data FROM records ORDER BY collection, seq")
+            .map_err(corrupted)?;
+        let rows = stmt
+            .query_map([], |r| Ok((r.get::<_, String>(0)?, r.get::<_, String>(1)?)))
+            .map_err(corrupted)?;
+        for row in rows {
+            let (collection, data) = row.map_err(corrupted)?;
+            let item: Value = serde_json::from_str(&data).map_err(corrupted)?;
+            match obj.get_mut(&collection) {
+                Some(Value::Array(arr)) => arr.push(item),
+                _ => return Err(CliError::io("DB corrupted")),
+            }
+        }
+
+        Ok(Some(Value::Object(obj)))
+    }
+
+    fn save(&self, value: &Value, previous: Option<&Value>) -> Result<(), CliError> {
+        let obj = value
+            .as_object()
+            .ok_or_else(|| CliError::io("DB IO error"))?;
+        let prev = previous.and_then(|p| p.as_object());
+
+        let mut conn = self.open_rw()?;
+        let tx = conn.transaction().map_err(io_err)?;
+
+        if prev.is_none() {
+            tx.execute_batch("DELETE FROM records; DELETE FROM doc;")
+                .map_err(io_err)?;
+        }
+
+        if let Some(prev) = prev {
+            for key in prev.keys().filter(|k| !obj.contains_key(*k)) {
+                tx.execute("DELETE FROM doc WHERE key = ?1", params![key])
+                    .map_err(io_err)?;
+                tx.execute("DELETE FROM records WHERE collection = ?1", params![key])
+                    .map_err(io_err)?;
+            }
+        }
+
+        for (key, v) in obj.iter() {
+            let old = prev.and_then(|p| p.get(key));
+            match v {
+                Value::Array(items) => {
+                    let old_items: &[Value] = match old {
+                        Some(Value::Array(o)) => o,
+                        _ => {
+                            put_doc(&tx, key, KIND_RECORDS, None)?;
+                            tx.execute("DELETE FROM records WHERE collection = ?1", params![key])
+                                .map_err(io_err)?;
+                            &[]
+                        }
+                    };
+                    for (i, item) in items.iter().enumerate() {
+                        if old_items.get(i) != Some(item) {
+                            put_record(&tx, key, i, item)?;
+                        }
+                    }
+                    if old_items.len() > items.len() {
+                        tx.execute(
+                            "DELETE FROM records WHERE collection = ?1 AND seq >= ?2",
+                            params![key, items.len() as i64],
+                        )
+                        .map_err(io_err)?;
+                    }
+                }
+                _ => {
+                    if old != Some(v) {
+                        if matches!(old, Some(Value::Array(_))) {
+                            tx.execute("DELETE FROM records WHERE collection = ?1", params![key])
+                                .map_err(io_err)?;
+                        }
+                        let data =
+                            serde_json::to_string(v).map_err(|_| CliError::io("DB IO error"))?;
+                        put_doc(&tx, key, KIND_VALUE, Some(data))?;
+                    }
+                }
+            }
+        }
+
+        tx.commit().map_err(io_err)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn roundtrip_and_incremental_save() {
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join("db.sqlite");
+        let store = SqliteStorage::new(&path.to_string_lossy());
+        assert!(store.load().unwrap().is_none());
+
+        let v1 = serde_json::json!({
+            "version": 2,
+            "meta": { "next_habit_number": 2 },
+            "habits": [{ "id": "h0001", "name": "A" }],
+            "checkin_events": [],
+            "nag": null,
+        });
+        store.save(&v1, None).unwrap();
+        assert_eq!(store.load().unwrap().unwrap(), v1);
+
+        let mut v2 = v1.clone();
+        v2["habits"][0]["name"] = Value::from("B");
+        v2["checkin_events"] = serde_json::json!([
+            { "id": "c000001", "habit_id": "h0001", "date": "2026-01-01", "delta": 1 },
+            { "id": "c000001", "habit_id": "h0001", "date": "2026-01-01", "delta": 1 },
+        ]);
+        v2.as_object_mut().unwrap().remove("nag");
+        store.save(&v2, Some(&v1)).unwrap();
+        assert_eq!(store.load().unwrap().unwrap(), v2);
+
+        let mut v3 = v2.clone();
+        v3["checkin_events"].as_array_mut().unwrap().pop();
+        store.save(&v3, Some(&v2)).unwrap();
+        assert_eq!(store.load().unwrap().unwrap(), v3);
+    }
+}
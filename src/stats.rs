@@ -1,19 +1,27 @@
-use crate::completion::counted_quantity;
-use crate::date::{add_days, date_range_inclusive, iso_week_end, iso_week_start};
+use crate::completion::{meets_target, CompletionIndex, DayCredit};
+use crate::date::date_range_inclusive;
 use crate::error::CliError;
 use crate::habits::{is_scheduled_on, stable_habit_sort};
-use crate::model::{Db, Habit};
+use crate::model::{Habit, HabitKind};
+use crate::period::{window_end, window_starts, window_sum};
+use crate::strength::{habit_strength, Strength};
 
 #[derive(Debug, Clone, serde::Serialize)]
 pub struct StatsRow {
     pub habit_id: String,
     pub name: String,
     pub period: String,
+    pub kind: HabitKind,
     pub target: u32,
     pub window: Window,
     pub current_streak: u32,
     pub longest_streak: u32,
     pub success_rate: SuccessRate,
+    /// Unmet scheduled days covered by an allowed excuse (per `excuse_policy`:
+    /// skipped under `freeze`, counted as met under `count`).
+    pub excused_days: u32,
+    /// Habit strength as of `window.to`, with a weekly history over the window.
+    pub strength: Option<Strength>,
 }
 
 #[derive(Debug, Clone, serde::Serialize)]
@@ -29,7 +37,12 @@ pub struct SuccessRate {
     pub rate: Option<f64>,
 }
 
-fn compute_daily_stats(db: &Db, habit: &Habit, from: &str, to: &str) -> Result<StatsRow, CliError> {
+fn compute_daily_stats(
+    idx: &CompletionIndex,
+    habit: &Habit,
+    from: &str,
+    to: &str,
+) -> Result<StatsRow, CliError> {
     let days = date_range_inclusive(from, to)?;
 
     let mut scheduled_days: Vec<String> = Vec::new();
@@ -39,34 +52,29 @@ fn compute_daily_stats(db: &Db, habit: &Habit, from: &str, to: &str) -> Result<S
         }
     }
 
-    let mut successes = 0u32;
-    for d in scheduled_days.iter() {
-        if counted_quantity(db, habit, d) >= habit.target.quantity {
-            successes += 1;
-        }
-    }
+    let credits: Vec<DayCredit> = scheduled_days
+        .iter()
+        .map(|d| idx.day_credit(habit, d))
+        .collect();
+    let excused_days = credits.iter().filter(|c| c.excused()).count() as u32;
 
-    let eligible = scheduled_days.len() as u32;
+    // frozen days drop out, so they neither break nor extend a streak
+    let met: Vec<bool> = credits.iter().filter_map(|c| c.counted()).collect();
+
+    let successes = met.iter().filter(|ok| **ok).count() as u32;
+    let eligible = met.len() as u32;
     let rate = if eligible == 0 {
         None
     } else {
         Some(successes as f64 / eligible as f64)
     };
 
-    let mut current = 0u32;
-    for d in scheduled_days.iter().rev() {
-        let ok = counted_quantity(db, habit, d) >= habit.target.quantity;
-        if !ok {
-            break;
-        }
-        current += 1;
-    }
+    let current = met.iter().rev().take_while(|ok| **ok).count() as u32;
 
     let mut longest = 0u32;
     let mut run = 0u32;
-    for d in scheduled_days.iter() {
-        let ok = counted_quantity(db, habit, d) >= habit.target.quantity;
-        if ok {
+    for ok in met.iter() {
+        if *ok {
             run += 1;
             longest = longest.max(run);
         } else {
@@ -78,6 +86,7 @@ fn compute_daily_stats(db: &Db, habit: &Habit, from: &str, to: &str) -> Result<S
         habit_id: habit.id.clone(),
         name: habit.name.clone(),
         period: "day".to_string(),
+        kind: habit.kind,
         target: habit.target.quantity,
         window: Window {
             from: from.to_string(),
@@ -90,81 +99,48 @@ fn compute_daily_stats(db: &Db, habit: &Habit, from: &str, to: &str) -> Result<S
             eligible,
             rate,
         },
+        excused_days,
+        strength: habit_strength(idx, habit, to, Some(from))?,
     })
 }
 
-fn week_sum_for_habit(db: &Db, habit: &Habit, week_start_date: &str) -> Result<u32, CliError> {
-    let end = iso_week_end(week_start_date)?;
-    let days = date_range_inclusive(week_start_date, &end)?;
-
-    let mut sum = 0u32;
-    for d in days {
-        if d < habit.created_date {
-            continue;
-        }
-        sum = sum.saturating_add(counted_quantity(db, habit, &d));
-    }
-    Ok(sum)
-}
-
-fn week_range_inclusive(
-    from_week_start: &str,
-    to_week_start: &str,
-) -> Result<Vec<String>, CliError> {
-    let mut weeks: Vec<String> = Vec::new();
-    let mut cur = from_week_start.to_string();
-    while cur.as_str() <= to_week_start {
-        weeks.push(cur.clone());
-        cur = add_days(&cur, 7)?;
-    }
-    Ok(weeks)
-}
-
-fn compute_weekly_stats(
-    db: &Db,
+/// Stats for week- and month-period habits: one success per window whose sum meets the target.
+fn compute_period_stats(
+    idx: &CompletionIndex,
     habit: &Habit,
     from: &str,
     to: &str,
 ) -> Result<StatsRow, CliError> {
-    let start_week = iso_week_start(from)?;
-    let end_week = iso_week_start(to)?;
-    let all_week_starts = week_range_inclusive(&start_week, &end_week)?;
-
-    let mut eligible_week_starts: Vec<String> = Vec::new();
-    for ws in all_week_starts.iter() {
-        if iso_week_end(ws)? >= habit.created_date {
-            eligible_week_starts.push(ws.clone());
+    let period = habit.target.period.as_str();
+    let all_window_starts = window_starts(period, from, to)?;
+
+    let mut eligible_window_starts: Vec<String> = Vec::new();
+    for ws in all_window_starts.iter() {
+        if window_end(period, ws)? >= habit.created_date {
+            eligible_window_starts.push(ws.clone());
         }
     }
 
-    let mut successes = 0u32;
-    for ws in eligible_week_starts.iter() {
-        if week_sum_for_habit(db, habit, ws)? >= habit.target.quantity {
-            successes += 1;
-        }
+    let mut met: Vec<bool> = Vec::new();
+    for ws in eligible_window_starts.iter() {
+        let (_, counted) = window_sum(idx, habit, period, ws)?;
+        met.push(meets_target(habit, counted));
     }
 
-    let eligible = eligible_week_starts.len() as u32;
+    let successes = met.iter().filter(|ok| **ok).count() as u32;
+    let eligible = met.len() as u32;
     let rate = if eligible == 0 {
         None
     } else {
         Some(successes as f64 / eligible as f64)
     };
 
-    let mut current = 0u32;
-    for ws in eligible_week_starts.iter().rev() {
-        let ok = week_sum_for_habit(db, habit, ws)? >= habit.target.quantity;
-        if !ok {
-            break;
-        }
-        current += 1;
-    }
+    let current = met.iter().rev().take_while(|ok| **ok).count() as u32;
 
     let mut longest = 0u32;
     let mut run = 0u32;
-    for ws in eligible_week_starts.iter() {
-        let ok = week_sum_for_habit(db, habit, ws)? >= habit.target.quantity;
-        if ok {
+    for ok in met.iter() {
+        if *ok {
             run += 1;
             longest = longest.max(run);
         } else {
@@ -175,7 +151,8 @@ fn compute_weekly_stats(
     Ok(StatsRow {
         habit_id: habit.id.clone(),
         name: habit.name.clone(),
-        period: "week".to_string(),
+        period: period.to_string(),
+        kind: habit.kind,
         target: habit.target.quantity,
         window: Window {
             from: from.to_string(),
@@ -188,11 +165,13 @@ fn compute_weekly_stats(
             eligible,
             rate,
         },
+        excused_days: 0,
+        strength: habit_strength(idx, habit, to, Some(from))?,
     })
 }
 
 pub fn build_stats(
-    db: &Db,
+    idx: &CompletionIndex,
     habits: &[Habit],
     from: &str,
     to: &str,
@@ -203,9 +182,9 @@ pub fn build_stats(
     let mut rows = Vec::new();
     for h in sorted.iter() {
         if h.target.period == "day" {
-            rows.push(compute_daily_stats(db, h, from, to)?);
+            rows.push(compute_daily_stats(idx, h, from, to)?);
         } else {
-            rows.push(compute_weekly_stats(db, h, from, to)?);
+            rows.push(compute_period_stats(idx, h, from, to)?);
         }
     }
 
@@ -1,9 +1,9 @@
-use crate::checkins::get_quantity;
-use crate::completion::{counted_quantity, is_declared};
+use crate::completion::{meets_target, CompletionIndex};
 use crate::date::{date_range_inclusive, iso_week_end, iso_week_id, iso_week_start};
 use crate::error::CliError;
 use crate::habits::{is_scheduled_on, stable_habit_sort};
-use crate::model::{Db, Habit};
+use crate::model::{Db, Habit, HabitKind};
+use crate::period::{window_id, window_start, window_sum};
 
 #[derive(Debug, Clone, serde::Serialize)]
 pub struct Status {
@@ -22,6 +22,7 @@ pub struct TodayHabitRow {
     pub id: String,
     pub name: String,
     pub period: String,
+    pub kind: HabitKind,
     pub target: u32,
 
     /// Quantity that counts toward completion semantics.
@@ -30,6 +31,7 @@ pub struct TodayHabitRow {
     /// Raw recorded quantity (may differ if declaration-gated).
     pub raw_quantity: u32,
 
+    /// Target met (for limit habits: still at or under the ceiling).
     pub done: bool,
 
     /// Whether this habit requires a declaration for completion.
@@ -37,6 +39,9 @@ pub struct TodayHabitRow {
 
     /// Whether a declaration exists for this date (if required).
     pub declared: bool,
+
+    /// Not done, but covered by an allowed excuse the habit's policy honors.
+    pub excused: bool,
 }
 
 #[derive(Debug, Clone, serde::Serialize)]
@@ -52,6 +57,7 @@ pub struct WeekSection {
 pub enum WeekHabitRow {
     Day(WeekHabitDayRow),
     Week(WeekHabitWeekRow),
+    Month(WeekHabitMonthRow),
 }
 
 #[derive(Debug, Clone, serde::Serialize)]
@@ -59,8 +65,12 @@ pub struct WeekHabitDayRow {
     pub id: String,
     pub name: String,
     pub period: String,
+    /// Scheduled days, minus excused ones under the `freeze` policy.
     pub scheduled_days: u32,
+    /// Met days, plus excused ones under the `count` policy.
     pub done_scheduled_days: u32,
+    /// Unmet scheduled days covered by an allowed excuse.
+    pub excused_days: u32,
     pub needs_declaration: bool,
 }
 
@@ -80,23 +90,22 @@ pub struct WeekHabitWeekRow {
     pub needs_declaration: bool,
 }
 
-fn week_sum_for_habit(
-    db: &Db,
-    habit: &Habit,
-    week_start_date: &str,
-) -> Result<(u32, u32), CliError> {
-    let end = iso_week_end(week_start_date)?;
-    let days = date_range_inclusive(week_start_date, &end)?;
-    let mut raw_sum = 0u32;
-    let mut counted_sum = 0u32;
-    for d in days {
-        if d < habit.created_date {
-            continue;
-        }
-        raw_sum = raw_sum.saturating_add(get_quantity(db, &habit.id, &d));
-        counted_sum = counted_sum.saturating_add(counted_quantity(db, habit, &d));
-    }
-    Ok((raw_sum, counted_sum))
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct WeekHabitMonthRow {
+    pub id: String,
+    pub name: String,
+    pub period: String,
+    /// Calendar month containing the reference date, e.g. `2026-01`.
+    pub month: String,
+    pub target: u32,
+
+    /// Counted quantity for the month.
+    pub quantity: u32,
+
+    /// Raw recorded quantity for the month.
+    pub raw_quantity: u32,
+
+    pub needs_declaration: bool,
 }
 
 pub fn build_status(
@@ -106,9 +115,11 @@ pub fn build_status(
     include_archived: bool,
 ) -> Result<Status, CliError> {
     let today = date;
-    let week_start = iso_week_start(week_of.unwrap_or(today))?;
+    let reference = week_of.unwrap_or(today);
+    let week_start = iso_week_start(reference)?;
     let week_end = iso_week_end(&week_start)?;
     let week_id = iso_week_id(&week_start)?;
+    let idx = CompletionIndex::new(db);
 
     let mut habits: Vec<Habit> = db
         .habits
@@ -124,34 +135,40 @@ pub fn build_status(
             continue;
         }
         if h.target.period == "day" {
-            let raw = get_quantity(db, &h.id, today);
-            let counted = counted_quantity(db, h, today);
-            let declared = is_declared(db, h, today);
-            let done = declared && counted >= h.target.quantity;
+            let raw = idx.raw_quantity(&h.id, today);
+            let counted = idx.counted_quantity(h, today);
+            let declared = idx.is_declared(h, today);
+            let done = idx.day_met(h, today);
             today_rows.push(TodayHabitRow {
                 id: h.id.clone(),
                 name: h.name.clone(),
                 period: "day".to_string(),
+                kind: h.kind,
                 target: h.target.quantity,
                 quantity: counted,
                 raw_quantity: raw,
                 done,
                 needs_declaration: h.needs_declaration,
                 declared,
+                excused: idx.day_credit(h, today).excused(),
             });
         } else {
-            let (raw_sum, counted_sum) = week_sum_for_habit(db, h, &week_start)?;
-            let done = counted_sum >= h.target.quantity;
+            let period = h.target.period.as_str();
+            let start = window_start(period, reference)?;
+            let (raw_sum, counted_sum) = window_sum(&idx, h, period, &start)?;
+            let done = meets_target(h, counted_sum);
             today_rows.push(TodayHabitRow {
                 id: h.id.clone(),
                 name: h.name.clone(),
-                period: "week".to_string(),
+                period: period.to_string(),
+                kind: h.kind,
                 target: h.target.quantity,
                 quantity: counted_sum,
                 raw_quantity: raw_sum,
                 done,
                 needs_declaration: h.needs_declaration,
-                declared: is_declared(db, h, today),
+                declared: idx.is_declared(h, today),
+                excused: false,
             });
         }
     }
@@ -163,15 +180,18 @@ pub fn build_status(
         if h.target.period == "day" {
             let mut scheduled = 0u32;
             let mut done_days = 0u32;
+            let mut excused_days = 0u32;
             for d in week_days.iter() {
                 if !is_scheduled_on(h, d)? {
                     continue;
                 }
-                scheduled += 1;
-                let counted = counted_quantity(db, h, d);
-                let declared = is_declared(db, h, d);
-                if declared && counted >= h.target.quantity {
-                    done_days += 1;
+                let credit = idx.day_credit(h, d);
+                if credit.excused() {
+                    excused_days += 1;
+                }
+                if let Some(met) = credit.counted() {
+                    scheduled += 1;
+                    done_days += u32::from(met);
                 }
             }
             week_rows.push(WeekHabitRow::Day(WeekHabitDayRow {
@@ -180,10 +200,24 @@ pub fn build_status(
                 period: "day".to_string(),
                 scheduled_days: scheduled,
                 done_scheduled_days: done_days,
+                excused_days,
+                needs_declaration: h.needs_declaration,
+            }));
+        } else if h.target.period == "month" {
+            let start = window_start("month", reference)?;
+            let (raw_sum, counted_sum) = window_sum(&idx, h, "month", &start)?;
+            week_rows.push(WeekHabitRow::Month(WeekHabitMonthRow {
+                id: h.id.clone(),
+                name: h.name.clone(),
+                period: "month".to_string(),
+                month: window_id("month", &start)?,
+                target: h.target.quantity,
+                quantity: counted_sum,
+                raw_quantity: raw_sum,
                 needs_declaration: h.needs_declaration,
             }));
         } else {
-            let (raw_sum, counted_sum) = week_sum_for_habit(db, h, &week_start)?;
+            let (raw_sum, counted_sum) = window_sum(&idx, h, "week", &week_start)?;
             week_rows.push(WeekHabitRow::Week(WeekHabitWeekRow {
                 id: h.id.clone(),
                 name: h.name.clone(),
@@ -0,0 +1,179 @@
+use crate::db::ensure_parent_dir;
+use crate::error::CliError;
+use crate::stable_json::stable_to_string_pretty;
+use serde_json::Value;
+use std::fs;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::Path;
+use std::sync::OnceLock;
+
+#[cfg(unix)]
+use std::os::unix::fs::PermissionsExt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StorageKind {
+    Json,
+    Sqlite,
+}
+
+impl StorageKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            StorageKind::Json => "json",
+            StorageKind::Sqlite => "sqlite",
+        }
+    }
+}
+
+/// Where the DB lives. Backends only move whole-DB JSON values in and out;
+/// migrations, journaling and backups stay in `db.rs`.
+pub trait Storage {
+    fn kind(&self) -> StorageKind;
+
+    /// The stored DB as JSON, before migrations. `None` when nothing is stored yet.
+    fn load(&self) -> Result<Option<Value>, CliError>;
+
+    /// Durably replaces the stored DB with `value`.
+    ///
+    /// `previous` is what `load` returned under the same write lock, so a
+    /// backend may write only what changed.
+    fn save(&self, value: &Value, previous: Option<&Value>) -> Result<(), CliError>;
+}
+
+static STORAGE_OVERRIDE: OnceLock<StorageKind> = OnceLock::new();
+
+/// `--storage`: forces a backend regardless of the DB path's extension.
+pub fn set_storage_override(kind: StorageKind) {
+    let _ = STORAGE_OVERRIDE.set(kind);
+}
+
+/// `*.sqlite`, `*.sqlite3` and `*.db` paths use SQLite; everything else JSON.
+pub fn kind_for_path(db_path: &str) -> StorageKind {
+    let ext = Path::new(db_path)
+        .extension()
+        .map(|e| e.to_string_lossy().to_ascii_lowercase())
+        .unwrap_or_default();
+    match ext.as_str() {
+        "sqlite" | "sqlite3" | "db" => StorageKind::Sqlite,
+        _ => StorageKind::Json,
+    }
+}
+
+pub fn open(db_path: &str) -> Result<Box<dyn Storage>, CliError> {
+    let kind = STORAGE_OVERRIDE
+        .get()
+        .copied()
+        .unwrap_or_else(|| kind_for_path(db_path));
+    open_kind(db_path, kind)
+}
+
+pub fn open_kind(db_path: &str, kind: StorageKind) -> Result<Box<dyn Storage>, CliError> {
+    match kind {
+        StorageKind::Json => Ok(Box::new(JsonStorage {
+            path: db_path.to_string(),
+        })),
+        #[cfg(feature = "sqlite")]
+        StorageKind::Sqlite => Ok(Box::new(crate::sqlite_store::SqliteStorage::new(db_path))),
+        #[cfg(not(feature = "sqlite"))]
+        StorageKind::Sqlite => Err(CliError::usage(
+            "SQLite storage is not available in this build",
+        )),
+    }
+}
+
+/// fsyncs a directory so a rename/create inside it survives power loss.
+pub fn sync_dir(dir: &Path) -> Result<(), CliError> {
+    #[cfg(unix)]
+    {
+        fs::File::open(dir)
+            .and_then(|d| d.sync_all())
+            .map_err(|_| CliError::io("DB IO error"))?;
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = dir;
+    }
+    Ok(())
+}
+
+/// The original single-file backend: stable, pretty-printed JSON.
+pub struct JsonStorage {
+    path: String,
+}
+
+impl Storage for JsonStorage {
+    fn kind(&self) -> StorageKind {
+        StorageKind::Json
+    }
+
+    fn load(&self) -> Result<Option<Value>, CliError> {
+        match fs::read_to_string(&self.path) {
+            Ok(txt) => serde_json::from_str(&txt)
+                .map(Some)
+                .map_err(|_| CliError::io("DB corrupted")),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(_) => Err(CliError::io("DB IO error")),
+        }
+    }
+
+    /// Writes to a temp file, fsyncs it, renames it over the DB and fsyncs
+    /// the parent directory.
+    fn save(&self, value: &Value, _previous: Option<&Value>) -> Result<(), CliError> {
+        let data = stable_to_string_pretty(value).map_err(|_| CliError::io("DB IO error"))? + "\n";
+        ensure_parent_dir(&self.path)?;
+
+        let dir = Path::new(&self.path)
+            .parent()
+            .ok_or_else(|| CliError::io("DB IO error"))?;
+
+        let tmp_path = dir.join(format!(".db.json.tmp.{}", std::process::id()));
+
+        {
+            let mut f = OpenOptions::new()
+                .write(true)
+                .create(true)
+                .truncate(true)
+                .open(&tmp_path)
+                .map_err(|_| CliError::io("DB IO error"))?;
+
+            #[cfg(unix)]
+            {
+                let _ = f.set_permissions(fs::Permissions::from_mode(0o600));
+            }
+
+            f.write_all(data.as_bytes())
+                .and_then(|_| f.sync_all())
+                .map_err(|_| {
+                    let _ = fs::remove_file(&tmp_path);
+                    CliError::io("DB IO error")
+                })?;
+        }
+
+        fs::rename(&tmp_path, &self.path).map_err(|_| {
+            let _ = fs::remove_file(&tmp_path);
+            CliError::io("DB IO error")
+        })?;
+
+        #[cfg(unix)]
+        {
+            let _ = fs::set_permissions(&self.path, fs::Permissions::from_mode(0o600));
+        }
+
+        sync_dir(dir)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn backend_follows_extension() {
+        assert_eq!(kind_for_path("/x/db.json"), StorageKind::Json);
+        assert_eq!(kind_for_path("/x/habits"), StorageKind::Json);
+        assert_eq!(kind_for_path("/x/habits.sqlite"), StorageKind::Sqlite);
+        assert_eq!(kind_for_path("/x/habits.DB"), StorageKind::Sqlite);
+    }
+}
@@ -0,0 +1,273 @@
+//! Loop-style habit strength: an exponential moving average over scheduled
+//! periods, so one miss dents the score instead of resetting it like a streak.
+//!
+//! - **Daily habits**: one step per scheduled day since `created_date`.
+//! - **Weekly habits**: one step per ISO week since `created_date`.
+//! - **Monthly habits**: no score.
+//!
+//! Each step moves the score toward the period's value (1.0 met, goal habits
+//! get `counted / target` partial credit, 0.0 otherwise) with a fixed
+//! half-life. The period containing the evaluation date is still in progress,
+//! so it only counts once met. Excused days follow the habit's
+//! `excuse_policy`: skipped (`freeze`) or counted as met (`count`).
+
+use crate::completion::{meets_target, CompletionIndex};
+use crate::date::{add_days, date_range_inclusive, iso_weekday};
+use crate::error::CliError;
+use crate::habits::is_scheduled_on;
+use crate::model::{Habit, HabitKind};
+use crate::period::{window_end, window_starts};
+
+/// Scheduled days after which an old check-in weighs half as much.
+pub const DAY_HALF_LIFE: f64 = 13.0;
+/// ISO weeks after which an old week weighs half as much.
+pub const WEEK_HALF_LIFE: f64 = 5.0;
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct Strength {
+    /// 0.0-1.0, rounded to 4 decimals
+    pub score: f64,
+    /// 0-100
+    pub percent: u32,
+    /// Score at each Sunday in the requested window (and at its last day).
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub history: Vec<StrengthPoint>,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct StrengthPoint {
+    pub date: String,
+    pub score: f64,
+    pub percent: u32,
+}
+
+/// Period value in 0.0-1.0.
+fn value(habit: &Habit, met: bool, counted: u32) -> f64 {
+    if met {
+        1.0
+    } else if habit.kind == HabitKind::Goal && habit.target.quantity > 0 {
+        (counted as f64 / habit.target.quantity as f64).min(1.0)
+    } else {
+        0.0
+    }
+}
+
+/// (step date, value) per counted period, in date order.
+fn steps(idx: &CompletionIndex, habit: &Habit, to: &str) -> Result<Vec<(String, f64)>, CliError> {
+    let mut out: Vec<(String, f64)> = Vec::new();
+    if habit.created_date.as_str() > to {
+        return Ok(out);
+    }
+    let to = match habit.archived_date.as_deref() {
+        Some(a) if a < to => a,
+        _ => to,
+    };
+
+    match habit.target.period.as_str() {
+        "day" => {
+            for d in date_range_inclusive(&habit.created_date, to)? {
+                if !is_scheduled_on(habit, &d)? {
+                    continue;
+                }
+                let Some(met) = idx.day_credit(habit, &d).counted() else {
+                    continue;
+                };
+                if d == to && !met {
+                    continue;
+                }
+                let v = value(habit, met, idx.counted_quantity(habit, &d));
+                out.push((d, v));
+            }
+        }
+        "week" => {
+            for ws in window_starts("week", &habit.created_date, to)? {
+                let end = window_end("week", &ws)?;
+                let from = ws.as_str().max(habit.created_date.as_str());
+                let (_, counted) = idx.range_sums(habit, from, end.as_str().min(to));
+                let met = meets_target(habit, counted);
+                if end.as_str() >= to && !met {
+                    continue;
+                }
+                out.push((end, value(habit, met, counted)));
+            }
+        }
+        _ => {}
+    }
+    Ok(out)
+}
+
+fn round4(x: f64) -> f64 {
+    (x * 10000.0).round() / 10000.0
+}
+
+fn point(date: &str, score: f64) -> StrengthPoint {
+    let score = round4(score);
+    StrengthPoint {
+        date: date.to_string(),
+        score,
+        percent: (score * 100.0).round() as u32,
+    }
+}
+
+/// Strength of `habit` as of `to`, or `None` for monthly habits and habits
+/// with no counted period yet. With `history_from`, also samples the score at
+/// each Sunday in `history_from..=to` and at `to`.
+pub fn habit_strength(
+    idx: &CompletionIndex,
+    habit: &Habit,
+    to: &str,
+    history_from: Option<&str>,
+) -> Result<Option<Strength>, CliError> {
+    let half_life = match habit.target.period.as_str() {
+        "day" => DAY_HALF_LIFE,
+        "week" => WEEK_HALF_LIFE,
+        _ => return Ok(None),
+    };
+    let steps = steps(idx, habit, to)?;
+    if steps.is_empty() {
+        return Ok(None);
+    }
+    let keep = 0.5f64.powf(1.0 / half_life);
+
+    // score after each step
+    let mut series: Vec<(String, f64)> = Vec::with_capacity(steps.len());
+    let mut score = 0.0;
+    for (date, v) in steps {
+        score = score * keep + v * (1.0 - keep);
+        series.push((date, score));
+    }
+
+    let mut history: Vec<StrengthPoint> = Vec::new();
+    if let Some(from) = history_from {
+        let mut samples: Vec<String> = Vec::new();
+        for d in date_range_inclusive(from, to)? {
+            if iso_weekday(&d)? == 7 || d == to {
+                samples.push(d);
+            }
+        }
+        // samples are in date order, so one cursor walks the series once
+        let mut next = 0;
+        for d in samples {
+            // week steps are dated by week end, so a mid-week `to` would miss
+            // the current week's step without this look-ahead
+            let horizon = if d == to { add_days(to, 6)? } else { d.clone() };
+            while next < series.len() && series[next].0 <= horizon {
+                next += 1;
+            }
+            if next > 0 {
+                history.push(point(&d, series[next - 1].1));
+            }
+        }
+    }
+
+    let last = point(to, score);
+    Ok(Some(Strength {
+        score: last.score,
+        percent: last.percent,
+        history,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::checkins::{add_quantity, EventInfo};
+    use crate::habits::make_habit;
+    use crate::model::default_db;
+
+    fn info() -> EventInfo {
+        EventInfo {
+            ts: "2026-01-01T08:00:00Z".to_string(),
+            note: None,
+            source: "cli".to_string(),
+        }
+    }
+
+    fn habit(period: &str, target: u32) -> Habit {
+        make_habit(
+            "h0001".to_string(),
+            "Read",
+            "everyday",
+            period,
+            target,
+            None,
+            "2026-01-05",
+            false,
+            2,
+            HabitKind::Goal,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn one_miss_dents_instead_of_resetting() {
+        let mut full = default_db();
+        let mut db = default_db();
+        let h = habit("day", 2);
+        for d in date_range_inclusive("2026-01-05", "2026-01-18").unwrap() {
+            add_quantity(&mut full, "h0001", &d, 2, &info()).unwrap();
+            if d != "2026-01-12" {
+                add_quantity(&mut db, "h0001", &d, 2, &info()).unwrap();
+            }
+        }
+        let full = habit_strength(&CompletionIndex::new(&full), &h, "2026-01-18", None)
+            .unwrap()
+            .unwrap();
+        let idx = CompletionIndex::new(&db);
+
+        let before = habit_strength(&idx, &h, "2026-01-11", None)
+            .unwrap()
+            .unwrap();
+        let after = habit_strength(&idx, &h, "2026-01-18", Some("2026-01-05"))
+            .unwrap()
+            .unwrap();
+        assert!(after.score < full.score && after.score > 0.9 * full.score);
+        assert!(after.score > before.score);
+        assert_eq!(after.history.len(), 2);
+        assert_eq!(after.history[0].date, "2026-01-11");
+        assert_eq!(after.history[0].score, before.score);
+
+        // today's unmet day is still in progress; a half-done day counts half
+        assert_eq!(
+            habit_strength(&idx, &h, "2026-01-19", None)
+                .unwrap()
+                .unwrap()
+                .score,
+            after.score
+        );
+        add_quantity(&mut db, "h0001", "2026-01-19", 1, &info()).unwrap();
+        let idx = CompletionIndex::new(&db);
+        let half = habit_strength(&idx, &h, "2026-01-20", None)
+            .unwrap()
+            .unwrap();
+        let keep = 0.5f64.powf(1.0 / DAY_HALF_LIFE);
+        assert!((half.score - (after.score * keep + 0.5 * (1.0 - keep))).abs() < 1e-3);
+    }
+
+    #[test]
+    fn weekly_steps_and_monthly_none() {
+        let mut db = default_db();
+        let h = habit("week", 3);
+        for d in ["2026-01-05", "2026-01-06", "2026-01-07"] {
+            add_quantity(&mut db, "h0001", d, 1, &info()).unwrap();
+        }
+        let idx = CompletionIndex::new(&db);
+
+        assert!(habit_strength(&idx, &h, "2026-01-06", None)
+            .unwrap()
+            .is_none());
+        let s = habit_strength(&idx, &h, "2026-01-07", None)
+            .unwrap()
+            .unwrap();
+        let keep = 0.5f64.powf(1.0 / WEEK_HALF_LIFE);
+        assert_eq!(s.score, round4(1.0 - keep));
+        let s2 = habit_strength(&idx, &h, "2026-01-19", None)
+            .unwrap()
+            .unwrap();
+        assert_eq!(s2.score, round4((1.0 - keep) * keep));
+
+        assert!(habit_strength(&idx, &habit("month", 3), "2026-01-31", None)
+            .unwrap()
+            .is_none());
+    }
+}
@@ -0,0 +1,738 @@
+//! `habit import --from loop|csv`: history exported by other trackers.
+//!
+//! Both readers produce the same intermediate shape (habits with a per-day
+//! history), which is then merged like a JSON import: habits are matched by
+//! name, new ones are created with an equivalent schedule and target, and
+//! the history is backfilled as `add` check-in events (source `import`).
+//!
+//! A new habit's `created_date` is its first imported day, so the history
+//! counts in stats exactly as if it had been tracked here all along. A
+//! matched habit keeps its `created_date`; days before it are stored but,
+//! as always, not counted (listed in `notes`).
+
+use crate::checkins::next_checkin_event_id;
+use crate::completion::CompletionIndex;
+use crate::date::parse_date_string;
+use crate::error::CliError;
+use crate::habits::{make_habit, next_habit_id};
+use crate::import::{
+    count, match_by_name, CollectionCounts, IdMapping, ImportMode, ImportReport, ImportSource,
+};
+use crate::model::{CheckinEvent, CheckinEventKind, Db, HabitKind};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Imported habits keep the `habit add` default quota.
+const DEFAULT_EXCUSE_QUOTA: u32 = 2;
+
+#[derive(Debug, Clone)]
+pub struct TrackerHabit {
+    /// How the source refers to the habit (Loop position, or the name).
+    pub key: String,
+    pub name: String,
+    pub schedule: String,
+    pub period: String,
+    pub target: u32,
+    pub kind: HabitKind,
+    pub notes: Option<String>,
+    pub archived: bool,
+    /// date -> quantity (days without a check-in are absent).
+    pub history: BTreeMap<String, u32>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct TrackerData {
+    pub habits: Vec<TrackerHabit>,
+    pub unmapped: Vec<String>,
+    pub notes: Vec<String>,
+}
+
+/// RFC 4180 CSV: quoted fields may contain commas, newlines and `""`.
+/// Blank lines are skipped; a leading BOM is ignored.
+pub fn parse_csv(text: &str) -> Result<Vec<Vec<String>>, CliError> {
+    let text = text.strip_prefix('\u{feff}').unwrap_or(text);
+    let mut rows: Vec<Vec<String>> = Vec::new();
+    let mut row: Vec<String> = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = text.chars().peekable();
+
+    let end_row = |row: &mut Vec<String>, rows: &mut Vec<Vec<String>>| {
+        let line = std::mem::take(row);
+        if !(line.len() == 1 && line[0].is_empty()) {
+            rows.push(line);
+        }
+    };
+
+    while let Some(c) = chars.next() {
+        if quoted {
+            match c {
+                '"' if chars.peek() == Some(&'"') => {
+                    chars.next();
+                    field.push('"');
+                }
+                '"' => quoted = false,
+                _ => field.push(c),
+            }
+            continue;
+        }
+        match c {
+            '"' if field.is_empty() => quoted = true,
+            ',' => row.push(std::mem::take(&mut field)),
+            '\r' if chars.peek() == Some(&'\n') => {}
+            '\n' | '\r' => {
+                row.push(std::mem::take(&mut field));
+                end_row(&mut row, &mut rows);
+            }
+            _ => field.push(c),
+        }
+    }
+    if quoted {
+        return Err(CliError::usage("Invalid CSV: unterminated quoted field"));
+    }
+    if !field.is_empty() || !row.is_empty() {
+        row.push(field);
+        end_row(&mut row, &mut rows);
+    }
+    Ok(rows)
+}
+
+fn read_file(path: &Path) -> Result<String, CliError> {
+    fs::read_to_string(path).map_err(|e| {
+        if e.kind() == std::io::ErrorKind::NotFound {
+            CliError::not_found(format!("File not found: {}", path.display()))
+        } else {
+            CliError::io("DB IO error")
+        }
+    })
+}
+
+fn column(header: &[String], names: &[&str]) -> Option<usize> {
+    header.iter().position(|h| names.contains(&h.trim()))
+}
+
+fn cell(row: &[String], idx: Option<usize>) -> &str {
+    idx.and_then(|i| row.get(i)).map(|s| s.trim()).unwrap_or("")
+}
+
+fn non_empty(s: &str) -> Option<&str> {
+    Some(s).filter(|s| !s.is_empty())
+}
+
+/// Loop's "N times every D days" as (schedule, period, target). The flag is
+/// set when there is no exact equivalent and a weekly target was derived.
+fn loop_frequency(num: u32, den: u32) -> (String, &'static str, u32, bool) {
+    let num = num.max(1);
+    match den {
+        0 | 1 => ("everyday".to_string(), "day", 1, false),
+        7 if num >= 7 => ("everyday".to_string(), "day", 1, false),
+        7 => ("everyday".to_string(), "week", num, false),
+        30 | 31 => ("everyday".to_string(), "month", num, false),
+        _ if num == 1 => (format!("every:{}d", den), "day", 1, false),
+        _ => {
+            let weekly = (num * 7).div_ceil(den).max(1);
+            ("everyday".to_string(), "week", weekly, true)
+        }
+    }
+}
+
+/// Loop exports are a ZIP; it has to be extracted first. `path` is the
+/// extracted folder or its top-level `Habits.csv`.
+fn loop_dir(path: &Path) -> Result<PathBuf, CliError> {
+    if path.is_dir() {
+        return Ok(path.to_path_buf());
+    }
+    if !path.exists() {
+        return Err(CliError::not_found(format!(
+            "File not found: {}",
+            path.display()
+        )));
+    }
+    let is_zip = fs::read(path)
+        .map(|b| b.starts_with(b"PK\x03\x04"))
+        .unwrap_or(false);
+    if is_zip {
+        return Err(CliError::usage(
+            "Loop exports are ZIP files: extract it and pass the folder",
+        ));
+    }
+    match path.parent() {
+        Some(dir) if path.file_name().is_some_and(|n| n == "Habits.csv") => Ok(dir.to_path_buf()),
+        _ => Err(CliError::usage(
+            "Expected a Loop export folder or its Habits.csv",
+        )),
+    }
+}
+
+/// Reads a Loop Habit Tracker export: `Habits.csv` for the definitions and
+/// the top-level `Checkmarks.csv` (one column per habit) for the history.
+///
+/// Boolean habits count explicit check marks (`2`) as 1; implicit marks
+/// (`1`, filled in by Loop from the frequency) and skips (`3`) are not
+/// check-ins. Numeric habits store thousandths (`2500` = 2.5), rounded to
+/// the nearest whole quantity.
+pub fn read_loop(path: &Path) -> Result<TrackerData, CliError> {
+    let dir = loop_dir(path)?;
+    let mut data = TrackerData::default();
+
+    let rows = parse_csv(&read_file(&dir.join("Habits.csv"))?)?;
+    let Some((header, body)) = rows.split_first() else {
+        return Err(CliError::usage("Invalid Loop export: Habits.csv is empty"));
+    };
+
+    let position = column(header, &["Position"]);
+    let name_col = column(header, &["Name"]);
+    let type_col = column(header, &["Type"]);
+    let question = column(header, &["Question"]);
+    let description = column(header, &["Description"]);
+    let numerator = column(header, &["FrequencyNumerator", "NumRepetitions"]);
+    let denominator = column(header, &["FrequencyDenominator", "Interval"]);
+    let unit = column(header, &["Unit"]);
+    let target_type = column(header, &["Target Type"]);
+    let target_value = column(header, &["Target Value"]);
+    let archived = column(header, &["Archived?"]);
+    let Some(name_col) = name_col else {
+        return Err(CliError::usage(
+            "Invalid Loop export: Habits.csv has no Name column",
+        ));
+    };
+
+    let used = [
+        position,
+        Some(name_col),
+        type_col,
+        question,
+        description,
+        numerator,
+        denominator,
+        unit,
+        target_type,
+        target_value,
+        archived,
+    ];
+    for (i, h) in header.iter().enumerate() {
+        if !used.contains(&Some(i)) {
+            data.unmapped.push(format!("Habits.csv: {}", h.trim()));
+        }
+    }
+
+    let mut numeric: Vec<bool> = Vec::new();
+    for row in body {
+        let name = cell(row, Some(name_col)).to_string();
+        let num: u32 = cell(row, numerator).parse().unwrap_or(1);
+        let den: u32 = cell(row, denominator).parse().unwrap_or(1);
+        let is_numeric = cell(row, type_col) == "1";
+
+        let (schedule, period, target, approximated) = if is_numeric {
+            let value: f64 = cell(row, target_value).parse().unwrap_or(1.0);
+            let period = match den {
+                7 => "week",
+                30 | 31 => "month",
+                _ => "day",
+            };
+            let target = value.ceil().max(1.0) as u32;
+            (
+                "everyday".to_string(),
+                period,
+                target,
+                !matches!(den, 0 | 1 | 7 | 30 | 31),
+            )
+        } else {
+            loop_frequency(num, den)
+        };
+        if approximated {
+            data.notes.push(format!(
+                "{}: {} times every {} days has no exact equivalent; using {}/{}",
+                name, num, den, target, period
+            ));
+        }
+
+        let notes: Vec<String> = [
+            non_empty(cell(row, question)).map(|s| s.to_string()),
+            non_empty(cell(row, description)).map(|s| s.to_string()),
+            non_empty(cell(row, unit)).map(|u| format!("unit: {}", u)),
+        ]
+        .into_iter()
+        .flatten()
+        .collect();
+
+        data.habits.push(TrackerHabit {
+            key: non_empty(cell(row, position)).unwrap_or(&name).to_string(),
+            name,
+            schedule,
+            period: period.to_string(),
+            target,
+            kind: if is_numeric && cell(row, target_type) == "1" {
+                HabitKind::Limit
+            } else {
+                HabitKind::Goal
+            },
+            notes: Some(notes.join("; ")).filter(|n| !n.is_empty()),
+            archived: matches!(cell(row, archived).to_lowercase().as_str(), "true" | "1"),
+            history: BTreeMap::new(),
+        });
+        numeric.push(is_numeric);
+    }
+
+    let rows = parse_csv(&read_file(&dir.join("Checkmarks.csv"))?)?;
+    let Some((header, body)) = rows.split_first() else {
+        return Ok(data);
+    };
+    // column -> habit index
+    let mut columns: Vec<(usize, usize)> = Vec::new();
+    for (i, h) in header.iter().enumerate().skip(1) {
+        match data.habits.iter().position(|x| x.name == h.trim()) {
+            Some(habit) => columns.push((i, habit)),
+            None => data.unmapped.push(format!("Checkmarks.csv: {}", h.trim())),
+        }
+    }
+
+    let mut implicit = 0u32;
+    for (line, row) in body.iter().enumerate() {
+        let date = cell(row, Some(0));
+        parse_date_string(date, "date").map_err(|_| {
+            CliError::usage(format!(
+                "Invalid date on Checkmarks.csv line {}: {}",
+                line + 2,
+                date
+            ))
+        })?;
+        for &(col, habit) in columns.iter() {
+            let raw = cell(row, Some(col));
+            let qty = if numeric[habit] {
+                match raw.parse::<f64>() {
+                    Ok(v) if raw.contains('.') => v.round(),
+                    Ok(v) => (v / 1000.0).round(),
+                    Err(_) => 0.0,
+                }
+            } else {
+                match raw {
+                    "2" => 1.0,
+                    "1" => {
+                        implicit += 1;
+                        0.0
+                    }
+                    _ => 0.0,
+                }
+            };
+            if qty >= 1.0 {
+                data.habits[habit]
+                    .history
+                    .insert(date.to_string(), qty.min(f64::from(u32::MAX)) as u32);
+            }
+        }
+    }
+    if implicit > 0 {
+        data.notes.push(format!(
+            "{} implicit check mark(s) filled in by Loop were not imported",
+            implicit
+        ));
+    }
+    Ok(data)
+}
+
+/// Fields a generic CSV column can be mapped to.
+pub const CSV_FIELDS: [&str; 8] = [
+    "habit", "date", "quantity", "schedule", "period", "target", "kind", "notes",
+];
+
+fn parse_quantity(raw: &str) -> Option<u32> {
+    match raw.to_lowercase().as_str() {
+        "" | "false" | "no" | "n" => Some(0),
+        "true" | "yes" | "y" | "x" => Some(1),
+        s => s
+            .parse::<f64>()
+            .ok()
+            .filter(|v| v.is_finite() && *v >= 0.0)
+            .map(|v| v.round().min(f64::from(u32::MAX)) as u32),
+    }
+}
+
+/// Reads a CSV with one row per check-in (or per day).
+///
+/// `maps` are `field=Column` pairs; fields not listed use the column of the
+/// same name (case-insensitive), if any. `habit` and `date` are required.
+/// Rows for the same habit and day add up; a missing `quantity` column
+/// counts each row as 1. The habit definition (`schedule`, `period`,
+/// `target`, `kind`, `notes`) comes from the habit's first row that has it,
+/// defaulting to `habit add`'s defaults.
+pub fn read_csv(
+    text: &str,
+    maps: &[String],
+    date_format: Option<&str>,
+) -> Result<TrackerData, CliError> {
+    let rows = parse_csv(text)?;
+    let Some((header, body)) = rows.split_first() else {
+        return Err(CliError::usage("Invalid CSV: no header row"));
+    };
+
+    let mut fields: BTreeMap<&str, usize> = BTreeMap::new();
+    for m in maps {
+        let (field, col) = m.split_once('=').ok_or_else(|| {
+            CliError::usage(format!("Invalid --map: {} (expected field=column)", m))
+        })?;
+        let field = CSV_FIELDS
+            .iter()
+            .find(|f| f.eq_ignore_ascii_case(field.trim()))
+            .ok_or_else(|| {
+                CliError::usage(format!(
+                    "Invalid --map field: {} (one of: {})",
+                    field,
+                    CSV_FIELDS.join(", ")
+                ))
+            })?;
+        let idx = header
+            .iter()
+            .position(|h| h.trim() == col.trim())
+            .ok_or_else(|| CliError::usage(format!("Unknown column: {}", col.trim())))?;
+        fields.insert(field, idx);
+    }
+    for field in CSV_FIELDS {
+        if fields.contains_key(field) {
+            continue;
+        }
+        let taken: Vec<usize> = fields.values().copied().collect();
+        if let Some((idx, _)) = header
+            .iter()
+            .enumerate()
+            .find(|(i, h)| !taken.contains(i) && h.trim().eq_ignore_ascii_case(field))
+        {
+            fields.insert(field, idx);
+        }
+    }
+    for required in ["habit", "date"] {
+        if !fields.contains_key(required) {
+            return Err(CliError::usage(format!(
+                "Missing column for {} (use --map {}=<column>)",
+                required, required
+            )));
+        }
+    }
+
+    let mut data = TrackerData::default();
+    let mapped: Vec<usize> = fields.values().copied().collect();
+    for (i, h) in header.iter().enumerate() {
+        if !mapped.contains(&i) {
+            data.unmapped.push(h.trim().to_string());
+        }
+    }
+
+    let col = |field: &str| fields.get(field).copied();
+    let mut by_name: BTreeMap<String, usize> = BTreeMap::new();
+    for (i, row) in body.iter().enumerate() {
+        let line = i + 2;
+        let name = cell(row, col("habit"));
+        if name.is_empty() {
+            return Err(CliError::usage(format!("Missing habit on line {}", line)));
+        }
+
+        let raw_date = cell(row, col("date"));
+        let date = match date_format {
+            Some(fmt) => chrono::NaiveDate::parse_from_str(raw_date, fmt)
+                .map(|d| d.format("%Y-%m-%d").to_string())
+                .ok(),
+            None => parse_date_string(raw_date, "date")
+                .ok()
+                .map(|_| raw_date.to_string()),
+        }
+        .ok_or_else(|| CliError::usage(format!("Invalid date on line {}: {}", line, raw_date)))?;
+
+        let qty = match col("quantity") {
+            Some(c) => parse_quantity(cell(row, Some(c))).ok_or_else(|| {
+                CliError::usage(format!(
+                    "Invalid quantity on line {}: {}",
+                    line,
+                    cell(row, Some(c))
+                ))
+            })?,
+            None => 1,
+        };
+
+        let idx = *by_name.entry(name.to_string()).or_insert_with(|| {
+            data.habits.push(TrackerHabit {
+                key: name.to_string(),
+                name: name.to_string(),
+                schedule: String::new(),
+                period: String::new(),
+                target: 0,
+                kind: HabitKind::Goal,
+                notes: None,
+                archived: false,
+                history: BTreeMap::new(),
+            });
+            data.habits.len() - 1
+        });
+        let habit = &mut data.habits[idx];
+        if habit.schedule.is_empty() {
+            habit.schedule = cell(row, col("schedule")).to_string();
+        }
+        if habit.period.is_empty() {
+            habit.period = cell(row, col("period")).to_lowercase();
+        }
+        if habit.target == 0 {
+            let raw = cell(row, col("target"));
+            if !raw.is_empty() {
+                habit.target = raw.parse().map_err(|_| {
+                    CliError::usage(format!("Invalid target on line {}: {}", line, raw))
+                })?;
+            }
+        }
+        match cell(row, col("kind")).to_lowercase().as_str() {
+            "" => {}
+            "goal" => habit.kind = HabitKind::Goal,
+            "limit" => habit.kind = HabitKind::Limit,
+            other => {
+                return Err(CliError::usage(format!(
+                    "Invalid kind on line {}: {}",
+                    line, other
+                )))
+            }
+        }
+        if habit.notes.is_none() {
+            habit.notes = non_empty(cell(row, col("notes"))).map(|s| s.to_string());
+        }
+        if qty > 0 {
+            let day = habit.history.entry(date).or_insert(0);
+            *day = day.saturating_add(qty);
+        }
+    }
+
+    for h in data.habits.iter_mut() {
+        if h.schedule.is_empty() {
+            h.schedule = "everyday".to_string();
+        }
+        if h.period.is_empty() {
+            h.period = "day".to_string();
+        }
+        if h.target == 0 {
+            h.target = 1;
+        }
+    }
+    Ok(data)
+}
+
+/// Merges tracker data into `db`: habits by name, history as `add` events
+/// topping each day up to the imported quantity (never lowering it), so
+/// importing the same file twice adds nothing.
+pub fn import(
+    db: &mut Db,
+    data: &TrackerData,
+    source: ImportSource,
+    today: &str,
+) -> Result<ImportReport, CliError> {
+    let mut report = ImportReport {
+        source,
+        mode: ImportMode::Merge,
+        version: None,
+        collections: ["habits", "checkin_events"]
+            .iter()
+            .map(|c| (c.to_string(), CollectionCounts::default()))
+            .collect(),
+        habits: Vec::new(),
+        routines: Vec::new(),
+        unmapped: data.unmapped.clone(),
+        notes: data.notes.clone(),
+    };
+
+    let idx = CompletionIndex::new(db);
+    for h in data.habits.iter() {
+        let existing = match_by_name(
+            db.habits.iter().map(|x| (x.id.as_str(), x.name.as_str())),
+            &h.name,
+        );
+        let matched = existing.is_some();
+        let habit_id = match existing {
+            Some(id) => {
+                let created = db
+                    .habits
+                    .iter()
+                    .find(|x| x.id == id)
+                    .map(|x| x.created_date.clone())
+                    .unwrap_or_default();
+                let before = h.history.range(..created.clone()).count();
+                if before > 0 {
+                    report.notes.push(format!(
+                        "{}: {} day(s) before its created date {} are stored but not counted",
+                        h.name, before, created
+                    ));
+                }
+                id
+            }
+            None => {
+                let created = h
+                    .history
+                    .keys()
+                    .next()
+                    .map(|d| d.as_str())
+                    .filter(|d| *d < today)
+                    .unwrap_or(today);
+                let mut habit = make_habit(
+                    next_habit_id(db),
+                    &h.name,
+                    &h.schedule,
+                    &h.period,
+                    h.target,
+                    h.notes.as_deref(),
+                    created,
+                    false,
+                    DEFAULT_EXCUSE_QUOTA,
+                    h.kind,
+                )
+                .map_err(|e| CliError::usage(format!("{}: {}", h.name, e.message)))?;
+                if h.archived {
+                    habit.archived = true;
+                    habit.archived_date = Some(today.to_string());
+                }
+                let id = habit.id.clone();
+                db.habits.push(habit);
+                id
+            }
+        };
+        count(&mut report, "habits", !matched);
+        report.habits.push(IdMapping {
+            from: h.key.clone(),
+            to: habit_id.clone(),
+            name: h.name.clone(),
+            matched,
+        });
+
+        for (date, &qty) in h.history.iter() {
+            let cur = if matched {
+                idx.raw_quantity(&habit_id, date)
+            } else {
+                0
+            };
+            let added = qty > cur;
+            if added {
+                let id = next_checkin_event_id(db);
+                db.checkin_events.push(CheckinEvent {
+                    id,
+                    habit_id: habit_id.clone(),
+                    date: date.clone(),
+                    ts: format!("{}T00:00:00Z", date),
+                    kind: CheckinEventKind::Add,
+                    delta: i64::from(qty - cur),
+                    note: None,
+                    source: "import".to_string(),
+                });
+            }
+            count(&mut report, "checkin_events", added);
+        }
+    }
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::default_db;
+
+    #[test]
+    fn csv_parser_handles_quotes_and_line_endings() {
+        let rows = parse_csv("\u{feff}a,b\r\n\"x, \"\"y\"\"\",\"multi\nline\"\n\n1,\n").unwrap();
+        assert_eq!(
+            rows,
+            vec![
+                vec!["a".to_string(), "b".to_string()],
+                vec!["x, \"y\"".to_string(), "multi\nline".to_string()],
+                vec!["1".to_string(), String::new()],
+            ]
+        );
+        assert!(parse_csv("\"open").is_err());
+    }
+
+    #[test]
+    fn loop_export_maps_frequencies_and_checkmarks() {
+        let tmp = tempfile::tempdir().unwrap();
+        fs::write(
+            tmp.path().join("Habits.csv"),
+            "Position,Name,Type,Question,Description,FrequencyNumerator,FrequencyDenominator,Color,Unit,Target Type,Target Value,Archived?\n\
+             001,Meditate,0,Did you meditate?,,1,1,#FF0000,,0,0,false\n\
+             002,Gym,0,,,3,7,#00FF00,,0,0,false\n\
+             003,Pages,1,,,1,1,#0000FF,pages,0,20,true\n\
+             004,Call mom,0,,,1,3,#000000,,0,0,false\n",
+        )
+        .unwrap();
+        fs::write(
+            tmp.path().join("Checkmarks.csv"),
+            "Date,Meditate,Gym,Pages,Call mom,Ghost\n\
+             2026-01-03,1,2,25500,2,2\n\
+             2026-01-02,2,0,0,0,0\n\
+             2026-01-01,2,3,-1,0,0\n",
+        )
+        .unwrap();
+
+        let data = read_loop(tmp.path()).unwrap();
+        let shape: Vec<(&str, &str, &str, u32)> = data
+            .habits
+            .iter()
+            .map(|h| {
+                (
+                    h.name.as_str(),
+                    h.schedule.as_str(),
+                    h.period.as_str(),
+                    h.target,
+                )
+            })
+            .collect();
+        assert_eq!(
+            shape,
+            vec![
+                ("Meditate", "everyday", "day", 1),
+                ("Gym", "everyday", "week", 3),
+                ("Pages", "everyday", "day", 20),
+                ("Call mom", "every:3d", "day", 1),
+            ]
+        );
+        assert_eq!(
+            data.unmapped,
+            vec!["Habits.csv: Color", "Checkmarks.csv: Ghost"]
+        );
+        assert_eq!(data.habits[0].history.len(), 2);
+        assert_eq!(data.habits[2].history.get("2026-01-03"), Some(&26));
+        assert!(data.habits[2].archived);
+        assert_eq!(data.habits[2].notes.as_deref(), Some("unit: pages"));
+
+        let mut db = default_db();
+        let report = import(&mut db, &data, ImportSource::Loop, "2026-02-01").unwrap();
+        assert_eq!(report.collections["habits"].added, 4);
+        assert_eq!(report.collections["checkin_events"].added, 5);
+        assert_eq!(db.habits[0].created_date, "2026-01-01");
+        assert_eq!(db.habits[3].created_date, "2026-01-03");
+        assert!(!db.habits[0].needs_declaration);
+
+        // re-import matches every habit and adds nothing
+        let again = import(&mut db, &data, ImportSource::Loop, "2026-02-01").unwrap();
+        assert!(again.habits.iter().all(|m| m.matched));
+        assert_eq!(again.collections["checkin_events"].added, 0);
+        assert_eq!(again.collections["checkin_events"].skipped, 5);
+    }
+
+    #[test]
+    fn generic_csv_uses_column_mapping() {
+        let text = "When,Activity,Count,Mood\n\
+                    03/01/2026,Water,2,ok\n\
+                    03/01/2026,Water,1,ok\n\
+                    04/01/2026,Water,,meh\n\
+                    04/01/2026,Run,1,good\n";
+        let maps = vec![
+            "date=When".to_string(),
+            "habit=Activity".to_string(),
+            "quantity=Count".to_string(),
+        ];
+        let data = read_csv(text, &maps, Some("%d/%m/%Y")).unwrap();
+        assert_eq!(data.unmapped, vec!["Mood"]);
+        assert_eq!(data.habits[0].history.get("2026-01-03"), Some(&3));
+        assert_eq!(data.habits[0].history.len(), 1);
+        assert_eq!(data.habits[1].history.get("2026-01-04"), Some(&1));
+
+        let err = read_csv(text, &["habit=Activity".to_string()], None).unwrap_err();
+        assert_eq!(
+            err.message,
+            "Missing column for date (use --map date=<column>)"
+        );
+        let err = read_csv(text, &maps, None).unwrap_err();
+        assert_eq!(err.message, "Invalid date on line 2: 03/01/2026");
+    }
+}
@@ -9,3 +9,17 @@ pub fn validate_rfc3339(ts: &str, label: &str) -> Result<(), CliError> {
         .map(|_| ())
         .map_err(|_| CliError::usage(format!("Invalid {}: {}", label, ts)))
 }
+
+/// Current system time as RFC3339 UTC (seconds precision).
+///
+/// The default for `checkin --ts`, and the lock file's acquisition time.
+pub fn system_now_rfc3339() -> String {
+    let secs = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0);
+    chrono::DateTime::from_timestamp(secs, 0)
+        .unwrap_or_default()
+        .format("%Y-%m-%dT%H:%M:%SZ")
+        .to_string()
+}
@@ -21,6 +21,20 @@ fn stderr_str(out: &std::process::Output) -> String {
     String::from_utf8_lossy(&out.stderr).to_string()
 }
 
+/// `habit --db <db> --today <day> --no-color <args>`.
+fn run_at_output(db: &str, day: &str, args: &[&str]) -> std::process::Output {
+    let mut full: Vec<&str> = vec!["--db", db, "--today", day, "--no-color"];
+    full.extend_from_slice(args);
+    run_habit(&full, &[("HABITCLI_TODAY", day), ("NO_COLOR", "1")])
+}
+
+/// Like `run_at_output`, but the command must succeed; returns stdout.
+fn run_at(db: &str, day: &str, args: &[&str]) -> String {
+    let out = run_at_output(db, day, args);
+    assert_eq!(out.status.code(), Some(0), "stderr: {}", stderr_str(&out));
+    stdout_str(&out)
+}
+
 #[test]
 fn routine_session_flow_is_deterministic_and_idempotent() {
     let tmp = tempfile::tempdir().unwrap();
@@ -256,7 +270,7 @@ fn nag_plan_respects_quiet_hours_snooze_and_cadence() {
         assert_eq!(out.status.code(), Some(0), "stderr: {}", stderr_str(&out));
 
         let json: serde_json::Value = serde_json::from_str(stdout_str(&out).trim()).unwrap();
-        assert_eq!(json.get("should_send").unwrap().as_bool().unwrap(), false);
+        assert!(!json.get("should_send").unwrap().as_bool().unwrap());
         assert_eq!(
             json.get("next_check_at").unwrap().as_str().unwrap(),
             "2026-01-31T08:00:00+09:00"
@@ -283,7 +297,7 @@ fn nag_plan_respects_quiet_hours_snooze_and_cadence() {
 
         let json: serde_json::Value = serde_json::from_str(stdout_str(&out).trim()).unwrap();
         assert_eq!(json.get("severity").unwrap().as_u64().unwrap(), 1);
-        assert_eq!(json.get("should_send").unwrap().as_bool().unwrap(), true);
+        assert!(json.get("should_send").unwrap().as_bool().unwrap());
         assert_eq!(
             json.get("next_check_at").unwrap().as_str().unwrap(),
             "2026-01-31T09:00:00+09:00"
@@ -325,7 +339,7 @@ fn nag_plan_respects_quiet_hours_snooze_and_cadence() {
         assert_eq!(out.status.code(), Some(0), "stderr: {}", stderr_str(&out));
 
         let json: serde_json::Value = serde_json::from_str(stdout_str(&out).trim()).unwrap();
-        assert_eq!(json.get("should_send").unwrap().as_bool().unwrap(), false);
+        assert!(!json.get("should_send").unwrap().as_bool().unwrap());
         assert_eq!(
             json.get("next_check_at").unwrap().as_str().unwrap(),
             "2026-01-31T12:00:00+09:00"
@@ -369,7 +383,7 @@ fn nag_plan_respects_quiet_hours_snooze_and_cadence() {
         assert_eq!(out.status.code(), Some(0), "stderr: {}", stderr_str(&out));
 
         let json: serde_json::Value = serde_json::from_str(stdout_str(&out).trim()).unwrap();
-        assert_eq!(json.get("should_send").unwrap().as_bool().unwrap(), false);
+        assert!(!json.get("should_send").unwrap().as_bool().unwrap());
         assert_eq!(
             json.get("next_check_at").unwrap().as_str().unwrap(),
             "2026-01-31T13:00:00+09:00"
@@ -395,7 +409,7 @@ fn nag_plan_respects_quiet_hours_snooze_and_cadence() {
         assert_eq!(out.status.code(), Some(0), "stderr: {}", stderr_str(&out));
 
         let json: serde_json::Value = serde_json::from_str(stdout_str(&out).trim()).unwrap();
-        assert_eq!(json.get("should_send").unwrap().as_bool().unwrap(), true);
+        assert!(json.get("should_send").unwrap().as_bool().unwrap());
         assert_eq!(
             json.get("next_check_at").unwrap().as_str().unwrap(),
             "2026-01-31T13:30:00+09:00"
@@ -708,7 +722,7 @@ fn mvp_flow_is_deterministic_in_json_mode() {
         assert_eq!(out.status.code(), Some(0), "stderr: {}", stderr_str(&out));
 
         let json: serde_json::Value = serde_json::from_str(stdout_str(&out).trim()).unwrap();
-        assert_eq!(json.get("version").unwrap().as_i64().unwrap(), 1);
+        assert_eq!(json.get("version").unwrap().as_i64().unwrap(), 2);
         assert!(json.get("habits").unwrap().is_array());
         assert!(json.get("checkins").unwrap().is_array());
     }
@@ -966,14 +980,11 @@ fn new_habit_completion_requires_declaration() {
             .iter()
             .find(|h| h.get("name").unwrap().as_str().unwrap() == "Gate")
             .unwrap();
-        assert_eq!(gate.get("done").unwrap().as_bool().unwrap(), false);
+        assert!(!gate.get("done").unwrap().as_bool().unwrap());
         assert_eq!(gate.get("quantity").unwrap().as_u64().unwrap(), 0);
         assert_eq!(gate.get("raw_quantity").unwrap().as_u64().unwrap(), 1);
-        assert_eq!(
-            gate.get("needs_declaration").unwrap().as_bool().unwrap(),
-            true
-        );
-        assert_eq!(gate.get("declared").unwrap().as_bool().unwrap(), false);
+        assert!(gate.get("needs_declaration").unwrap().as_bool().unwrap());
+        assert!(!gate.get("declared").unwrap().as_bool().unwrap());
     }
 
     // declare and check status again
@@ -1015,9 +1026,9 @@ fn new_habit_completion_requires_declaration() {
             .iter()
             .find(|h| h.get("name").unwrap().as_str().unwrap() == "Gate")
             .unwrap();
-        assert_eq!(gate.get("done").unwrap().as_bool().unwrap(), true);
+        assert!(gate.get("done").unwrap().as_bool().unwrap());
         assert_eq!(gate.get("quantity").unwrap().as_u64().unwrap(), 1);
-        assert_eq!(gate.get("declared").unwrap().as_bool().unwrap(), true);
+        assert!(gate.get("declared").unwrap().as_bool().unwrap());
     }
 }
 
@@ -1693,8 +1704,8 @@ fn due_command_json_schema_and_deterministic_ordering() {
             assert!(row.get("done").is_some(), "Missing done");
 
             // All should have scheduled=true and done=false
-            assert_eq!(row.get("scheduled").unwrap().as_bool().unwrap(), true);
-            assert_eq!(row.get("done").unwrap().as_bool().unwrap(), false);
+            assert!(row.get("scheduled").unwrap().as_bool().unwrap());
+            assert!(!row.get("done").unwrap().as_bool().unwrap());
         }
 
         // Validate Alpha specifics
@@ -2028,3 +2039,2078 @@ fn due_respects_declaration_gate() {
         assert!(!hay.contains("stretch"), "expected Stretch to be cleared from due list, got: {}", hay);
     }
 }
+
+#[test]
+fn db_migrate_reports_versions_and_rejects_newer_files() {
+    let tmp = tempfile::tempdir().unwrap();
+    let db_path = tmp.path().join("db.json");
+    let db = db_path.to_string_lossy().to_string();
+
+    let today = "2026-01-31";
+
+    let shared_env = [
+        ("HABITCLI_DB_PATH", db.as_str()),
+        ("HABITCLI_TODAY", today),
+        ("NO_COLOR", "1"),
+    ];
+    let global = ["--db", db.as_str(), "--today", today, "--no-color"];
+
+    // create a current-version DB
+    {
+        let mut args: Vec<&str> = Vec::new();
+        args.extend_from_slice(&global);
+        args.extend_from_slice(&["add", "Water", "--format", "json"]);
+        let out = run_habit(&args, &shared_env);
+        assert_eq!(out.status.code(), Some(0), "stderr: {}", stderr_str(&out));
+    }
+
+    // dry-run on an up-to-date DB is a no-op
+    {
+        let mut args: Vec<&str> = Vec::new();
+        args.extend_from_slice(&global);
+        args.extend_from_slice(&["db", "migrate", "--dry-run", "--format", "json"]);
+        let out = run_habit(&args, &shared_env);
+        assert_eq!(out.status.code(), Some(0), "stderr: {}", stderr_str(&out));
+        let json: serde_json::Value = serde_json::from_str(stdout_str(&out).trim()).unwrap();
+        assert!(json.get("dry_run").unwrap().as_bool().unwrap());
+        let report = json.get("report").unwrap();
+        assert_eq!(
+            report.get("from_version").unwrap(),
+            report.get("to_version").unwrap()
+        );
+        assert!(report.get("steps").unwrap().as_array().unwrap().is_empty());
+        assert!(json.get("backup").unwrap().is_null());
+    }
+
+    // a file written by a newer build is refused (exit 5), not silently rewritten
+    {
+        let txt = std::fs::read_to_string(&db_path).unwrap();
+        let mut v: serde_json::Value = serde_json::from_str(&txt).unwrap();
+        v["version"] = serde_json::Value::from(99);
+        std::fs::write(&db_path, serde_json::to_string(&v).unwrap()).unwrap();
+
+        let mut args: Vec<&str> = Vec::new();
+        args.extend_from_slice(&global);
+        args.extend_from_slice(&["list", "--format", "json"]);
+        let out = run_habit(&args, &shared_env);
+        assert_eq!(out.status.code(), Some(5));
+        assert!(stderr_str(&out).contains("newer than supported"));
+    }
+}
+
+#[test]
+fn config_stores_timezone_and_day_start() {
+    let tmp = tempfile::tempdir().unwrap();
+    let db_path = tmp.path().join("db.json");
+    let db = db_path.to_string_lossy().to_string();
+
+    let today = "2026-01-31";
+
+    let shared_env = [
+        ("HABITCLI_DB_PATH", db.as_str()),
+        ("HABITCLI_TODAY", today),
+        ("NO_COLOR", "1"),
+    ];
+    let global = ["--db", db.as_str(), "--today", today, "--no-color"];
+
+    // defaults: UTC, midnight boundary
+    {
+        let mut args: Vec<&str> = Vec::new();
+        args.extend_from_slice(&global);
+        args.extend_from_slice(&["config", "show", "--format", "json"]);
+        let out = run_habit(&args, &shared_env);
+        assert_eq!(out.status.code(), Some(0), "stderr: {}", stderr_str(&out));
+        let json: serde_json::Value = serde_json::from_str(stdout_str(&out).trim()).unwrap();
+        let cfg = json.get("config").unwrap();
+        assert!(cfg.get("timezone").unwrap().is_null());
+        assert_eq!(cfg.get("day_start").unwrap().as_str().unwrap(), "00:00");
+    }
+
+    {
+        let mut args: Vec<&str> = Vec::new();
+        args.extend_from_slice(&global);
+        args.extend_from_slice(&[
+            "config",
+            "set",
+            "--timezone",
+            "Asia/Seoul",
+            "--day-start",
+            "04:00",
+            "--format",
+            "json",
+        ]);
+        let out = run_habit(&args, &shared_env);
+        assert_eq!(out.status.code(), Some(0), "stderr: {}", stderr_str(&out));
+        let json: serde_json::Value = serde_json::from_str(stdout_str(&out).trim()).unwrap();
+        let cfg = json.get("config").unwrap();
+        assert_eq!(cfg.get("timezone").unwrap().as_str().unwrap(), "Asia/Seoul");
+        assert_eq!(cfg.get("day_start").unwrap().as_str().unwrap(), "04:00");
+    }
+
+    // invalid values are usage errors and leave the config untouched
+    for bad in [["--timezone", "Mars/Olympus"], ["--day-start", "4am"]] {
+        let mut args: Vec<&str> = Vec::new();
+        args.extend_from_slice(&global);
+        args.extend_from_slice(&["config", "set"]);
+        args.extend_from_slice(&bad);
+        let out = run_habit(&args, &shared_env);
+        assert_eq!(out.status.code(), Some(2));
+    }
+
+    // without a --today override, "today" is derived from the stored settings
+    {
+        let out = run_habit(
+            &["--db", db.as_str(), "due", "--format", "json"],
+            &[("NO_COLOR", "1")],
+        );
+        assert_eq!(out.status.code(), Some(0), "stderr: {}", stderr_str(&out));
+    }
+}
+
+#[test]
+fn unreadable_db_does_not_block_commands_without_today() {
+    let tmp = tempfile::tempdir().unwrap();
+    let db_path = tmp.path().join("db.json");
+    let db = db_path.to_string_lossy().to_string();
+    std::fs::write(&db_path, "{\"version\": 2, \"hab").unwrap();
+
+    // no --today and no HABITCLI_TODAY: today would come from the DB's timezone
+    let out = Command::new(habit_bin())
+        .args(["--db", db.as_str(), "--no-color", "db", "restore", "--list", "--format", "json"])
+        .env_remove("HABITCLI_TODAY")
+        .output()
+        .unwrap();
+    assert_eq!(out.status.code(), Some(0), "stderr: {}", stderr_str(&out));
+
+    let out = Command::new(habit_bin())
+        .args(["--db", db.as_str(), "--no-color", "status"])
+        .env_remove("HABITCLI_TODAY")
+        .output()
+        .unwrap();
+    assert_eq!(out.status.code(), Some(5));
+}
+
+#[test]
+fn every_n_days_schedule_drives_due_and_stats() {
+    let tmp = tempfile::tempdir().unwrap();
+    let db_path = tmp.path().join("db.json");
+    let db = db_path.to_string_lossy().to_string();
+
+    let today = "2026-01-05";
+
+    let shared_env = [
+        ("HABITCLI_DB_PATH", db.as_str()),
+        ("HABITCLI_TODAY", today),
+        ("NO_COLOR", "1"),
+    ];
+    let global = ["--db", db.as_str(), "--today", today, "--no-color"];
+
+    // anchor defaults to the creation date
+    {
+        let mut args: Vec<&str> = Vec::new();
+        args.extend_from_slice(&global);
+        args.extend_from_slice(&[
+            "add",
+            "Water plants",
+            "--schedule",
+            "every:3d",
+            "--needs-declaration",
+            "false",
+            "--format",
+            "json",
+        ]);
+        let out = run_habit(&args, &shared_env);
+        assert_eq!(out.status.code(), Some(0), "stderr: {}", stderr_str(&out));
+        let json: serde_json::Value = serde_json::from_str(stdout_str(&out).trim()).unwrap();
+        let schedule = json.get("habit").unwrap().get("schedule").unwrap();
+        assert_eq!(schedule.get("type").unwrap().as_str().unwrap(), "every_n_days");
+        assert_eq!(schedule.get("every").unwrap().as_u64().unwrap(), 3);
+        assert_eq!(schedule.get("anchor").unwrap().as_str().unwrap(), today);
+    }
+
+    for (date, expected) in [("2026-01-05", 1), ("2026-01-06", 0), ("2026-01-08", 1)] {
+        let mut args: Vec<&str> = Vec::new();
+        args.extend_from_slice(&global);
+        args.extend_from_slice(&["due", "--date", date, "--format", "json"]);
+        let out = run_habit(&args, &shared_env);
+        assert_eq!(out.status.code(), Some(0), "stderr: {}", stderr_str(&out));
+        let json: serde_json::Value = serde_json::from_str(stdout_str(&out).trim()).unwrap();
+        assert_eq!(
+            json.get("counts").unwrap().get("due").unwrap().as_u64().unwrap(),
+            expected,
+            "date {}",
+            date
+        );
+    }
+
+    {
+        let mut args: Vec<&str> = Vec::new();
+        args.extend_from_slice(&global);
+        args.extend_from_slice(&["checkin", "Water", "--date", "2026-01-08"]);
+        let out = run_habit(&args, &shared_env);
+        assert_eq!(out.status.code(), Some(0), "stderr: {}", stderr_str(&out));
+    }
+
+    // scheduled days in 01-05..01-13: 05, 08, 11 -> 1 success
+    {
+        let mut args: Vec<&str> = Vec::new();
+        args.extend_from_slice(&global);
+        args.extend_from_slice(&[
+            "stats",
+            "Water",
+            "--from",
+            "2026-01-05",
+            "--to",
+            "2026-01-13",
+            "--format",
+            "json",
+        ]);
+        let out = run_habit(&args, &shared_env);
+        assert_eq!(out.status.code(), Some(0), "stderr: {}", stderr_str(&out));
+        let json: serde_json::Value = serde_json::from_str(stdout_str(&out).trim()).unwrap();
+        let rate = json.get("stats").unwrap()[0].get("success_rate").unwrap();
+        assert_eq!(rate.get("eligible").unwrap().as_u64().unwrap(), 3);
+        assert_eq!(rate.get("successes").unwrap().as_u64().unwrap(), 1);
+    }
+}
+
+#[test]
+fn month_period_and_days_of_month_schedule() {
+    let tmp = tempfile::tempdir().unwrap();
+    let db_path = tmp.path().join("db.json");
+    let db = db_path.to_string_lossy().to_string();
+
+    let today = "2026-01-05";
+
+    let shared_env = [
+        ("HABITCLI_DB_PATH", db.as_str()),
+        ("HABITCLI_TODAY", today),
+        ("NO_COLOR", "1"),
+    ];
+    let global = ["--db", db.as_str(), "--today", today, "--no-color"];
+
+    for add in [
+        &["add", "Long run", "--period", "month", "--target", "4"][..],
+        &["add", "Finances", "--schedule", "monthly:1,15,last"][..],
+    ] {
+        let mut args: Vec<&str> = Vec::new();
+        args.extend_from_slice(&global);
+        args.extend_from_slice(add);
+        args.extend_from_slice(&["--needs-declaration", "false", "--format", "json"]);
+        let out = run_habit(&args, &shared_env);
+        assert_eq!(out.status.code(), Some(0), "stderr: {}", stderr_str(&out));
+    }
+
+    // January: 3 runs (miss). February: 4 runs (hit).
+    for (date, qty) in [("2026-01-10", "3"), ("2026-02-03", "2"), ("2026-02-20", "2")] {
+        let mut args: Vec<&str> = Vec::new();
+        args.extend_from_slice(&global);
+        args.extend_from_slice(&["checkin", "Long", "--date", date, "--qty", qty]);
+        let out = run_habit(&args, &shared_env);
+        assert_eq!(out.status.code(), Some(0), "stderr: {}", stderr_str(&out));
+    }
+
+    {
+        let mut args: Vec<&str> = Vec::new();
+        args.extend_from_slice(&global);
+        args.extend_from_slice(&[
+            "stats",
+            "Long",
+            "--from",
+            "2026-01-01",
+            "--to",
+            "2026-02-28",
+            "--format",
+            "json",
+        ]);
+        let out = run_habit(&args, &shared_env);
+        assert_eq!(out.status.code(), Some(0), "stderr: {}", stderr_str(&out));
+        let json: serde_json::Value = serde_json::from_str(stdout_str(&out).trim()).unwrap();
+        let row = &json.get("stats").unwrap()[0];
+        assert_eq!(row.get("period").unwrap().as_str().unwrap(), "month");
+        let rate = row.get("success_rate").unwrap();
+        assert_eq!(rate.get("eligible").unwrap().as_u64().unwrap(), 2);
+        assert_eq!(rate.get("successes").unwrap().as_u64().unwrap(), 1);
+        assert_eq!(row.get("current_streak").unwrap().as_u64().unwrap(), 1);
+    }
+
+    // a new month starts a fresh window
+    {
+        let mut args: Vec<&str> = Vec::new();
+        args.extend_from_slice(&global);
+        args.extend_from_slice(&["due", "--date", "2026-03-10", "--format", "json"]);
+        let out = run_habit(&args, &shared_env);
+        assert_eq!(out.status.code(), Some(0), "stderr: {}", stderr_str(&out));
+        let json: serde_json::Value = serde_json::from_str(stdout_str(&out).trim()).unwrap();
+        let due = json.get("due").unwrap().as_array().unwrap();
+        assert_eq!(due.len(), 1);
+        assert_eq!(due[0].get("period").unwrap().as_str().unwrap(), "month");
+        assert_eq!(due[0].get("quantity").unwrap().as_u64().unwrap(), 0);
+        assert_eq!(due[0].get("remaining").unwrap().as_u64().unwrap(), 4);
+    }
+
+    // day-of-month schedule: 1st, 15th and last day (Long run is done for February)
+    for (date, expected) in [("2026-02-15", 1), ("2026-02-16", 0), ("2026-02-28", 1)] {
+        let mut args: Vec<&str> = Vec::new();
+        args.extend_from_slice(&global);
+        args.extend_from_slice(&["due", "--date", date, "--format", "json"]);
+        let out = run_habit(&args, &shared_env);
+        assert_eq!(out.status.code(), Some(0), "stderr: {}", stderr_str(&out));
+        let json: serde_json::Value = serde_json::from_str(stdout_str(&out).trim()).unwrap();
+        assert_eq!(
+            json.get("counts").unwrap().get("due").unwrap().as_u64().unwrap(),
+            expected,
+            "date {}",
+            date
+        );
+    }
+
+    {
+        let mut args: Vec<&str> = Vec::new();
+        args.extend_from_slice(&["--db", db.as_str(), "--today", "2026-02-10"]);
+        args.extend_from_slice(&["status", "--format", "json"]);
+        let out = run_habit(&args, &shared_env);
+        assert_eq!(out.status.code(), Some(0), "stderr: {}", stderr_str(&out));
+        let json: serde_json::Value = serde_json::from_str(stdout_str(&out).trim()).unwrap();
+        let week_rows = json.get("week").unwrap().get("habits").unwrap().as_array().unwrap();
+        let run = week_rows
+            .iter()
+            .find(|r| r.get("name").unwrap().as_str().unwrap() == "Long run")
+            .unwrap();
+        assert_eq!(run.get("month").unwrap().as_str().unwrap(), "2026-02");
+        assert_eq!(run.get("quantity").unwrap().as_u64().unwrap(), 4);
+    }
+}
+
+#[test]
+fn limit_habits_succeed_at_or_under_the_ceiling() {
+    let tmp = tempfile::tempdir().unwrap();
+    let db_path = tmp.path().join("db.json");
+    let db = db_path.to_string_lossy().to_string();
+
+    let today = "2026-01-01";
+
+    let shared_env = [
+        ("HABITCLI_DB_PATH", db.as_str()),
+        ("HABITCLI_TODAY", today),
+        ("NO_COLOR", "1"),
+    ];
+    let global = ["--db", db.as_str(), "--today", today, "--no-color"];
+
+    // declaration gate left at its default: it must not hide consumption
+    {
+        let mut args: Vec<&str> = Vec::new();
+        args.extend_from_slice(&global);
+        args.extend_from_slice(&[
+            "add", "Coffee", "--kind", "limit", "--target", "2", "--format", "json",
+        ]);
+        let out = run_habit(&args, &shared_env);
+        assert_eq!(out.status.code(), Some(0), "stderr: {}", stderr_str(&out));
+        let json: serde_json::Value = serde_json::from_str(stdout_str(&out).trim()).unwrap();
+        assert_eq!(
+            json.get("habit").unwrap().get("kind").unwrap().as_str().unwrap(),
+            "limit"
+        );
+    }
+
+    for (date, qty) in [("2026-01-01", "1"), ("2026-01-02", "3")] {
+        let mut args: Vec<&str> = Vec::new();
+        args.extend_from_slice(&global);
+        args.extend_from_slice(&["checkin", "Coffee", "--date", date, "--qty", qty]);
+        let out = run_habit(&args, &shared_env);
+        assert_eq!(out.status.code(), Some(0), "stderr: {}", stderr_str(&out));
+    }
+
+    // 01-01 under, 01-02 over, 01-03 nothing logged (under)
+    {
+        let mut args: Vec<&str> = Vec::new();
+        args.extend_from_slice(&global);
+        args.extend_from_slice(&[
+            "stats", "Coffee", "--from", "2026-01-01", "--to", "2026-01-03", "--format", "json",
+        ]);
+        let out = run_habit(&args, &shared_env);
+        assert_eq!(out.status.code(), Some(0), "stderr: {}", stderr_str(&out));
+        let json: serde_json::Value = serde_json::from_str(stdout_str(&out).trim()).unwrap();
+        let row = &json.get("stats").unwrap()[0];
+        assert_eq!(row.get("kind").unwrap().as_str().unwrap(), "limit");
+        assert_eq!(row.get("current_streak").unwrap().as_u64().unwrap(), 1);
+        assert_eq!(row.get("longest_streak").unwrap().as_u64().unwrap(), 1);
+        let rate = row.get("success_rate").unwrap();
+        assert_eq!(rate.get("successes").unwrap().as_u64().unwrap(), 2);
+        assert_eq!(rate.get("eligible").unwrap().as_u64().unwrap(), 3);
+    }
+
+    // limit habits are never "due", even when over the ceiling
+    {
+        let mut args: Vec<&str> = Vec::new();
+        args.extend_from_slice(&global);
+        args.extend_from_slice(&["due", "--date", "2026-01-02", "--format", "json"]);
+        let out = run_habit(&args, &shared_env);
+        assert_eq!(out.status.code(), Some(0), "stderr: {}", stderr_str(&out));
+        let json: serde_json::Value = serde_json::from_str(stdout_str(&out).trim()).unwrap();
+        assert!(json.get("due").unwrap().as_array().unwrap().is_empty());
+    }
+
+    {
+        let mut args: Vec<&str> = Vec::new();
+        args.extend_from_slice(&global);
+        args.extend_from_slice(&["status", "--date", "2026-01-02", "--format", "json"]);
+        let out = run_habit(&args, &shared_env);
+        assert_eq!(out.status.code(), Some(0), "stderr: {}", stderr_str(&out));
+        let json: serde_json::Value = serde_json::from_str(stdout_str(&out).trim()).unwrap();
+        let row = &json.get("today").unwrap().get("habits").unwrap()[0];
+        assert!(!row.get("done").unwrap().as_bool().unwrap());
+        assert_eq!(row.get("quantity").unwrap().as_u64().unwrap(), 3);
+    }
+
+    // penalty debt is sized from the overage (3 - 2) * multiplier
+    {
+        let mut args: Vec<&str> = Vec::new();
+        args.extend_from_slice(&global);
+        args.extend_from_slice(&[
+            "penalty",
+            "arm",
+            "Coffee",
+            "--date",
+            today,
+            "--ts",
+            "2026-01-01T09:00:00Z",
+        ]);
+        let out = run_habit(&args, &shared_env);
+        assert_eq!(out.status.code(), Some(0), "stderr: {}", stderr_str(&out));
+
+        for (date, expected) in [("2026-01-01", 0), ("2026-01-02", 1)] {
+            let mut args: Vec<&str> = Vec::new();
+            args.extend_from_slice(&global);
+            args.extend_from_slice(&[
+                "penalty",
+                "tick",
+                "--date",
+                date,
+                "--ts",
+                "2026-01-02T23:00:00Z",
+                "--format",
+                "json",
+            ]);
+            let out = run_habit(&args, &shared_env);
+            assert_eq!(out.status.code(), Some(0), "stderr: {}", stderr_str(&out));
+            let json: serde_json::Value = serde_json::from_str(stdout_str(&out).trim()).unwrap();
+            let created = json.get("created").unwrap().as_array().unwrap();
+            assert_eq!(created.len(), expected, "date {}", date);
+            if expected == 1 {
+                assert_eq!(created[0].get("quantity").unwrap().as_u64().unwrap(), 2);
+            }
+        }
+    }
+}
+
+#[test]
+fn checkins_are_recorded_as_events_and_v1_rows_migrate() {
+    let tmp = tempfile::tempdir().unwrap();
+    let db_path = tmp.path().join("db.json");
+    let db = db_path.to_string_lossy().to_string();
+
+    let today = "2026-01-03";
+
+    let shared_env = [
+        ("HABITCLI_DB_PATH", db.as_str()),
+        ("HABITCLI_TODAY", today),
+        ("NO_COLOR", "1"),
+    ];
+    let global = ["--db", db.as_str(), "--today", today, "--no-color"];
+
+    // a v1 file with aggregate per-day rows
+    let v1 = serde_json::json!({
+        "version": 1,
+        "meta": { "next_habit_number": 2 },
+        "habits": [{
+            "id": "h0001",
+            "name": "Pushups",
+            "schedule": { "type": "days_of_week", "days": [1, 2, 3, 4, 5, 6, 7] },
+            "target": { "period": "day", "quantity": 10 },
+            "notes": null,
+            "archived": false,
+            "created_date": "2026-01-01",
+            "archived_date": null,
+        }],
+        "checkins": [{ "habit_id": "h0001", "date": "2026-01-01", "quantity": 7 }],
+    });
+    std::fs::write(&db_path, serde_json::to_string(&v1).unwrap()).unwrap();
+
+    {
+        let mut args: Vec<&str> = Vec::new();
+        args.extend_from_slice(&global);
+        args.extend_from_slice(&["db", "migrate", "--format", "json"]);
+        let out = run_habit(&args, &shared_env);
+        assert_eq!(out.status.code(), Some(0), "stderr: {}", stderr_str(&out));
+        let json: serde_json::Value = serde_json::from_str(stdout_str(&out).trim()).unwrap();
+        let report = json.get("report").unwrap();
+        assert_eq!(report.get("from_version").unwrap().as_u64().unwrap(), 1);
+        assert_eq!(report.get("to_version").unwrap().as_u64().unwrap(), 2);
+        assert!(std::path::Path::new(&format!("{}.v1.bak", db)).exists());
+    }
+
+    let steps: [&[&str]; 3] = [
+        &[
+            "checkin",
+            "Pushups",
+            "--date",
+            "2026-01-02",
+            "--qty",
+            "5",
+            "--ts",
+            "2026-01-02T07:30:00+09:00",
+            "--note",
+            "morning set",
+        ],
+        &[
+            "checkin",
+            "Pushups",
+            "--date",
+            "2026-01-02",
+            "--set",
+            "3",
+            "--ts",
+            "2026-01-02T21:00:00+09:00",
+            "--source",
+            "watch",
+        ],
+        &[
+            "checkin",
+            "Pushups",
+            "--date",
+            "2026-01-01",
+            "--delete",
+            "--ts",
+            "2026-01-03T08:00:00Z",
+        ],
+    ];
+    for step in steps.iter() {
+        let mut args: Vec<&str> = Vec::new();
+        args.extend_from_slice(&global);
+        args.extend_from_slice(step);
+        args.extend_from_slice(&["--format", "json"]);
+        let out = run_habit(&args, &shared_env);
+        assert_eq!(out.status.code(), Some(0), "stderr: {}", stderr_str(&out));
+    }
+
+    {
+        let mut args: Vec<&str> = Vec::new();
+        args.extend_from_slice(&global);
+        args.extend_from_slice(&["show", "Pushups", "--format", "json"]);
+        let out = run_habit(&args, &shared_env);
+        assert_eq!(out.status.code(), Some(0), "stderr: {}", stderr_str(&out));
+        let json: serde_json::Value = serde_json::from_str(stdout_str(&out).trim()).unwrap();
+
+        // totals are derived: 01-01 deleted, 01-02 set to 3
+        let checkins = json.get("checkins").unwrap().as_array().unwrap();
+        assert_eq!(checkins.len(), 1);
+        assert_eq!(checkins[0].get("date").unwrap().as_str().unwrap(), "2026-01-02");
+        assert_eq!(checkins[0].get("quantity").unwrap().as_u64().unwrap(), 3);
+
+        // ...while the full history is kept
+        let events = json.get("checkin_events").unwrap().as_array().unwrap();
+        let summary: Vec<(String, i64, String)> = events
+            .iter()
+            .map(|e| {
+                (
+                    e.get("kind").unwrap().as_str().unwrap().to_string(),
+                    e.get("delta").unwrap().as_i64().unwrap(),
+                    e.get("source").unwrap().as_str().unwrap().to_string(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("add".to_string(), 7, "migration".to_string()),
+                ("add".to_string(), 5, "cli".to_string()),
+                ("set".to_string(), -2, "watch".to_string()),
+                ("delete".to_string(), -7, "cli".to_string()),
+            ]
+        );
+        assert_eq!(events[1].get("note").unwrap().as_str().unwrap(), "morning set");
+        assert_eq!(
+            events[1].get("ts").unwrap().as_str().unwrap(),
+            "2026-01-02T07:30:00+09:00"
+        );
+        assert_eq!(events[3].get("id").unwrap().as_str().unwrap(), "c000004");
+    }
+
+    // a bad --ts is rejected before anything is written
+    {
+        let mut args: Vec<&str> = Vec::new();
+        args.extend_from_slice(&global);
+        args.extend_from_slice(&["checkin", "Pushups", "--ts", "yesterday"]);
+        let out = run_habit(&args, &shared_env);
+        assert_eq!(out.status.code(), Some(2));
+    }
+}
+
+#[test]
+fn lock_timeout_waits_and_stale_locks_are_reclaimed() {
+    let tmp = tempfile::tempdir().unwrap();
+    let db_path = tmp.path().join("db.json");
+    let db = db_path.to_string_lossy().to_string();
+    let lock_path = tmp.path().join("db.json.lock");
+
+    let today = "2026-01-01";
+
+    let shared_env = [
+        ("HABITCLI_DB_PATH", db.as_str()),
+        ("HABITCLI_TODAY", today),
+        ("NO_COLOR", "1"),
+    ];
+    let global = ["--db", db.as_str(), "--today", today, "--no-color"];
+
+    // a fresh lock is respected; with a timeout the write retries before failing
+    {
+        std::fs::write(&lock_path, "").unwrap();
+        let mut args: Vec<&str> = Vec::new();
+        args.extend_from_slice(&global);
+        args.extend_from_slice(&["--lock-timeout", "0.3", "add", "Water"]);
+        let started = std::time::Instant::now();
+        let out = run_habit(&args, &shared_env);
+        assert_eq!(out.status.code(), Some(5));
+        assert!(stderr_str(&out).contains("DB is locked"));
+        assert!(started.elapsed() >= std::time::Duration::from_millis(300));
+    }
+
+    // the lock is released while we wait
+    {
+        let releaser = {
+            let lock_path = lock_path.clone();
+            std::thread::spawn(move || {
+                std::thread::sleep(std::time::Duration::from_millis(200));
+                std::fs::remove_file(&lock_path).unwrap();
+            })
+        };
+        let mut args: Vec<&str> = Vec::new();
+        args.extend_from_slice(&global);
+        args.extend_from_slice(&["--lock-timeout", "10", "add", "Water"]);
+        let out = run_habit(&args, &shared_env);
+        releaser.join().unwrap();
+        assert_eq!(out.status.code(), Some(0), "stderr: {}", stderr_str(&out));
+        assert!(!lock_path.exists());
+    }
+
+    // a lock left behind by a killed (pre-owner-info) process is reclaimed
+    {
+        let f = std::fs::File::create(&lock_path).unwrap();
+        f.set_modified(std::time::SystemTime::now() - std::time::Duration::from_secs(3600))
+            .unwrap();
+        drop(f);
+
+        let mut args: Vec<&str> = Vec::new();
+        args.extend_from_slice(&global);
+        args.extend_from_slice(&["add", "Stretch"]);
+        let out = run_habit(&args, &shared_env);
+        assert_eq!(out.status.code(), Some(0), "stderr: {}", stderr_str(&out));
+        assert!(!lock_path.exists());
+    }
+
+    // invalid timeouts are usage errors
+    {
+        let mut args: Vec<&str> = Vec::new();
+        args.extend_from_slice(&global);
+        args.extend_from_slice(&["--lock-timeout", "-1", "list"]);
+        let out = run_habit(&args, &shared_env);
+        assert_eq!(out.status.code(), Some(2));
+    }
+}
+
+#[test]
+fn undo_reverts_journaled_operations() {
+    let tmp = tempfile::tempdir().unwrap();
+    let db_path = tmp.path().join("db.json");
+    let db = db_path.to_string_lossy().to_string();
+
+    let today = "2026-01-05";
+
+    let shared_env = [
+        ("HABITCLI_DB_PATH", db.as_str()),
+        ("HABITCLI_TODAY", today),
+        ("NO_COLOR", "1"),
+    ];
+    let global = ["--db", db.as_str(), "--today", today, "--no-color"];
+
+    let steps: [&[&str]; 4] = [
+        &["add", "Pushups", "--target", "10"],
+        &["checkin", "Pushups", "--qty", "8", "--ts", "2026-01-05T08:00:00Z"],
+        &["checkin", "Pushups", "--set", "0", "--ts", "2026-01-05T09:00:00Z"],
+        &["archive", "Pushups"],
+    ];
+    for step in steps.iter() {
+        let mut args: Vec<&str> = Vec::new();
+        args.extend_from_slice(&global);
+        args.extend_from_slice(step);
+        let out = run_habit(&args, &shared_env);
+        assert_eq!(out.status.code(), Some(0), "stderr: {}", stderr_str(&out));
+    }
+
+    let show = |shared_env: &[(&str, &str)]| -> serde_json::Value {
+        let mut args: Vec<&str> = Vec::new();
+        args.extend_from_slice(&global);
+        args.extend_from_slice(&["show", "Pushups", "--format", "json"]);
+        let out = run_habit(&args, shared_env);
+        assert_eq!(out.status.code(), Some(0), "stderr: {}", stderr_str(&out));
+        serde_json::from_str(stdout_str(&out).trim()).unwrap()
+    };
+
+    // history lists invocations newest first, without global options
+    {
+        let mut args: Vec<&str> = Vec::new();
+        args.extend_from_slice(&global);
+        args.extend_from_slice(&["history", "--format", "json"]);
+        let out = run_habit(&args, &shared_env);
+        assert_eq!(out.status.code(), Some(0), "stderr: {}", stderr_str(&out));
+        let json: serde_json::Value = serde_json::from_str(stdout_str(&out).trim()).unwrap();
+        let ops = json.get("operations").unwrap().as_array().unwrap();
+        let labels: Vec<(&str, &str)> = ops
+            .iter()
+            .map(|o| {
+                (
+                    o.get("id").unwrap().as_str().unwrap(),
+                    o.get("command").unwrap().as_str().unwrap(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            labels,
+            vec![
+                ("op000004", "archive Pushups"),
+                (
+                    "op000003",
+                    "checkin Pushups --set 0 --ts 2026-01-05T09:00:00Z"
+                ),
+                (
+                    "op000002",
+                    "checkin Pushups --qty 8 --ts 2026-01-05T08:00:00Z"
+                ),
+                ("op000001", "add Pushups --target 10"),
+            ]
+        );
+
+        // stamped with the command's --ts, else midnight of --today
+        let stamps: Vec<&str> = ops.iter().map(|o| o["ts"].as_str().unwrap()).collect();
+        assert_eq!(
+            stamps,
+            vec![
+                "2026-01-05T00:00:00Z",
+                "2026-01-05T09:00:00Z",
+                "2026-01-05T08:00:00Z",
+                "2026-01-05T00:00:00Z",
+            ]
+        );
+    }
+
+    // undo the archive and the mistaken --set 0
+    {
+        let mut args: Vec<&str> = Vec::new();
+        args.extend_from_slice(&global);
+        args.extend_from_slice(&["undo", "--steps", "2", "--format", "json"]);
+        let out = run_habit(&args, &shared_env);
+        assert_eq!(out.status.code(), Some(0), "stderr: {}", stderr_str(&out));
+        let json: serde_json::Value = serde_json::from_str(stdout_str(&out).trim()).unwrap();
+        let undone: Vec<&str> = json
+            .get("undone")
+            .unwrap()
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|o| o.get("id").unwrap().as_str().unwrap())
+            .collect();
+        assert_eq!(undone, vec!["op000004", "op000003"]);
+
+        let json = show(&shared_env);
+        assert!(!json["habit"]["archived"].as_bool().unwrap());
+        assert_eq!(json["checkins"][0]["quantity"].as_u64().unwrap(), 8);
+        assert_eq!(json["checkin_events"].as_array().unwrap().len(), 1);
+    }
+
+    // the undo is itself journaled; undo never redoes
+    {
+        let mut args: Vec<&str> = Vec::new();
+        args.extend_from_slice(&global);
+        args.extend_from_slice(&["history", "--limit", "1"]);
+        let out = run_habit(&args, &shared_env);
+        assert_eq!(out.status.code(), Some(0), "stderr: {}", stderr_str(&out));
+        let text = stdout_str(&out);
+        assert!(text.contains("op000005"));
+        assert!(text.contains("undo of op000004,op000003"));
+
+        let mut args: Vec<&str> = Vec::new();
+        args.extend_from_slice(&global);
+        args.extend_from_slice(&["undo"]);
+        let out = run_habit(&args, &shared_env);
+        assert_eq!(out.status.code(), Some(0), "stderr: {}", stderr_str(&out));
+        assert!(stdout_str(&out).contains("Undone: op000002"));
+        assert!(show(&shared_env)["checkins"].as_array().unwrap().is_empty());
+    }
+
+    // asking for more than is available changes nothing
+    {
+        let mut args: Vec<&str> = Vec::new();
+        args.extend_from_slice(&global);
+        args.extend_from_slice(&["undo", "--steps", "5"]);
+        let out = run_habit(&args, &shared_env);
+        assert_eq!(out.status.code(), Some(2));
+        assert!(stderr_str(&out).contains("Only 1 operation(s) can be undone"));
+    }
+}
+
+#[test]
+fn rolling_backups_can_be_listed_and_restored() {
+    let tmp = tempfile::tempdir().unwrap();
+    let db_path = tmp.path().join("db.json");
+    let db = db_path.to_string_lossy().to_string();
+
+    let today = "2026-01-05";
+
+    let shared_env = [
+        ("HABITCLI_DB_PATH", db.as_str()),
+        ("HABITCLI_TODAY", today),
+        ("NO_COLOR", "1"),
+        ("HABITCLI_BACKUPS", "3"),
+    ];
+    let global = ["--db", db.as_str(), "--today", today, "--no-color"];
+
+    // first write has nothing to back up; the next four each back up the prior file
+    for name in ["A", "B", "C", "D", "E"] {
+        let mut args: Vec<&str> = Vec::new();
+        args.extend_from_slice(&global);
+        args.extend_from_slice(&["add", name]);
+        let out = run_habit(&args, &shared_env);
+        assert_eq!(out.status.code(), Some(0), "stderr: {}", stderr_str(&out));
+    }
+
+    let list_backups = || -> Vec<String> {
+        let mut args: Vec<&str> = Vec::new();
+        args.extend_from_slice(&global);
+        args.extend_from_slice(&["db", "restore", "--list", "--format", "json"]);
+        let out = run_habit(&args, &shared_env);
+        assert_eq!(out.status.code(), Some(0), "stderr: {}", stderr_str(&out));
+        let json: serde_json::Value = serde_json::from_str(stdout_str(&out).trim()).unwrap();
+        json.get("backups")
+            .unwrap()
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|b| b.get("name").unwrap().as_str().unwrap().to_string())
+            .collect()
+    };
+
+    let habit_names = || -> Vec<String> {
+        let txt = std::fs::read_to_string(&db_path).unwrap();
+        let v: serde_json::Value = serde_json::from_str(&txt).unwrap();
+        v["habits"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|h| h["name"].as_str().unwrap().to_string())
+            .collect()
+    };
+
+    let backups = list_backups();
+    assert_eq!(backups.len(), 3);
+    let mut sorted = backups.clone();
+    sorted.sort();
+    sorted.reverse();
+    assert_eq!(backups, sorted, "newest first");
+
+    // no-op invocations don't rewrite the file or rotate backups
+    {
+        let mut args: Vec<&str> = Vec::new();
+        args.extend_from_slice(&global);
+        args.extend_from_slice(&["config", "set", "--day-start", "00:00"]);
+        let out = run_habit(&args, &shared_env);
+        assert_eq!(out.status.code(), Some(0), "stderr: {}", stderr_str(&out));
+        assert_eq!(list_backups(), backups);
+    }
+
+    // default restore brings back the newest backup (the DB before "E")
+    {
+        let mut args: Vec<&str> = Vec::new();
+        args.extend_from_slice(&global);
+        args.extend_from_slice(&["db", "restore", "--format", "json"]);
+        let out = run_habit(&args, &shared_env);
+        assert_eq!(out.status.code(), Some(0), "stderr: {}", stderr_str(&out));
+        let json: serde_json::Value = serde_json::from_str(stdout_str(&out).trim()).unwrap();
+        assert!(json
+            .get("restored_from")
+            .unwrap()
+            .as_str()
+            .unwrap()
+            .ends_with(&backups[0]));
+        assert!(json.get("backup").unwrap().is_string());
+        assert_eq!(habit_names(), vec!["A", "B", "C", "D"]);
+    }
+
+    // an older backup by name; the pre-restore state is kept as the newest backup
+    {
+        let mut args: Vec<&str> = Vec::new();
+        args.extend_from_slice(&global);
+        args.extend_from_slice(&["db", "restore", backups[1].as_str()]);
+        let out = run_habit(&args, &shared_env);
+        assert_eq!(out.status.code(), Some(0), "stderr: {}", stderr_str(&out));
+        assert_eq!(habit_names(), vec!["A", "B", "C"]);
+
+        let newest = list_backups()[0].clone();
+        let mut args: Vec<&str> = Vec::new();
+        args.extend_from_slice(&global);
+        args.extend_from_slice(&["db", "restore", newest.as_str()]);
+        let out = run_habit(&args, &shared_env);
+        assert_eq!(out.status.code(), Some(0), "stderr: {}", stderr_str(&out));
+        assert_eq!(habit_names(), vec!["A", "B", "C", "D"]);
+    }
+
+    // a truncated DB can still be restored over; its bytes are kept as-is
+    {
+        let txt = std::fs::read_to_string(&db_path).unwrap();
+        let truncated = &txt[..txt.len() / 2];
+        std::fs::write(&db_path, truncated).unwrap();
+
+        // the newest backup is the DB before the last restore
+        let newest = list_backups()[0].clone();
+        let mut args: Vec<&str> = Vec::new();
+        args.extend_from_slice(&global);
+        args.extend_from_slice(&["db", "restore", newest.as_str(), "--format", "json"]);
+        let out = run_habit(&args, &shared_env);
+        assert_eq!(out.status.code(), Some(0), "stderr: {}", stderr_str(&out));
+        assert_eq!(habit_names(), vec!["A", "B", "C"]);
+
+        let json: serde_json::Value = serde_json::from_str(stdout_str(&out).trim()).unwrap();
+        let aside = json.get("backup").unwrap().as_str().unwrap();
+        assert_eq!(std::fs::read_to_string(aside).unwrap(), truncated);
+    }
+
+    {
+        let mut args: Vec<&str> = Vec::new();
+        args.extend_from_slice(&global);
+        args.extend_from_slice(&["db", "restore", "nope.json"]);
+        let out = run_habit(&args, &shared_env);
+        assert_eq!(out.status.code(), Some(3));
+    }
+}
+
+#[test]
+fn db_check_reports_and_repairs_integrity_issues() {
+    let tmp = tempfile::tempdir().unwrap();
+    let db_path = tmp.path().join("db.json");
+    let db = db_path.to_string_lossy().to_string();
+
+    let today = "2026-01-05";
+
+    let shared_env = [
+        ("HABITCLI_DB_PATH", db.as_str()),
+        ("HABITCLI_TODAY", today),
+        ("NO_COLOR", "1"),
+    ];
+    let global = ["--db", db.as_str(), "--today", today, "--no-color"];
+
+    let run_check = |extra: &[&str]| -> serde_json::Value {
+        let mut args: Vec<&str> = Vec::new();
+        args.extend_from_slice(&global);
+        args.extend_from_slice(&["db", "check", "--format", "json"]);
+        args.extend_from_slice(extra);
+        let out = run_habit(&args, &shared_env);
+        assert_eq!(out.status.code(), Some(0), "stderr: {}", stderr_str(&out));
+        serde_json::from_str(stdout_str(&out).trim()).unwrap()
+    };
+
+    {
+        let mut args: Vec<&str> = Vec::new();
+        args.extend_from_slice(&global);
+        args.extend_from_slice(&["add", "Water"]);
+        let out = run_habit(&args, &shared_env);
+        assert_eq!(out.status.code(), Some(0), "stderr: {}", stderr_str(&out));
+    }
+    let json = run_check(&[]);
+    assert!(json["ok"].as_bool().unwrap());
+    assert!(json["issues"].as_array().unwrap().is_empty());
+
+    // hand-edit: counter behind an existing id + a declaration for a missing habit
+    {
+        let txt = std::fs::read_to_string(&db_path).unwrap();
+        let mut v: serde_json::Value = serde_json::from_str(&txt).unwrap();
+        v["meta"]["next_habit_number"] = serde_json::Value::from(1);
+        v["declarations"] = serde_json::json!([{
+            "id": "d000001",
+            "habit_id": "h0042",
+            "date": "2026-01-05",
+            "ts": "2026-01-05T08:00:00Z",
+            "text": "I will",
+        }]);
+        v["meta"]["next_declaration_number"] = serde_json::Value::from(2);
+        std::fs::write(&db_path, serde_json::to_string(&v).unwrap()).unwrap();
+    }
+
+    let summary = |json: &serde_json::Value| -> Vec<(String, String, bool)> {
+        json["issues"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|i| {
+                (
+                    i["kind"].as_str().unwrap().to_string(),
+                    i["id"].as_str().unwrap().to_string(),
+                    i["repaired"].as_bool().unwrap(),
+                )
+            })
+            .collect()
+    };
+
+    let json = run_check(&[]);
+    assert!(!json["ok"].as_bool().unwrap());
+    assert_eq!(
+        summary(&json),
+        vec![
+            ("orphan_reference".to_string(), "d000001".to_string(), false),
+            (
+                "counter_behind".to_string(),
+                "next_habit_number".to_string(),
+                false
+            ),
+        ]
+    );
+
+    let json = run_check(&["--repair"]);
+    assert!(!json["ok"].as_bool().unwrap(), "orphans are not auto-repaired");
+    assert_eq!(
+        summary(&json),
+        vec![
+            ("orphan_reference".to_string(), "d000001".to_string(), false),
+            (
+                "counter_behind".to_string(),
+                "next_habit_number".to_string(),
+                true
+            ),
+        ]
+    );
+
+    let json = run_check(&[]);
+    assert_eq!(json["issues"].as_array().unwrap().len(), 1);
+
+    // the next habit gets a fresh id
+    {
+        let mut args: Vec<&str> = Vec::new();
+        args.extend_from_slice(&global);
+        args.extend_from_slice(&["add", "Stretch", "--format", "json"]);
+        let out = run_habit(&args, &shared_env);
+        assert_eq!(out.status.code(), Some(0), "stderr: {}", stderr_str(&out));
+        let json: serde_json::Value = serde_json::from_str(stdout_str(&out).trim()).unwrap();
+        assert_eq!(json["habit"]["id"].as_str().unwrap(), "h0002");
+    }
+}
+
+#[test]
+fn sqlite_storage_roundtrips_through_db_convert() {
+    let tmp = tempfile::tempdir().unwrap();
+    let json_path = tmp.path().join("db.json");
+    let sqlite_path = tmp.path().join("db.sqlite");
+    let back_path = tmp.path().join("back.json");
+    let json_db = json_path.to_string_lossy().to_string();
+    let sqlite_db = sqlite_path.to_string_lossy().to_string();
+    let back_db = back_path.to_string_lossy().to_string();
+
+    let today = "2026-01-05";
+    let shared_env = [("HABITCLI_TODAY", today), ("NO_COLOR", "1")];
+
+    let run_ok = |db: &str, extra: &[&str]| -> String {
+        let mut args: Vec<&str> = vec!["--db", db, "--today", today, "--no-color"];
+        args.extend_from_slice(extra);
+        let out = run_habit(&args, &shared_env);
+        assert_eq!(out.status.code(), Some(0), "stderr: {}", stderr_str(&out));
+        stdout_str(&out)
+    };
+
+    run_ok(&json_db, &["add", "Water", "--target", "3"]);
+    run_ok(&json_db, &["add", "Stretch"]);
+    run_ok(&json_db, &["checkin", "Water", "--qty", "2", "--date", "2026-01-04"]);
+
+    let out = run_ok(
+        &json_db,
+        &["db", "convert", "--to", sqlite_db.as_str(), "--format", "json"],
+    );
+    let json: serde_json::Value = serde_json::from_str(out.trim()).unwrap();
+    assert_eq!(json["from_storage"], "json");
+    assert_eq!(json["to_storage"], "sqlite");
+
+    // the SQLite DB is a working DB: writes, reads and undo all go through it
+    run_ok(&sqlite_db, &["checkin", "Water", "--qty", "1"]);
+    run_ok(&sqlite_db, &["checkin", "Stretch"]);
+    run_ok(&sqlite_db, &["undo"]);
+    let out = run_ok(&sqlite_db, &["show", "Water", "--format", "json"]);
+    let json: serde_json::Value = serde_json::from_str(out.trim()).unwrap();
+    assert_eq!(json["checkin_events"].as_array().unwrap().len(), 2);
+
+    run_ok(
+        &sqlite_db,
+        &["db", "convert", "--to", back_db.as_str(), "--format", "json"],
+    );
+    let export = |db: &str| -> serde_json::Value {
+        let out = run_ok(db, &["export", "--format", "json"]);
+        serde_json::from_str(out.trim()).unwrap()
+    };
+    assert_eq!(export(&back_db), export(&sqlite_db));
+    let back: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(&back_path).unwrap()).unwrap();
+    assert_eq!(back["journal"].as_array().unwrap().len(), 6);
+
+    // never overwrites
+    let out = run_habit(
+        &[
+            "--db",
+            json_db.as_str(),
+            "--today",
+            today,
+            "db",
+            "convert",
+            "--to",
+            sqlite_db.as_str(),
+        ],
+        &shared_env,
+    );
+    assert_eq!(out.status.code(), Some(2));
+
+    // --storage overrides the extension
+    let forced = tmp.path().join("forced.dat");
+    let forced_db = forced.to_string_lossy().to_string();
+    run_ok(&forced_db, &["--storage", "sqlite", "add", "Read"]);
+    let head = std::fs::read(&forced).unwrap();
+    assert!(head.starts_with(b"SQLite format 3"));
+    let out = run_ok(&forced_db, &["--storage", "sqlite", "list", "--format", "json"]);
+    assert!(out.contains("Read"));
+}
+
+#[test]
+fn json_export_roundtrips_through_import() {
+    let tmp = tempfile::tempdir().unwrap();
+    let src = tmp.path().join("src.json").to_string_lossy().to_string();
+    let restored_path = tmp.path().join("restored.json");
+    let restored = restored_path.to_string_lossy().to_string();
+    let merged = tmp.path().join("merged.json").to_string_lossy().to_string();
+    let file = tmp.path().join("export.json").to_string_lossy().to_string();
+
+    let today = "2026-01-05";
+    let shared_env = [("HABITCLI_TODAY", today), ("NO_COLOR", "1")];
+
+    let run_ok = |db: &str, extra: &[&str]| -> String {
+        let mut args: Vec<&str> = vec!["--db", db, "--today", today, "--no-color"];
+        args.extend_from_slice(extra);
+        let out = run_habit(&args, &shared_env);
+        assert_eq!(out.status.code(), Some(0), "stderr: {}", stderr_str(&out));
+        stdout_str(&out)
+    };
+    let export = |db: &str| -> serde_json::Value {
+        let out = run_ok(db, &["export", "--format", "json", "--include-archived"]);
+        serde_json::from_str(out.trim()).unwrap()
+    };
+
+    run_ok(&src, &["add", "Water", "--target", "2"]);
+    run_ok(&src, &["add", "Read"]);
+    run_ok(&src, &["checkin", "Water", "--date", "2026-01-04", "--ts", "2026-01-04T08:00:00Z"]);
+    run_ok(&src, &["checkin", "Read", "--date", "2026-01-04", "--ts", "2026-01-04T21:00:00Z"]);
+    run_ok(
+        &src,
+        &["declare", "Water", "--date", "2026-01-04", "--ts", "2026-01-04T07:00:00Z", "--text", "2 glasses"],
+    );
+    run_ok(
+        &src,
+        &["excuse", "Read", "--date", "2026-01-03", "--ts", "2026-01-03T22:00:00Z", "--reason", "travel"],
+    );
+    run_ok(
+        &src,
+        &[
+            "penalty", "arm", "Water", "--multiplier", "2", "--cap", "8", "--deadline-days", "1",
+            "--date", "2026-01-01", "--ts", "2026-01-01T09:00:00Z",
+        ],
+    );
+    run_ok(&src, &["penalty", "tick", "--date", "2026-01-05", "--ts", "2026-01-05T23:50:00Z"]);
+    run_ok(&src, &["routine", "add", "Morning"]);
+    run_ok(&src, &["routine", "step-add", "Morning", "--name", "Stretch", "--minutes", "5"]);
+    run_ok(
+        &src,
+        &["routine", "start", "Morning", "--date", "2026-01-04", "--ts", "2026-01-04T07:30:00Z"],
+    );
+    run_ok(&src, &["export", "--format", "json", "--include-archived", "--out", file.as_str()]);
+
+    let original = export(&src);
+    assert_eq!(original["version"], 2);
+    for key in ["declarations", "excuses", "penalty_rules", "penalty_debts", "routines", "routine_sessions"] {
+        assert_eq!(original[key].as_array().unwrap().len(), 1, "{}", key);
+    }
+
+    // dry run reports but writes nothing
+    let out = run_ok(&restored, &["import", file.as_str(), "--dry-run", "--format", "json"]);
+    let report: serde_json::Value = serde_json::from_str(out.trim()).unwrap();
+    assert_eq!(report["dry_run"], true);
+    assert_eq!(report["mode"], "restore");
+    assert_eq!(report["collections"]["checkin_events"]["added"], 2);
+    assert!(!restored_path.exists());
+
+    // restore into an empty DB: identical export
+    run_ok(&restored, &["import", file.as_str()]);
+    assert_eq!(export(&restored), original);
+
+    // merge: "read" matches Read, Water is new; re-import adds nothing
+    run_ok(&merged, &["add", "Stretch"]);
+    run_ok(&merged, &["add", "read"]);
+    let out = run_ok(&merged, &["import", file.as_str(), "--format", "json"]);
+    let report: serde_json::Value = serde_json::from_str(out.trim()).unwrap();
+    assert_eq!(report["mode"], "merge");
+    let mapping: Vec<(String, String, bool)> = report["habits"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|m| {
+            (
+                m["from"].as_str().unwrap().to_string(),
+                m["to"].as_str().unwrap().to_string(),
+                m["matched"].as_bool().unwrap(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        mapping,
+        vec![
+            ("h0002".to_string(), "h0002".to_string(), true),
+            ("h0001".to_string(), "h0003".to_string(), false),
+        ]
+    );
+    let merged_export = export(&merged);
+    assert_eq!(merged_export["penalty_debts"][0]["id"], "pd_h0003_20260105");
+    assert_eq!(merged_export["routine_sessions"][0]["id"], "rs:r0001:2026-01-04:1");
+
+    let out = run_ok(&merged, &["import", file.as_str(), "--format", "json"]);
+    let report: serde_json::Value = serde_json::from_str(out.trim()).unwrap();
+    assert!(report["collections"]
+        .as_object()
+        .unwrap()
+        .values()
+        .all(|c| c["added"] == 0));
+
+    // unsupported version
+    std::fs::write(&file, r#"{"version": 99, "habits": []}"#).unwrap();
+    let out = run_habit(&["--db", merged.as_str(), "import", file.as_str()], &shared_env);
+    assert_eq!(out.status.code(), Some(2));
+}
+
+#[test]
+fn csv_export_writes_every_collection() {
+    let tmp = tempfile::tempdir().unwrap();
+    let db = tmp.path().join("db.json").to_string_lossy().to_string();
+    let out_dir = tmp.path().join("csv");
+    let out_dir_s = out_dir.to_string_lossy().to_string();
+
+    let today = "2026-01-05";
+    let shared_env = [("HABITCLI_TODAY", "2026-01-01"), ("NO_COLOR", "1")];
+
+    let run_ok = |extra: &[&str]| {
+        let mut args: Vec<&str> = vec!["--db", db.as_str(), "--no-color"];
+        args.extend_from_slice(extra);
+        let out = run_habit(&args, &shared_env);
+        assert_eq!(out.status.code(), Some(0), "stderr: {}", stderr_str(&out));
+    };
+
+    run_ok(&["add", "Water", "--target", "2", "--needs-declaration", "true"]);
+    for (date, text) in [("2026-01-02", "early"), ("2026-01-04", "in range")] {
+        let ts = format!("{}T07:00:00Z", date);
+        run_ok(&["declare", "Water", "--date", date, "--ts", ts.as_str(), "--text", text]);
+    }
+    run_ok(
+        &["excuse", "Water", "--date", "2026-01-05", "--ts", "2026-01-05T22:00:00Z", "--reason", "sick, really"],
+    );
+    run_ok(
+        &[
+            "penalty", "arm", "Water", "--multiplier", "2", "--cap", "8", "--deadline-days", "1",
+            "--date", "2026-01-01", "--ts", "2026-01-01T09:00:00Z",
+        ],
+    );
+    run_ok(&["penalty", "tick", "--date", "2026-01-04", "--ts", "2026-01-04T23:50:00Z"]);
+    run_ok(
+        &[
+            "penalty", "resolve", "pd_h0001_20260104", "--date", "2026-01-05", "--ts",
+            "2026-01-05T10:00:00Z", "--reason", "paid",
+        ],
+    );
+    run_ok(&["routine", "add", "Morning"]);
+    run_ok(&["routine", "step-add", "Morning", "--name", "Stretch", "--minutes", "5"]);
+    run_ok(&["routine", "step-add", "Morning", "--name", "Read", "--minutes", "10"]);
+    run_ok(&["routine", "start", "Morning", "--date", "2026-01-04", "--ts", "2026-01-04T07:30:00Z"]);
+    run_ok(&["routine", "next", "rs:r0001:2026-01-04:1", "--ts", "2026-01-04T07:35:00Z"]);
+
+    run_ok(
+        &[
+            "--today", today, "export", "--format", "csv", "--out", out_dir_s.as_str(), "--from",
+            "2026-01-03", "--to", "2026-01-05",
+        ],
+    );
+
+    let read = |name: &str| -> Vec<String> {
+        std::fs::read_to_string(out_dir.join(name))
+            .unwrap()
+            .lines()
+            .map(|l| l.to_string())
+            .collect()
+    };
+
+    let habits = read("habits.csv");
+    assert_eq!(
+        habits[0],
+        "id,name,schedule,period,target,notes,archived,created_date,archived_date,needs_declaration,excuse_quota_per_week,kind,excuse_policy"
+    );
+    assert!(habits[1].ends_with(",true,2,goal,freeze"));
+
+    // the 2026-01-02 declaration is outside --from/--to
+    let declarations = read("declarations.csv");
+    assert_eq!(declarations[0], "id,habit_id,date,ts,text");
+    assert_eq!(declarations.len(), 2);
+    assert!(declarations[1].contains("in range"));
+
+    let excuses = read("excuses.csv");
+    assert_eq!(excuses[1], "e000001,h0001,2026-01-05,2026-01-05T22:00:00Z,allowed,\"sick, really\"");
+
+    assert_eq!(read("penalty_rules.csv").len(), 2);
+    assert_eq!(read("penalty_debts.csv")[1].split(',').next(), Some("pd_h0001_20260104"));
+    assert_eq!(read("penalty_actions.csv")[0], "id,debt_id,kind,date,ts,reason,quantity");
+    assert!(read("penalty_actions.csv")[1].contains(",resolve,"));
+
+    assert_eq!(read("routines.csv")[0], "id,name,at,archived,created_date,archived_date");
+    assert_eq!(
+        read("routine_steps.csv"),
+        vec![
+            "routine_id,index,name,minutes,quote".to_string(),
+            "r0001,1,Stretch,5,".to_string(),
+            "r0001,2,Read,10,".to_string(),
+        ]
+    );
+    assert_eq!(read("routine_sessions.csv")[1], "rs:r0001:2026-01-04:1,r0001,Morning,2026-01-04,2026-01-04T07:30:00Z,active");
+    let actions = read("routine_session_actions.csv");
+    assert_eq!(actions[0], "session_id,id,kind,ts,step_index,reason");
+    assert!(actions[1].starts_with("rs:r0001:2026-01-04:1,ra_rs_r0001_2026-01-04_1_next_"));
+    assert_eq!(read("checkin_events.csv")[0], "id,habit_id,date,ts,kind,delta,note,source");
+}
+
+#[test]
+fn loop_and_csv_imports_backfill_history() {
+    let tmp = tempfile::tempdir().unwrap();
+    let db = tmp.path().join("db.json").to_string_lossy().to_string();
+    let loop_dir = tmp.path().join("loop");
+    std::fs::create_dir(&loop_dir).unwrap();
+    std::fs::write(
+        loop_dir.join("Habits.csv"),
+        "Position,Name,Type,Question,Description,FrequencyNumerator,FrequencyDenominator,Color,Unit,Target Type,Target Value,Archived?\n\
+         001,Meditate,0,Did you meditate?,,1,1,#FF0000,,0,0,false\n\
+         002,Gym,0,,,3,7,#00FF00,,0,0,false\n",
+    )
+    .unwrap();
+    std::fs::write(
+        loop_dir.join("Checkmarks.csv"),
+        "Date,Meditate,Gym\n2026-01-04,2,0\n2026-01-03,1,2\n2026-01-02,2,2\n",
+    )
+    .unwrap();
+    let csv = tmp.path().join("other.csv");
+    std::fs::write(&csv, "Day,Task,Amount,Tag\n2026-01-03,Water,2,x\n2026-01-04,Meditate,1,y\n").unwrap();
+    let loop_path = loop_dir.to_string_lossy().to_string();
+    let csv_path = csv.to_string_lossy().to_string();
+
+    let today = "2026-01-05";
+    let shared_env = [("HABITCLI_TODAY", today), ("NO_COLOR", "1")];
+    let run_ok = |extra: &[&str]| -> String {
+        let mut args: Vec<&str> = vec!["--db", db.as_str(), "--today", today, "--no-color"];
+        args.extend_from_slice(extra);
+        let out = run_habit(&args, &shared_env);
+        assert_eq!(out.status.code(), Some(0), "stderr: {}", stderr_str(&out));
+        stdout_str(&out)
+    };
+
+    let out = run_ok(&["import", "--from", "loop", loop_path.as_str(), "--format", "json"]);
+    let report: serde_json::Value = serde_json::from_str(out.trim()).unwrap();
+    assert_eq!(report["source"], "loop");
+    assert_eq!(report["collections"]["habits"]["added"], 2);
+    assert_eq!(report["collections"]["checkin_events"]["added"], 4);
+    assert_eq!(report["unmapped"], serde_json::json!(["Habits.csv: Color"]));
+
+    let out = run_ok(&["list", "--format", "json"]);
+    let list: serde_json::Value = serde_json::from_str(out.trim()).unwrap();
+    let gym = &list["habits"][0];
+    assert_eq!(gym["name"], "Gym");
+    assert_eq!(gym["target"]["period"], "week");
+    assert_eq!(gym["target"]["quantity"], 3);
+    assert_eq!(gym["created_date"], "2026-01-02");
+
+    // history counts from the first imported day
+    let out = run_ok(&["stats", "Meditate", "--from", "2026-01-02", "--to", "2026-01-04", "--format", "json"]);
+    let stats: serde_json::Value = serde_json::from_str(out.trim()).unwrap();
+    assert_eq!(stats["stats"][0]["success_rate"]["successes"], 2);
+
+    // generic CSV: Meditate is matched, Water is new, Tag is reported
+    let out = run_ok(&[
+        "import", "--from", "csv", csv_path.as_str(), "--map", "date=Day", "--map", "habit=Task",
+        "--map", "quantity=Amount",
+    ]);
+    assert!(out.contains("Imported"));
+    assert!(out.contains("unmapped: Tag"));
+    assert!(out.contains("habit Meditate -> h0001 (Meditate, matched)"));
+
+    let out = run_habit(
+        &["--db", db.as_str(), "import", "--from", "csv", csv_path.as_str()],
+        &shared_env,
+    );
+    assert_eq!(out.status.code(), Some(2));
+    assert!(stderr_str(&out).contains("Missing column for habit"));
+}
+
+#[test]
+fn ics_export_is_deterministic_and_marks_completed_days() {
+    let tmp = tempfile::tempdir().unwrap();
+    let db = tmp.path().join("db.json").to_string_lossy().to_string();
+    let file = tmp.path().join("habits.ics");
+    let file_s = file.to_string_lossy().to_string();
+
+    let today = "2026-01-05";
+    let shared_env = [("HABITCLI_TODAY", today), ("NO_COLOR", "1")];
+    let run_ok = |extra: &[&str]| -> String {
+        let mut args: Vec<&str> = vec!["--db", db.as_str(), "--today", today, "--no-color"];
+        args.extend_from_slice(extra);
+        let out = run_habit(&args, &shared_env);
+        assert_eq!(out.status.code(), Some(0), "stderr: {}", stderr_str(&out));
+        stdout_str(&out)
+    };
+
+    run_ok(&["add", "Gym", "--schedule", "tue,thu", "--needs-declaration", "false"]);
+    run_ok(&["add", "Water", "--target", "2", "--needs-declaration", "false"]);
+    run_ok(&["checkin", "Gym", "--date", "2026-01-06", "--ts", "2026-01-06T19:30:00+01:00"]);
+    run_ok(&["checkin", "Water", "--date", "2026-01-06", "--ts", "2026-01-06T08:00:00Z"]);
+    run_ok(&["routine", "add", "Morning", "--at", "07:30"]);
+    run_ok(&["routine", "step-add", "Morning", "--name", "Stretch", "--minutes", "10"]);
+
+    let ics = run_ok(&["export", "--format", "ics"]);
+    assert_eq!(ics, run_ok(&["export", "--format", "ics"]));
+    run_ok(&["export", "--format", "ics", "--out", file_s.as_str()]);
+    assert_eq!(std::fs::read_to_string(&file).unwrap(), ics);
+
+    assert!(ics.starts_with("BEGIN:VCALENDAR\r\nVERSION:2.0\r\n"));
+    assert!(ics.ends_with("END:VCALENDAR\r\n"));
+    for line in [
+        "UID:h0001@habit-cli",
+        "DTSTART;VALUE=DATE:20260106",
+        "RRULE:FREQ=WEEKLY;BYDAY=TU,TH",
+        "UID:h0002@habit-cli",
+        "RRULE:FREQ=DAILY",
+        "UID:r0001@habit-cli",
+        "DTSTART:20260105T073000",
+        "DURATION:PT10M",
+    ] {
+        assert!(ics.contains(&format!("{}\r\n", line)), "missing {}", line);
+    }
+
+    // Gym met its target on 2026-01-06; Water only has 1 of 2
+    let todo = |uid: &str| -> String {
+        let start = ics.find(&format!("UID:{}", uid)).unwrap();
+        let end = start + ics[start..].find("END:VTODO").unwrap();
+        ics[start..end].to_string()
+    };
+    let gym = todo("h0001-20260106@habit-cli");
+    assert!(gym.contains("STATUS:COMPLETED\r\nCOMPLETED:20260106T183000Z"));
+    assert!(todo("h0002-20260106@habit-cli").contains("STATUS:IN-PROCESS"));
+
+    let out = run_habit(&["--db", db.as_str(), "list", "--format", "ics"], &shared_env);
+    assert_eq!(out.status.code(), Some(2));
+}
+
+#[test]
+fn report_renders_markdown_and_html() {
+    let tmp = tempfile::tempdir().unwrap();
+    let db = tmp.path().join("db.json").to_string_lossy().to_string();
+    let file = tmp.path().join("report.html");
+    let file_s = file.to_string_lossy().to_string();
+
+    let today = "2026-01-07";
+
+    run_at(&db, "2026-01-01", &["add", "Water", "--target", "2", "--needs-declaration", "false"]);
+    run_at(
+        &db,
+        "2026-01-01",
+        &[
+            "penalty", "arm", "Water", "--multiplier", "1", "--cap", "4", "--deadline-days", "1",
+            "--date", "2026-01-01", "--ts", "2026-01-01T09:00:00Z",
+        ],
+    );
+    for (date, qty) in [("2026-01-02", "2"), ("2026-01-03", "2"), ("2026-01-05", "1")] {
+        run_at(&db, today, &["checkin", "Water", "--date", date, "--qty", qty]);
+    }
+    run_at(
+        &db,
+        today,
+        &["excuse", "Water", "--date", "2026-01-04", "--ts", "2026-01-04T20:00:00Z", "--reason", "sick | tired"],
+    );
+    run_at(&db, today, &["penalty", "tick", "--date", "2026-01-05", "--ts", "2026-01-05T23:50:00Z"]);
+
+    let md = run_at(&db, today, &["report", "--format", "md", "--from", "2026-01-01", "--to", "2026-01-07"]);
+    assert!(md.starts_with("# Habit report: 2026-01-01 to 2026-01-07\n"));
+    assert!(md.contains("| Water | 2/day | 33% | `███░░░░░░░` | 2/6 | 0 | 2 |"));
+    assert!(md.contains("| 2026-W01 |  |  |  | ░ | █ | █ | E |"));
+    assert!(md.contains("| 2026-W02 | ▒ | ░ | ░ |  |  |  |  |"));
+    assert!(md.contains("- 2026-01-04 allowed: sick \\| tired"));
+    assert!(md.contains("| pd_h0001_20260105 | Water | 2026-01-05 | 2026-01-06 | 2 | open |"));
+
+    // deterministic, and the default range is the last 7 days
+    assert_eq!(md, run_at(&db, today, &["report", "--format", "md"]));
+
+    run_at(&db, today, &["report", "--format", "html", "--out", file_s.as_str()]);
+    let html = std::fs::read_to_string(&file).unwrap();
+    assert!(html.starts_with("<!DOCTYPE html>"));
+    assert!(html.contains("<td class=\"excused\">E</td>"));
+    assert!(html.contains("sick | tired"));
+    assert!(html.trim_end().ends_with("</html>"));
+
+    let out = run_at_output(&db, today, &["report"]);
+    assert_eq!(out.status.code(), Some(2));
+}
+
+#[test]
+fn calendar_heatmap_and_json_day_states() {
+    let tmp = tempfile::tempdir().unwrap();
+    let db = tmp.path().join("db.json").to_string_lossy().to_string();
+
+    let today = "2026-01-10";
+
+    run_at(&db, "2026-01-05", &["add", "Read", "--schedule", "weekdays", "--needs-declaration", "true"]);
+    for date in ["2026-01-05", "2026-01-07"] {
+        run_at(&db, today, &["declare", "Read", "--date", date, "--ts", "2026-01-05T07:00:00Z", "--text", "yes"]);
+    }
+    for date in ["2026-01-05", "2026-01-06"] {
+        run_at(&db, today, &["checkin", "Read", "--date", date]);
+    }
+    run_at(&db, today, &["excuse", "Read", "--date", "2026-01-08", "--ts", "2026-01-08T20:00:00Z", "--reason", "travel"]);
+
+    let text = run_at(&db, today, &["calendar", "Read", "--month", "2026-01"]);
+    let lines: Vec<&str> = text.lines().collect();
+    assert_eq!(lines[0], "Read (h0001) 2026-01-01 to 2026-01-31");
+    assert_eq!(lines[1], "    Jan");
+    assert_eq!(lines[2], "Mon  █");
+    assert_eq!(lines[3], "Tue  ?");
+    assert_eq!(lines[4], "Wed  ░");
+    assert_eq!(lines[5], "Thu  E");
+    assert_eq!(lines[7], "Sat  ·");
+    assert!(text.contains("█ met 1  ▒ partial 0  ░ missed 2  E excused 1  ? undeclared 1  · not scheduled 1"));
+
+    let json: serde_json::Value =
+        serde_json::from_str(&run_at(&db, today, &["--format", "json", "calendar", "h0001", "--month", "2026-01"])).unwrap();
+    assert_eq!(json["days"].as_array().unwrap().len(), 31);
+    assert_eq!(json["days"][4]["state"], "met");
+    assert_eq!(json["days"][5]["state"], "undeclared");
+    assert_eq!(json["days"][5]["quantity"], 1);
+    assert_eq!(json["days"][20]["state"], "outside");
+    assert_eq!(json["counts"]["excused"], 1);
+
+    let out = run_at_output(&db, today, &["calendar", "Read", "--year", "2026", "--month", "2026-01"]);
+    assert_eq!(out.status.code(), Some(2));
+}
+
+#[test]
+fn strength_survives_a_miss_in_stats_and_recap() {
+    let tmp = tempfile::tempdir().unwrap();
+    let db = tmp.path().join("db.json").to_string_lossy().to_string();
+
+    let today = "2026-01-21";
+
+    run_at(&db, "2026-01-05", &["add", "Read", "--needs-declaration", "false"]);
+    run_at(&db, "2026-01-05", &["add", "Gym", "--period", "month", "--target", "4", "--needs-declaration", "false"]);
+    for day in 5..=20 {
+        if day != 19 {
+            let date = format!("2026-01-{:02}", day);
+            run_at(&db, today, &["checkin", "Read", "--date", date.as_str()]);
+        }
+    }
+
+    let json: serde_json::Value = serde_json::from_str(&run_at(
+        &db,
+        today,
+        &["stats", "--from", "2026-01-05", "--to", "2026-01-18", "--format", "json"],
+    ))
+    .unwrap();
+    let read = &json["stats"][1];
+    assert_eq!(read["name"], "Read");
+    assert!(json["stats"][0]["strength"].is_null());
+    let history = read["strength"]["history"].as_array().unwrap();
+    assert_eq!(history.len(), 2);
+    assert_eq!(history[1]["date"], "2026-01-18");
+    assert_eq!(history[1]["score"], read["strength"]["score"]);
+    let before_miss = read["strength"]["score"].as_f64().unwrap();
+    assert!(history[0]["score"].as_f64().unwrap() < before_miss);
+
+    // the miss on the 19th resets the streak but only dents strength;
+    // today (unmet so far) is still in progress
+    let json: serde_json::Value = serde_json::from_str(&run_at(
+        &db,
+        today,
+        &["stats", "Read", "--from", "2026-01-05", "--to", today, "--format", "json"],
+    ))
+    .unwrap();
+    assert_eq!(json["stats"][0]["current_streak"], 0);
+    let after_miss = json["stats"][0]["strength"]["score"].as_f64().unwrap();
+    assert!(after_miss > before_miss * 0.9 && after_miss < before_miss + 0.1);
+
+    let recap: serde_json::Value =
+        serde_json::from_str(&run_at(&db, today, &["recap", "--range", "week", "--format", "json"])).unwrap();
+    let read = recap["recap"].as_array().unwrap().iter().find(|r| r["name"] == "Read").unwrap();
+    assert_eq!(read["strength"]["score"].as_f64().unwrap(), after_miss);
+    assert!(read["strength"].get("history").is_none());
+
+    let table = run_at(&db, today, &["recap", "--range", "week"]);
+    assert!(table.contains("strength"));
+    assert!(table.contains("n/a"));
+}
+
+#[test]
+fn allowed_excuses_freeze_streaks_per_habit_policy() {
+    let tmp = tempfile::tempdir().unwrap();
+    let db = tmp.path().join("db.json").to_string_lossy().to_string();
+
+    let today = "2026-01-07";
+
+    run_at(&db, "2026-01-05", &["add", "Run", "--needs-declaration", "false"]);
+    for date in ["2026-01-05", "2026-01-07"] {
+        run_at(&db, today, &["checkin", "Run", "--date", date]);
+    }
+    run_at(&db, today, &["excuse", "Run", "--date", "2026-01-06", "--ts", "2026-01-06T20:00:00Z", "--reason", "sick"]);
+
+    let stats = |extra: &[&str]| -> serde_json::Value {
+        let mut args = vec!["stats", "Run", "--from", "2026-01-05", "--to", today, "--format", "json"];
+        args.extend_from_slice(extra);
+        let v: serde_json::Value = serde_json::from_str(&run_at(&db, today, &args)).unwrap();
+        v["stats"][0].clone()
+    };
+
+    // freeze (default): the excused day neither breaks nor extends the streak
+    let row = stats(&[]);
+    assert_eq!(row["current_streak"], 2);
+    assert_eq!(row["excused_days"], 1);
+    assert_eq!(row["success_rate"]["eligible"], 2);
+    let status: serde_json::Value =
+        serde_json::from_str(&run_at(&db, today, &["status", "--format", "json"])).unwrap();
+    assert_eq!(status["week"]["habits"][0]["scheduled_days"], 6);
+    assert_eq!(status["week"]["habits"][0]["excused_days"], 1);
+    assert!(run_at(&db, today, &["status"]).contains("- Run 2/6 scheduled days done (1 excused)"));
+    assert!(run_at(&db, today, &["stats", "Run", "--from", "2026-01-05"]).contains("100% (2/2, 1 excused)"));
+
+    run_at(&db, today, &["edit", "Run", "--excuse-policy", "count"]);
+    assert!(run_at(&db, today, &["show", "Run"]).contains("excuse_policy: count"));
+    let row = stats(&[]);
+    assert_eq!(row["current_streak"], 3);
+    assert_eq!(row["success_rate"]["successes"], 3);
+    let recap: serde_json::Value =
+        serde_json::from_str(&run_at(&db, today, &["recap", "--range", "week", "--format", "json"])).unwrap();
+    assert_eq!(recap["recap"][0]["excused_days"], 1);
+    assert_eq!(recap["recap"][0]["percent"], 100);
+
+    run_at(&db, today, &["edit", "Run", "--excuse-policy", "ignore"]);
+    let row = stats(&[]);
+    assert_eq!(row["current_streak"], 1);
+    assert_eq!(row["excused_days"], 0);
+    assert_eq!(row["success_rate"]["eligible"], 3);
+}
+
+#[test]
+fn weekly_habit_penalty_closes_at_iso_week_end() {
+    let tmp = tempfile::tempdir().unwrap();
+    let db = tmp.path().join("db.json").to_string_lossy().to_string();
+
+    let today = "2026-01-25";
+    let tick = |date: &str| -> serde_json::Value {
+        let ts = format!("{}T23:50:00Z", date);
+        serde_json::from_str(&run_at(&db, today, &["penalty", "tick", "--date", date, "--ts", ts.as_str(), "--format", "json"]))
+            .unwrap()
+    };
+
+    run_at(&db, "2026-01-05", &["add", "Run", "--period", "week", "--target", "3", "--needs-declaration", "false"]);
+    run_at(
+        &db,
+        today,
+        &[
+            "penalty", "arm", "Run", "--multiplier", "2", "--cap", "5", "--date", "2026-01-05", "--ts",
+            "2026-01-05T09:00:00Z",
+        ],
+    );
+    for date in ["2026-01-06", "2026-01-08"] {
+        run_at(&db, today, &["checkin", "Run", "--date", date]);
+    }
+
+    // mid-week days never evaluate weekly habits
+    assert_eq!(tick("2026-01-10")["created"].as_array().unwrap().len(), 0);
+
+    // 2/3 runs: shortfall 1 x 2, due next Monday, keyed by ISO week
+    let out = tick("2026-01-11");
+    let debt = &out["created"][0];
+    assert_eq!(debt["id"], "pd_h0001_2026W02");
+    assert_eq!(debt["quantity"], 2);
+    assert_eq!(debt["trigger_date"], "2026-01-11");
+    assert_eq!(debt["due_date"], "2026-01-12");
+    assert_eq!(tick("2026-01-11")["created"].as_array().unwrap().len(), 0);
+
+    // the next week meets its target, but the open debt escalates; the cap holds
+    for date in ["2026-01-12", "2026-01-13", "2026-01-14"] {
+        run_at(&db, today, &["checkin", "Run", "--date", date]);
+    }
+    let out = tick("2026-01-18");
+    assert_eq!(out["created"][0]["id"], "pd_h0001_2026W03");
+    assert_eq!(out["created"][0]["quantity"], 4);
+    run_at(
+        &db,
+        today,
+        &["penalty", "resolve", "pd_h0001_2026W03", "--date", "2026-01-19", "--ts", "2026-01-19T10:00:00Z", "--reason", "paid"],
+    );
+    let out = tick("2026-01-25");
+    assert_eq!(out["created"][0]["id"], "pd_h0001_2026W04");
+    assert_eq!(out["created"][0]["quantity"], 5);
+}
+
+#[test]
+fn weekly_penalty_skips_the_week_the_rule_was_armed_in() {
+    let tmp = tempfile::tempdir().unwrap();
+    let db = tmp.path().join("db.json").to_string_lossy().to_string();
+
+    let today = "2026-01-18";
+    run_at(&db, "2026-01-05", &["add", "Run", "--period", "week", "--target", "3", "--needs-declaration", "false"]);
+    run_at(
+        &db,
+        today,
+        &[
+            "penalty", "arm", "Run", "--multiplier", "2", "--cap", "5", "--date", "2026-01-07", "--ts",
+            "2026-01-07T09:00:00Z",
+        ],
+    );
+
+    // armed on a Wednesday: W02 is not owed, the first full week is
+    let out: serde_json::Value = serde_json::from_str(&run_at(
+        &db,
+        today,
+        &[
+            "penalty", "tick", "--from", "2026-01-05", "--to", "2026-01-18", "--ts", "2026-01-18T23:50:00Z", "--format",
+            "json",
+        ],
+    ))
+    .unwrap();
+    let ids: Vec<&str> = out["days"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .flat_map(|d| d["created"].as_array().unwrap().iter())
+        .map(|c| c["id"].as_str().unwrap())
+        .collect();
+    assert_eq!(ids, vec!["pd_h0001_2026W03"]);
+}
+
+#[test]
+fn weekly_penalty_skips_weeks_with_a_paused_day() {
+    let tmp = tempfile::tempdir().unwrap();
+    let db = tmp.path().join("db.json").to_string_lossy().to_string();
+
+    let today = "2026-02-01";
+    let created = |from: &str, to: &str| -> Vec<String> {
+        let out: serde_json::Value = serde_json::from_str(&run_at(
+            &db,
+            today,
+            &["penalty", "tick", "--from", from, "--to", to, "--ts", "2026-02-01T23:50:00Z", "--format", "json"],
+        ))
+        .unwrap();
+        out["days"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .flat_map(|d| d["created"].as_array().unwrap().iter())
+            .map(|c| c["id"].as_str().unwrap().to_string())
+            .collect()
+    };
+
+    run_at(&db, "2026-01-05", &["add", "Run", "--period", "week", "--target", "3", "--needs-declaration", "false"]);
+    run_at(
+        &db,
+        today,
+        &[
+            "penalty", "arm", "Run", "--multiplier", "2", "--cap", "5", "--date", "2026-01-05", "--ts",
+            "2026-01-05T09:00:00Z",
+        ],
+    );
+
+    // paused Mon-Sat of W03: the Sunday that closes it is not paused, the week still isn't owed
+    run_at(
+        &db,
+        today,
+        &["penalty", "pause", "Run", "--from", "2026-01-12", "--to", "2026-01-17", "--ts", "2026-01-05T10:00:00Z"],
+    );
+    assert_eq!(created("2026-01-05", "2026-01-25"), vec!["pd_h0001_2026W02", "pd_h0001_2026W04"]);
+
+    // a Sunday-only pause skips its week too
+    run_at(
+        &db,
+        today,
+        &["penalty", "pause", "Run", "--from", "2026-02-01", "--to", "2026-02-01", "--ts", "2026-01-26T10:00:00Z"],
+    );
+    assert!(created("2026-01-26", "2026-02-01").is_empty());
+}
+
+#[test]
+fn penalty_deadlines_default_overdue_debts() {
+    let tmp = tempfile::tempdir().unwrap();
+    let db = tmp.path().join("db.json").to_string_lossy().to_string();
+
+    let today = "2026-03-10";
+    let tick = |date: &str| -> serde_json::Value {
+        let ts = format!("{}T23:50:00Z", date);
+        serde_json::from_str(&run_at(&db, today, &["penalty", "tick", "--date", date, "--ts", ts.as_str(), "--format", "json"]))
+            .unwrap()
+    };
+    let status = |date: &str| -> serde_json::Value {
+        serde_json::from_str(&run_at(&db, today, &["penalty", "status", "--date", date, "--format", "json"])).unwrap()
+    };
+
+    run_at(&db, "2026-03-01", &["add", "Read", "--needs-declaration", "false"]);
+    run_at(&db, "2026-03-01", &["add", "Floss", "--needs-declaration", "false"]);
+    run_at(
+        &db,
+        today,
+        &[
+            "penalty", "arm", "Read", "--multiplier", "2", "--cap", "8", "--deadline-days", "3", "--on-default",
+            "escalate", "--date", "2026-03-01", "--ts", "2026-03-01T09:00:00Z",
+        ],
+    );
+    run_at(
+        &db,
+        today,
+        &[
+            "penalty", "arm", "Floss", "--multiplier", "2", "--cap", "8", "--on-default", "freeze-excuses", "--date",
+            "2026-03-01", "--ts", "2026-03-01T09:00:00Z",
+        ],
+    );
+    for date in ["2026-03-03", "2026-03-04", "2026-03-05", "2026-03-06"] {
+        run_at(&db, today, &["checkin", "Read", "--date", date]);
+    }
+
+    let out = tick("2026-03-02");
+    assert_eq!(out["created"][0]["id"], "pd_h0001_20260302");
+    assert_eq!(out["created"][0]["deadline_date"], "2026-03-05");
+
+    let st = status("2026-03-03");
+    let read = st["debts"].as_array().unwrap().iter().find(|d| d["id"] == "pd_h0001_20260302").unwrap();
+    assert_eq!(read["days_remaining"], 2);
+    assert_eq!(read["status"], "open");
+
+    // Floss's one-day deadline passes first and freezes its excuses
+    tick("2026-03-03");
+    let out = tick("2026-03-04");
+    let defaulted = out["defaulted"].as_array().unwrap();
+    assert!(defaulted.iter().any(|a| a["debt_id"] == "pd_h0002_20260302" && a["kind"] == "default"));
+    let excuse: serde_json::Value = serde_json::from_str(&run_at(
+        &db,
+        today,
+        &[
+            "excuse", "Floss", "--date", "2026-03-04", "--ts", "2026-03-04T22:00:00Z", "--reason", "travel", "--format",
+            "json",
+        ],
+    ))
+    .unwrap();
+    assert_eq!(excuse["excuse"]["kind"], "denied");
+
+    // Read defaults the day after its deadline and escalates
+    tick("2026-03-05");
+    let out = tick("2026-03-06");
+    assert!(out["defaulted"].as_array().unwrap().iter().any(|a| a["debt_id"] == "pd_h0001_20260302"));
+    let escalated = out["created"].as_array().unwrap().iter().find(|d| d["id"] == "pd_h0001_20260302_x").unwrap();
+    assert_eq!(escalated["quantity"], 4);
+    assert_eq!(escalated["due_date"], "2026-03-07");
+    assert_eq!(tick("2026-03-06")["defaulted"].as_array().unwrap().len(), 0);
+
+    let st = status("2026-03-06");
+    let read = st["debts"].as_array().unwrap().iter().find(|d| d["id"] == "pd_h0001_20260302").unwrap();
+    assert_eq!(read["status"], "defaulted");
+    assert_eq!(read["days_overdue"], 1);
+    assert_eq!(read["days_remaining"], serde_json::Value::Null);
+}
+
+#[test]
+fn surplus_checkins_pay_down_penalty_debt() {
+    let tmp = tempfile::tempdir().unwrap();
+    let db = tmp.path().join("db.json").to_string_lossy().to_string();
+
+    let today = "2026-03-10";
+    let tick = |date: &str| -> serde_json::Value {
+        let ts = format!("{}T23:50:00Z", date);
+        serde_json::from_str(&run_at(&db, today, &["penalty", "tick", "--date", date, "--ts", ts.as_str(), "--format", "json"]))
+            .unwrap()
+    };
+
+    run_at(&db, "2026-03-01", &["add", "Read", "--needs-declaration", "false"]);
+    run_at(
+        &db,
+        today,
+        &[
+            "penalty", "arm", "Read", "--multiplier", "3", "--cap", "8", "--deadline-days", "3", "--date", "2026-03-01",
+            "--ts", "2026-03-01T09:00:00Z",
+        ],
+    );
+
+    // surplus on the trigger day itself doesn't count: payment starts at the due date
+    run_at(&db, today, &["checkin", "Read", "--date", "2026-03-01", "--qty", "3"]);
+    assert_eq!(tick("2026-03-02")["created"][0]["quantity"], 3);
+
+    // 2 above target: a partial payment, recorded once however often tick runs
+    run_at(&db, today, &["checkin", "Read", "--date", "2026-03-03", "--qty", "3"]);
+    let out = tick("2026-03-03");
+    let pay = &out["payments"][0];
+    assert_eq!(pay["id"], "pa_pd_h0001_20260302_pay_20260303");
+    assert_eq!(pay["kind"], "payment");
+    assert_eq!(pay["quantity"], 2);
+    assert_eq!(out["payments"].as_array().unwrap().len(), 1);
+    assert_eq!(tick("2026-03-03")["payments"].as_array().unwrap().len(), 0);
+
+    let status: serde_json::Value =
+        serde_json::from_str(&run_at(&db, today, &["penalty", "status", "--date", "2026-03-03", "--format", "json"])).unwrap();
+    assert_eq!(status["debts"][0]["paid"], 2);
+
+    // the rest is paid the next day and the debt resolves itself
+    run_at(&db, today, &["checkin", "Read", "--date", "2026-03-04", "--qty", "4"]);
+    let out = tick("2026-03-04");
+    let kinds: Vec<&str> = out["payments"].as_array().unwrap().iter().map(|a| a["kind"].as_str().unwrap()).collect();
+    assert_eq!(kinds, ["payment", "resolve"]);
+    assert_eq!(out["payments"][0]["quantity"], 1);
+    run_at(&db, today, &["checkin", "Read", "--date", "2026-03-05"]);
+    assert_eq!(tick("2026-03-05")["created"].as_array().unwrap().len(), 0);
+    assert_eq!(tick("2026-03-04")["payments"].as_array().unwrap().len(), 0);
+
+    let status = run_at(&db, today, &["penalty", "status", "--date", "2026-03-05"]);
+    assert!(status.contains("(no outstanding penalty debts)"), "{}", status);
+}
+
+#[test]
+fn penalty_tick_range_catches_up_in_order() {
+    let tmp = tempfile::tempdir().unwrap();
+    let db = tmp.path().join("db.json").to_string_lossy().to_string();
+    let tmp_single = tempfile::tempdir().unwrap();
+    let db_single = tmp_single.path().join("db.json").to_string_lossy().to_string();
+
+    let today = "2026-03-10";
+    for d in [db.as_str(), db_single.as_str()] {
+        run_at(d, "2026-03-01", &["add", "Read", "--needs-declaration", "false"]);
+        run_at(
+            d,
+            today,
+            &[
+                "penalty", "arm", "Read", "--multiplier", "2", "--cap", "8", "--date", "2026-03-01", "--ts",
+                "2026-03-01T09:00:00Z",
+            ],
+        );
+        run_at(d, today, &["checkin", "Read", "--date", "2026-03-02"]);
+    }
+
+    let out: serde_json::Value = serde_json::from_str(&run_at(
+        &db,
+        today,
+        &["penalty", "tick", "--from", "2026-03-02", "--to", "2026-03-04", "--ts", "2026-03-05T08:00:00Z", "--format", "json"],
+    ))
+    .unwrap();
+    let days = out["days"].as_array().unwrap();
+    assert_eq!(days.len(), 3);
+    assert_eq!(days[0]["date"], "2026-03-02");
+    assert_eq!(days[0]["created"].as_array().unwrap().len(), 0);
+    // day 3 escalates from day 2's open debt
+    assert_eq!(days[1]["created"][0]["quantity"], 2);
+    assert_eq!(days[2]["created"][0]["quantity"], 4);
+
+    // same result as one tick per day; re-running the range creates nothing
+    for date in ["2026-03-02", "2026-03-03", "2026-03-04"] {
+        run_at(&db_single, today, &["penalty", "tick", "--date", date, "--ts", "2026-03-05T08:00:00Z"]);
+    }
+    let debts = |d: &str| -> serde_json::Value {
+        let v: serde_json::Value =
+            serde_json::from_str(&run_at(d, today, &["penalty", "status", "--date", "2026-03-05", "--format", "json"])).unwrap();
+        v["debts"].clone()
+    };
+    assert_eq!(debts(&db), debts(&db_single));
+
+    let text = run_at(
+        &db,
+        today,
+        &["penalty", "tick", "--from", "2026-03-02", "--to", "2026-03-04", "--ts", "2026-03-05T08:00:00Z"],
+    );
+    assert!(text.contains("2026-03-03: created 0 debt(s)"), "{}", text);
+    assert!(text.contains("Created 0 debt(s) over 3 day(s)."), "{}", text);
+
+    let bad = run_at_output(
+        &db,
+        today,
+        &["penalty", "tick", "--from", "2026-03-04", "--to", "2026-03-02", "--ts", "2026-03-05T08:00:00Z"],
+    );
+    assert_eq!(bad.status.code(), Some(2));
+    let bad = run_at_output(
+        &db,
+        today,
+        &["penalty", "tick", "--date", "2026-03-04", "--from", "2026-03-02", "--ts", "2026-03-05T08:00:00Z"],
+    );
+    assert_eq!(bad.status.code(), Some(2));
+}
+
+#[test]
+fn penalty_rule_versions_pause_and_disarm() {
+    let tmp = tempfile::tempdir().unwrap();
+    let db = tmp.path().join("db.json").to_string_lossy().to_string();
+
+    let today = "2026-03-10";
+    let run_json = |extra: &[&str]| -> serde_json::Value {
+        let mut args: Vec<&str> = extra.to_vec();
+        args.extend_from_slice(&["--format", "json"]);
+        serde_json::from_str(&run_at(&db, today, &args)).unwrap()
+    };
+    let arm = |date: &str, multiplier: &str, cap: &str| -> serde_json::Value {
+        let ts = format!("{}T09:00:00Z", date);
+        run_json(&["penalty", "arm", "Read", "--multiplier", multiplier, "--cap", cap, "--date", date, "--ts", ts.as_str()])
+    };
+
+    for name in ["Read", "Floss"] {
+        run_at(&db, "2026-03-01", &["add", name, "--needs-declaration", "false"]);
+    }
+    assert_eq!(arm("2026-03-01", "2", "8")["rule"]["id"], "pr000001");
+    // a new version from 03-05; re-arming it unchanged adds nothing, on any date
+    assert_eq!(arm("2026-03-05", "3", "9")["rule"]["id"], "pr000002");
+    assert_eq!(arm("2026-03-05", "3", "9")["rule"]["id"], "pr000002");
+    let same = arm("2026-03-06", "3", "9");
+    assert_eq!(same["rule"]["id"], "pr000002");
+    assert_eq!(same["rule"]["armed_date"], "2026-03-05");
+
+    let out = run_json(&["penalty", "pause", "Read", "--from", "2026-03-06", "--to", "2026-03-07", "--ts", "2026-03-05T10:00:00Z"]);
+    assert_eq!(out["rule"]["pauses"][0]["from"], "2026-03-06");
+    let out = run_json(&["penalty", "disarm", "Read", "--date", "2026-03-09", "--ts", "2026-03-08T10:00:00Z"]);
+    assert_eq!(out["rule"]["id"], "pr000002");
+    assert_eq!(out["rule"]["disarmed_date"], "2026-03-09");
+
+    let out = run_json(&["penalty", "tick", "--from", "2026-03-02", "--to", "2026-03-10", "--ts", "2026-03-10T08:00:00Z"]);
+    let day = |i: usize| out["days"][i].clone();
+
+    // v1 (x2, cap 8) through 03-04, escalating day by day
+    assert_eq!(day(0)["created"][0]["quantity"], 2);
+    assert_eq!(day(0)["created"][0]["rule_id"], "pr000001");
+    assert_eq!(day(2)["created"][0]["quantity"], 8);
+    // v2 (x3, cap 9) from 03-05
+    assert_eq!(day(3)["created"][0]["quantity"], 9);
+    assert_eq!(day(3)["created"][0]["rule_id"], "pr000002");
+    // paused: no debt and no defaults; the deferred defaults land on 03-08
+    for i in [4, 5] {
+        assert_eq!(day(i)["created"].as_array().unwrap().len(), 0);
+        assert_eq!(day(i)["defaulted"].as_array().unwrap().len(), 0);
+    }
+    assert_eq!(day(6)["created"][0]["quantity"], 3);
+    assert_eq!(day(6)["defaulted"].as_array().unwrap().len(), 2);
+    // disarmed from 03-09
+    for i in [7, 8] {
+        assert_eq!(day(i)["created"].as_array().unwrap().len(), 0);
+    }
+
+    // disarming twice is a no-op; a never-armed habit is not found
+    let out = run_json(&["penalty", "disarm", "Read", "--date", "2026-03-10", "--ts", "2026-03-10T10:00:00Z"]);
+    assert_eq!(out["rule"]["disarmed_date"], "2026-03-09");
+    let out = run_at_output(&db, today, &["penalty", "disarm", "Floss", "--date", "2026-03-10", "--ts", "2026-03-10T10:00:00Z"]);
+    assert_eq!(out.status.code(), Some(3));
+}